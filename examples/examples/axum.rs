@@ -39,8 +39,8 @@ async fn main() {
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
     let _redis_backend = RedisBackend::new().unwrap();
-    // let memory_backend = hitbox_stretto::StrettoBackend::builder(10)
-    //     .finalize()
+    // let memory_backend = hitbox_stretto::StrettoBackend::builder(10, 10)
+    //     .build()
     //     .unwrap();
     let memory_backend = hitbox_moka::MokaBackend::builder(1024 * 1024).build();
 
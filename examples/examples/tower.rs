@@ -22,7 +22,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
     let inmemory = MokaBackend::builder(10_000).build();
-    // let inmemory = StrettoBackend::builder(10_000_000).finalize().unwrap();
+    // let inmemory = StrettoBackend::builder(10_000, 10_000_000).build().unwrap();
     // let redis = RedisBackend::builder().build().unwrap();
 
     let _service = tower::ServiceBuilder::new()
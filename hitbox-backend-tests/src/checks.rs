@@ -0,0 +1,530 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::future::join_all;
+use hitbox_backend::{Backend, CacheBackend, CacheKeyFormat, DeleteStatus};
+use hitbox_core::{
+    CacheKey, CachePolicy, CacheValue, CacheableResponse, EntityPolicyConfig, KeyPart, Predicate,
+    ResponseCachePolicy,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{clock::Clock, suite::Conformance};
+
+/// Return the formatted failure unless the condition holds.
+macro_rules! ensure {
+    ($condition:expr, $($message:tt)+) => {
+        if !$condition {
+            return Err(format!($($message)+));
+        }
+    };
+}
+
+/// Single check of the conformance suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// Reading a key never written is a miss.
+    ReadMissing,
+    /// Written data is read back unchanged.
+    WriteRead,
+    /// The last write of a key wins.
+    Overwrite,
+    /// Removing a present key reports it deleted, removing a missing one reports it missing.
+    Remove,
+    /// Empty data is stored and is not a miss.
+    EmptyValue,
+    /// Data with every byte value is read back unchanged.
+    BinaryValue,
+    /// Large data is read back unchanged.
+    LargeValue,
+    /// Expire and stale timestamps survive a round trip with millisecond precision.
+    Metadata,
+    /// Values written with `CacheBackend::set` are read back with `CacheBackend::get`.
+    TypedRoundTrip,
+    /// Keys with unusual parts (unicode, separators, empty and missing values,
+    /// many or long parts) are stored without collisions.
+    KeyVariants,
+    /// Keys differing only in prefix or version don't collide, for key formats
+    /// which keep them.
+    PrefixAndVersion,
+    /// Simultaneous writers of distinct keys and of the same key don't lose or
+    /// tear values.
+    ConcurrentWriters,
+    /// An entry written with ttl is readable until the ttl passes.
+    TtlHonoured,
+    /// Without ttl an entry is readable until its value expires.
+    ExpireAsTtl,
+    /// A value written already expired without ttl is not stored.
+    ExpiredNotWritten,
+    /// Without ttl and expiration an entry doesn't expire.
+    NoTtlPersists,
+}
+
+impl Check {
+    /// Every check, in the order the suite runs them.
+    pub const ALL: &[Check] = &[
+        Check::ReadMissing,
+        Check::WriteRead,
+        Check::Overwrite,
+        Check::Remove,
+        Check::EmptyValue,
+        Check::BinaryValue,
+        Check::LargeValue,
+        Check::Metadata,
+        Check::TypedRoundTrip,
+        Check::KeyVariants,
+        Check::PrefixAndVersion,
+        Check::ConcurrentWriters,
+        Check::TtlHonoured,
+        Check::ExpireAsTtl,
+        Check::ExpiredNotWritten,
+        Check::NoTtlPersists,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::ReadMissing => "read_missing",
+            Check::WriteRead => "write_read",
+            Check::Overwrite => "overwrite",
+            Check::Remove => "remove",
+            Check::EmptyValue => "empty_value",
+            Check::BinaryValue => "binary_value",
+            Check::LargeValue => "large_value",
+            Check::Metadata => "metadata",
+            Check::TypedRoundTrip => "typed_round_trip",
+            Check::KeyVariants => "key_variants",
+            Check::PrefixAndVersion => "prefix_and_version",
+            Check::ConcurrentWriters => "concurrent_writers",
+            Check::TtlHonoured => "ttl_honoured",
+            Check::ExpireAsTtl => "expire_as_ttl",
+            Check::ExpiredNotWritten => "expired_not_written",
+            Check::NoTtlPersists => "no_ttl_persists",
+        }
+    }
+
+    pub(crate) async fn run<B, C>(self, suite: &Conformance<'_, B, C>) -> Result<(), String>
+    where
+        B: Backend,
+        C: Clock,
+    {
+        match self {
+            Check::ReadMissing => read_missing(suite).await,
+            Check::WriteRead => write_read(suite).await,
+            Check::Overwrite => overwrite(suite).await,
+            Check::Remove => remove(suite).await,
+            Check::EmptyValue => round_trip(suite, self, Vec::new()).await,
+            Check::BinaryValue => round_trip(suite, self, (0..=255).collect()).await,
+            Check::LargeValue => {
+                let data = (0..suite.large_value_size).map(|i| i as u8).collect();
+                round_trip(suite, self, data).await
+            }
+            Check::Metadata => metadata(suite).await,
+            Check::TypedRoundTrip => typed_round_trip(suite).await,
+            Check::KeyVariants => key_variants(suite).await,
+            Check::PrefixAndVersion => prefix_and_version(suite).await,
+            Check::ConcurrentWriters => concurrent_writers(suite).await,
+            Check::TtlHonoured => ttl_honoured(suite).await,
+            Check::ExpireAsTtl => expire_as_ttl(suite).await,
+            Check::ExpiredNotWritten => expired_not_written(suite).await,
+            Check::NoTtlPersists => no_ttl_persists(suite).await,
+        }
+    }
+}
+
+/// Ttl long enough not to interfere with checks not about expiry.
+const LONG_TTL: Duration = Duration::from_secs(3600);
+
+fn value(data: Vec<u8>) -> CacheValue<Vec<u8>> {
+    CacheValue::new(data, None, None)
+}
+
+fn millis(timestamp: Option<DateTime<Utc>>) -> Option<i64> {
+    timestamp.map(|timestamp| timestamp.timestamp_millis())
+}
+
+fn after<B, C>(suite: &Conformance<'_, B, C>, duration: Duration) -> DateTime<Utc>
+where
+    C: Clock,
+{
+    suite.clock.now() + TimeDelta::from_std(duration).expect("duration out of range")
+}
+
+async fn read_missing<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let data = suite
+        .read_data(&suite.key(Check::ReadMissing, "missing"))
+        .await?;
+    ensure!(data.is_none(), "expected a miss, read {data:?}");
+    Ok(())
+}
+
+async fn write_read<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let key = suite.key(Check::WriteRead, "key");
+    suite
+        .write(&key, value(b"value".to_vec()), Some(LONG_TTL))
+        .await?;
+    let data = suite.read_data(&key).await?;
+    ensure!(
+        data.as_deref() == Some(b"value".as_slice()),
+        "expected written data, read {data:?}"
+    );
+    Ok(())
+}
+
+async fn overwrite<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let key = suite.key(Check::Overwrite, "key");
+    suite
+        .write(&key, value(b"first".to_vec()), Some(LONG_TTL))
+        .await?;
+    suite
+        .write(&key, value(b"second".to_vec()), Some(LONG_TTL))
+        .await?;
+    let data = suite.read_data(&key).await?;
+    ensure!(
+        data.as_deref() == Some(b"second".as_slice()),
+        "expected the second write, read {data:?}"
+    );
+    Ok(())
+}
+
+async fn remove<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let key = suite.key(Check::Remove, "key");
+    suite
+        .write(&key, value(b"value".to_vec()), Some(LONG_TTL))
+        .await?;
+    let status = suite.remove(&key).await?;
+    ensure!(
+        matches!(status, DeleteStatus::Deleted(deleted) if deleted > 0),
+        "expected present key to be deleted, got {status:?}"
+    );
+    let data = suite.read_data(&key).await?;
+    ensure!(
+        data.is_none(),
+        "expected a miss after remove, read {data:?}"
+    );
+    let status = suite.remove(&key).await?;
+    ensure!(
+        status == DeleteStatus::Missing,
+        "expected removed key to be missing, got {status:?}"
+    );
+    Ok(())
+}
+
+async fn round_trip<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+    check: Check,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    let key = suite.key(check, "key");
+    suite
+        .write(&key, value(data.clone()), Some(LONG_TTL))
+        .await?;
+    match suite.read_data(&key).await? {
+        Some(read) if read == data => Ok(()),
+        Some(read) => Err(format!(
+            "data of {} bytes changed, read {} bytes",
+            data.len(),
+            read.len()
+        )),
+        None => Err("expected written data, got a miss".to_owned()),
+    }
+}
+
+async fn metadata<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let expire = after(suite, Duration::from_millis(3_600_123)) + TimeDelta::nanoseconds(456_789);
+    let stale = after(suite, Duration::from_millis(1_800_321));
+    for (name, expire, stale) in [
+        ("both", Some(expire), Some(stale)),
+        ("expire", Some(expire), None),
+        ("stale", None, Some(stale)),
+        ("none", None, None),
+    ] {
+        let key = suite.key(Check::Metadata, name);
+        suite
+            .write(
+                &key,
+                CacheValue::new(b"value".to_vec(), expire, stale),
+                Some(LONG_TTL),
+            )
+            .await?;
+        let read = suite
+            .read(&key)
+            .await?
+            .ok_or_else(|| format!("{name}: expected written value, got a miss"))?;
+        ensure!(
+            millis(read.expire) == millis(expire),
+            "{name}: expire {expire:?} read as {:?}",
+            read.expire
+        );
+        ensure!(
+            millis(read.stale) == millis(stale),
+            "{name}: stale {stale:?} read as {:?}",
+            read.stale
+        );
+    }
+    Ok(())
+}
+
+/// Response type of the typed round trip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Payload {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+    body: Vec<u8>,
+}
+
+#[async_trait]
+impl CacheableResponse for Payload {
+    type Cached = Self;
+    type Subject = Self;
+
+    async fn cache_policy<P>(
+        self,
+        _predicates: P,
+        _: &EntityPolicyConfig,
+    ) -> ResponseCachePolicy<Self>
+    where
+        P: Predicate<Subject = Self::Subject> + Send + Sync,
+    {
+        CachePolicy::Cacheable(CacheValue::new(self, None, None))
+    }
+
+    async fn into_cached(self) -> CachePolicy<Self::Cached, Self> {
+        CachePolicy::Cacheable(self)
+    }
+
+    async fn from_cached(cached: Self::Cached) -> Self {
+        cached
+    }
+}
+
+async fn typed_round_trip<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+) -> Result<(), String> {
+    let key = suite.key(Check::TypedRoundTrip, "key");
+    let payload = Payload {
+        id: u64::MAX,
+        name: "conformance ✓".to_owned(),
+        tags: vec!["a".to_owned(), String::new()],
+        body: (0..=255).collect(),
+    };
+    let expire = after(suite, LONG_TTL);
+    suite
+        .backend
+        .set::<Payload>(
+            &key,
+            &CacheValue::new(payload.clone(), Some(expire), None),
+            Some(LONG_TTL),
+        )
+        .await
+        .map_err(|err| format!("set failed: {err}"))?;
+    let read = suite
+        .backend
+        .get::<Payload>(&key)
+        .await
+        .map_err(|err| format!("get failed: {err}"))?
+        .ok_or("expected written value, got a miss")?;
+    ensure!(
+        read.data == payload,
+        "expected {payload:?}, got {:?}",
+        read.data
+    );
+    ensure!(
+        millis(read.expire) == millis(Some(expire)),
+        "expire {expire:?} read as {:?}",
+        read.expire
+    );
+    Ok(())
+}
+
+async fn key_variants<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let long = "k".repeat(1000);
+    let many: Vec<KeyPart> = (0..32)
+        .map(|i| KeyPart::new(format!("part{i}"), Some(i)))
+        .collect();
+    let part = |key: &str, value: Option<&str>| {
+        vec![
+            suite.namespace_part(),
+            KeyPart::new("check", Some(Check::KeyVariants.name())),
+            KeyPart::new(key, value),
+        ]
+    };
+    let keys = [
+        part("unicode", Some("ключ 🔑")),
+        part("separators", Some("a b&c=d/e?f%g#h\r\n")),
+        part("empty", Some("")),
+        part("missing", None),
+        part("long", Some(&long)),
+        part(&long, Some("long key")),
+        [part("many", None), many].concat(),
+    ];
+
+    for (index, parts) in keys.iter().enumerate() {
+        let key = CacheKey::new("conformance".to_owned(), 0, parts.clone());
+        suite
+            .write(&key, value(index.to_string().into_bytes()), Some(LONG_TTL))
+            .await?;
+    }
+    for (index, parts) in keys.into_iter().enumerate() {
+        let key = CacheKey::new("conformance".to_owned(), 0, parts);
+        let data = suite.read_data(&key).await?;
+        ensure!(
+            data == Some(index.to_string().into_bytes()),
+            "key {key:?} read {data:?}, expected its own value"
+        );
+    }
+    Ok(())
+}
+
+async fn prefix_and_version<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+) -> Result<(), String> {
+    // URL-encoded keys consist of key parts only.
+    if suite.backend.key_format() == &CacheKeyFormat::UrlEncoded {
+        return Ok(());
+    }
+    let parts = vec![
+        suite.namespace_part(),
+        KeyPart::new("check", Some(Check::PrefixAndVersion.name())),
+    ];
+    let keys = [
+        CacheKey::new("conformance".to_owned(), 0, parts.clone()),
+        CacheKey::new("conformance-other".to_owned(), 0, parts.clone()),
+        CacheKey::new("conformance".to_owned(), 1, parts),
+    ];
+    for (index, key) in keys.iter().enumerate() {
+        suite
+            .write(key, value(vec![index as u8]), Some(LONG_TTL))
+            .await?;
+    }
+    for (index, key) in keys.iter().enumerate() {
+        let data = suite.read_data(key).await?;
+        ensure!(
+            data == Some(vec![index as u8]),
+            "key {key:?} read {data:?}, expected its own value"
+        );
+    }
+    Ok(())
+}
+
+async fn concurrent_writers<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+) -> Result<(), String> {
+    let data = |writer: usize| format!("writer {writer} ").repeat(64).into_bytes();
+
+    let keys: Vec<CacheKey> = (0..suite.concurrency)
+        .map(|writer| suite.key(Check::ConcurrentWriters, &writer.to_string()))
+        .collect();
+    let writes = keys
+        .iter()
+        .enumerate()
+        .map(|(writer, key)| suite.write(key, value(data(writer)), Some(LONG_TTL)));
+    join_all(writes)
+        .await
+        .into_iter()
+        .collect::<Result<(), _>>()?;
+    for (writer, key) in keys.iter().enumerate() {
+        let read = suite.read_data(key).await?;
+        ensure!(
+            read == Some(data(writer)),
+            "value of writer {writer} was lost or changed"
+        );
+    }
+
+    let shared = suite.key(Check::ConcurrentWriters, "shared");
+    let writes = (0..suite.concurrency)
+        .map(|writer| suite.write(&shared, value(data(writer)), Some(LONG_TTL)));
+    join_all(writes)
+        .await
+        .into_iter()
+        .collect::<Result<(), _>>()?;
+    let read = suite
+        .read_data(&shared)
+        .await?
+        .ok_or("shared key is a miss after concurrent writes")?;
+    ensure!(
+        (0..suite.concurrency).any(|writer| read == data(writer)),
+        "shared key holds data of no writer"
+    );
+    Ok(())
+}
+
+async fn ttl_honoured<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let key = suite.key(Check::TtlHonoured, "key");
+    suite
+        .write(&key, value(b"value".to_vec()), Some(suite.ttl))
+        .await?;
+    suite.clock.advance(suite.ttl / 2).await;
+    ensure!(
+        suite.read(&key).await?.is_some(),
+        "entry expired before half of its ttl"
+    );
+    suite
+        .clock
+        .advance(suite.ttl - suite.ttl / 2 + suite.ttl_granularity)
+        .await;
+    ensure!(
+        suite.read(&key).await?.is_none(),
+        "entry is readable after its ttl"
+    );
+    Ok(())
+}
+
+async fn expire_as_ttl<B: Backend, C: Clock>(suite: &Conformance<'_, B, C>) -> Result<(), String> {
+    let key = suite.key(Check::ExpireAsTtl, "key");
+    let expire = after(suite, suite.ttl);
+    suite
+        .write(
+            &key,
+            CacheValue::new(b"value".to_vec(), Some(expire), None),
+            None,
+        )
+        .await?;
+    suite.clock.advance(suite.ttl / 2).await;
+    ensure!(
+        suite.read(&key).await?.is_some(),
+        "entry expired before its value"
+    );
+    suite
+        .clock
+        .advance(suite.ttl - suite.ttl / 2 + suite.ttl_granularity)
+        .await;
+    ensure!(
+        suite.read(&key).await?.is_none(),
+        "entry is readable after its value expired"
+    );
+    Ok(())
+}
+
+async fn expired_not_written<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+) -> Result<(), String> {
+    let key = suite.key(Check::ExpiredNotWritten, "key");
+    let expire = suite.clock.now() - TimeDelta::seconds(1);
+    suite
+        .write(
+            &key,
+            CacheValue::new(b"value".to_vec(), Some(expire), None),
+            None,
+        )
+        .await?;
+    ensure!(
+        suite.read(&key).await?.is_none(),
+        "value written already expired is readable"
+    );
+    Ok(())
+}
+
+async fn no_ttl_persists<B: Backend, C: Clock>(
+    suite: &Conformance<'_, B, C>,
+) -> Result<(), String> {
+    let key = suite.key(Check::NoTtlPersists, "key");
+    suite.write(&key, value(b"value".to_vec()), None).await?;
+    suite.clock.advance(suite.ttl + suite.ttl_granularity).await;
+    ensure!(
+        suite.read(&key).await?.is_some(),
+        "entry without ttl and expiration expired"
+    );
+    Ok(())
+}
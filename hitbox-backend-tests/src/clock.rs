@@ -0,0 +1,80 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use hitbox_core::TimeProvider;
+
+/// Source of time for the suite, able to move time forward.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Current time, as seen by the backend under test.
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Let `duration` pass for the backend under test.
+    async fn advance(&self, duration: Duration);
+}
+
+/// Real time, advancing sleeps.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn advance(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Time standing still until advanced, shared by clones.
+///
+/// Pass a clone to the backend as its [`TimeProvider`] to check expiry without
+/// waiting.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// Clock starting at the current system time.
+    pub fn new() -> Self {
+        Self::starting_at(Utc::now())
+    }
+
+    /// Clock starting at `now`.
+    pub fn starting_at(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeProvider for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        TimeProvider::now(self)
+    }
+
+    async fn advance(&self, duration: Duration) {
+        let duration = TimeDelta::from_std(duration).expect("duration out of range");
+        *self.now.lock().unwrap() += duration;
+    }
+}
@@ -0,0 +1,31 @@
+//! Conformance test suite for hitbox [Backend] implementations.
+//!
+//! The suite checks the behaviour every backend is expected to share: read,
+//! write and remove semantics, round-trip of values and their metadata, key
+//! handling, concurrent access and ttl rules. Call it from the tests of your
+//! backend:
+//!
+//! ```ignore
+//! use hitbox_backend_tests::{Conformance, SystemClock};
+//!
+//! #[tokio::test]
+//! async fn test_conformance() {
+//!     let backend = MyBackend::new();
+//!     Conformance::new(&backend, SystemClock).run().await;
+//! }
+//! ```
+//!
+//! Time-dependent checks move time forward with a [`Clock`]. Backends reading
+//! the system time are driven by [`SystemClock`], which sleeps; backends
+//! accepting a [`TimeProvider`] can share a [`ManualClock`] with the suite, so
+//! expiry is checked without waiting.
+//!
+//! [Backend]: hitbox_backend::Backend
+//! [`TimeProvider`]: hitbox_core::TimeProvider
+mod checks;
+mod clock;
+mod suite;
+
+pub use crate::checks::Check;
+pub use crate::clock::{Clock, ManualClock, SystemClock};
+pub use crate::suite::Conformance;
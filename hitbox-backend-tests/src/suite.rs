@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use hitbox_backend::{Backend, DeleteStatus, serializer::Raw};
+use hitbox_core::{CacheKey, CacheValue, KeyPart};
+
+use crate::{checks::Check, clock::Clock};
+
+/// Conformance suite run against a single backend.
+///
+/// Every check uses its own keys under a per-run namespace, so the suite can
+/// run against a backend holding other data. Checks which don't apply to the
+/// backend can be skipped.
+pub struct Conformance<'a, B, C> {
+    pub(crate) backend: &'a B,
+    pub(crate) clock: C,
+    pub(crate) ttl: Duration,
+    pub(crate) ttl_granularity: Duration,
+    pub(crate) large_value_size: usize,
+    pub(crate) concurrency: usize,
+    namespace: String,
+    skip: Vec<Check>,
+}
+
+impl<'a, B, C> Conformance<'a, B, C>
+where
+    B: Backend,
+    C: Clock,
+{
+    /// Suite for `backend`, moving its time with `clock`.
+    pub fn new(backend: &'a B, clock: C) -> Self {
+        let namespace = clock
+            .now()
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_string();
+        Self {
+            backend,
+            clock,
+            ttl: Duration::from_secs(1),
+            ttl_granularity: Duration::from_millis(100),
+            large_value_size: 1024 * 1024,
+            concurrency: 32,
+            namespace,
+            skip: Vec::new(),
+        }
+    }
+
+    /// Set ttl used by time-dependent checks, 1 second by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set how late an entry may expire, 100 milliseconds by default.
+    ///
+    /// Backends storing expiration with a coarse resolution, e.g. whole seconds,
+    /// need a larger granularity.
+    pub fn ttl_granularity(mut self, granularity: Duration) -> Self {
+        self.ttl_granularity = granularity;
+        self
+    }
+
+    /// Set size of the value written by [`Check::LargeValue`], 1 MiB by default.
+    pub fn large_value_size(mut self, size: usize) -> Self {
+        self.large_value_size = size;
+        self
+    }
+
+    /// Set number of simultaneous writers of [`Check::ConcurrentWriters`], 32 by default.
+    pub fn concurrency(mut self, writers: usize) -> Self {
+        self.concurrency = writers;
+        self
+    }
+
+    /// Set namespace of the keys used by the suite, unique per run by default.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Don't run `check`.
+    pub fn skip(mut self, check: Check) -> Self {
+        self.skip.push(check);
+        self
+    }
+
+    /// Run all checks which are not skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics listing every failed check.
+    pub async fn run(self) {
+        let mut failures = Vec::new();
+        for check in Check::ALL {
+            if self.skip.contains(check) {
+                continue;
+            }
+            if let Err(failure) = check.run(&self).await {
+                failures.push(format!("{}: {failure}", check.name()));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "backend failed conformance checks:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    /// Key `name` of `check`.
+    pub(crate) fn key(&self, check: Check, name: &str) -> CacheKey {
+        CacheKey::new(
+            "conformance".to_owned(),
+            0,
+            vec![
+                KeyPart::new("namespace", Some(&self.namespace)),
+                KeyPart::new("check", Some(check.name())),
+                KeyPart::new("key", Some(name)),
+            ],
+        )
+    }
+
+    pub(crate) fn namespace_part(&self) -> KeyPart {
+        KeyPart::new("namespace", Some(&self.namespace))
+    }
+
+    pub(crate) async fn read(&self, key: &CacheKey) -> Result<Option<CacheValue<Raw>>, String> {
+        self.backend
+            .read(key)
+            .await
+            .map_err(|err| format!("read failed: {err}"))
+    }
+
+    pub(crate) async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> Result<(), String> {
+        self.backend
+            .write(key, value, ttl)
+            .await
+            .map_err(|err| format!("write failed: {err}"))
+    }
+
+    pub(crate) async fn remove(&self, key: &CacheKey) -> Result<DeleteStatus, String> {
+        self.backend
+            .remove(key)
+            .await
+            .map_err(|err| format!("remove failed: {err}"))
+    }
+
+    /// Data read under `key`, `None` on a miss.
+    pub(crate) async fn read_data(&self, key: &CacheKey) -> Result<Option<Raw>, String> {
+        Ok(self.read(key).await?.map(|value| value.data))
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use hitbox_backend::{
+    CacheKeyFormat,
+    composite::Operation,
+    testing::{ChaosBackend, Fault, HashMapBackend, Trigger},
+};
+use hitbox_backend_tests::{Check, Conformance, ManualClock, SystemClock};
+
+#[tokio::test]
+async fn test_hashmap_backend_with_manual_clock() {
+    for key_format in [CacheKeyFormat::Bitcode, CacheKeyFormat::UrlEncoded] {
+        let clock = ManualClock::new();
+        let backend = HashMapBackend::builder()
+            .key_format(key_format)
+            .time_provider(clock.clone())
+            .build();
+
+        Conformance::new(&backend, clock).run().await;
+    }
+}
+
+#[tokio::test]
+async fn test_hashmap_backend_with_system_clock() {
+    let backend = HashMapBackend::new();
+
+    Conformance::new(&backend, SystemClock)
+        .ttl(Duration::from_millis(200))
+        .run()
+        .await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "ttl_honoured")]
+async fn test_backend_ignoring_ttl_fails() {
+    // Clock not shared with the backend, so entries never expire for it.
+    let backend = HashMapBackend::new();
+
+    Conformance::new(&backend, ManualClock::new()).run().await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "write_read")]
+async fn test_corrupting_backend_fails() {
+    let backend = ChaosBackend::new(HashMapBackend::new()).fault(
+        Operation::Write,
+        Trigger::Always,
+        Fault::Corrupt,
+    );
+
+    Conformance::new(&backend, SystemClock)
+        .skip(Check::TtlHonoured)
+        .skip(Check::ExpireAsTtl)
+        .skip(Check::NoTtlPersists)
+        .run()
+        .await;
+}
@@ -0,0 +1,107 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use hitbox_backend::{Compressor, PassthroughCompressor};
+use std::hint::black_box;
+
+fn create_small_json() -> Vec<u8> {
+    br#"{"id":123,"name":"Alice","email":"alice@example.com","active":true}"#.to_vec()
+}
+
+fn create_medium_json() -> Vec<u8> {
+    let items: Vec<String> = (0..50)
+        .map(|i| {
+            format!(
+                r#"{{"id":{i},"name":"user-{i}","email":"user-{i}@example.com","roles":["reader","writer"],"active":{}}}"#,
+                i % 2 == 0
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(",")).into_bytes()
+}
+
+fn create_large_text() -> Vec<u8> {
+    let paragraph = "Hitbox caches responses of upstream services. Cached values are \
+        serialized, compressed and stored in one of the supported backends, where \
+        they stay until they expire or get evicted. ";
+    let mut text = String::new();
+    for i in 0..2000 {
+        text.push_str(paragraph);
+        text.push_str(&format!("Section {i}.\n"));
+    }
+    text.into_bytes()
+}
+
+fn compressors() -> Vec<(&'static str, Box<dyn Compressor>)> {
+    #[allow(unused_mut)]
+    let mut compressors: Vec<(&'static str, Box<dyn Compressor>)> =
+        vec![("passthrough", Box::new(PassthroughCompressor))];
+    #[cfg(feature = "gzip")]
+    compressors.push(("gzip", Box::new(hitbox_backend::GzipCompressor::new())));
+    #[cfg(feature = "zstd")]
+    compressors.push(("zstd", Box::new(hitbox_backend::ZstdCompressor::new())));
+    #[cfg(feature = "lz4")]
+    compressors.push(("lz4", Box::new(hitbox_backend::Lz4Compressor::new())));
+    #[cfg(feature = "brotli")]
+    compressors.push(("brotli", Box::new(hitbox_backend::BrotliCompressor::new())));
+    #[cfg(feature = "snappy")]
+    compressors.push(("snappy", Box::new(hitbox_backend::SnappyCompressor)));
+    compressors
+}
+
+fn bench_compression(c: &mut Criterion) {
+    let test_cases = vec![
+        ("small_json", create_small_json()),
+        ("medium_json", create_medium_json()),
+        ("large_text", create_large_text()),
+    ];
+    let compressors = compressors();
+
+    let mut group = c.benchmark_group("compress");
+    for (name, data) in &test_cases {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        for (compressor_name, compressor) in &compressors {
+            group.bench_with_input(BenchmarkId::new(*compressor_name, name), data, |b, data| {
+                b.iter(|| black_box(compressor.compress(black_box(data)).unwrap()));
+            });
+        }
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("decompress");
+    for (name, data) in &test_cases {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        for (compressor_name, compressor) in &compressors {
+            let compressed = compressor.compress(data).unwrap();
+            group.bench_with_input(
+                BenchmarkId::new(*compressor_name, name),
+                &compressed,
+                |b, compressed| {
+                    b.iter(|| black_box(compressor.decompress(black_box(compressed)).unwrap()));
+                },
+            );
+        }
+    }
+    group.finish();
+
+    // Print compression ratio table
+    println!("\n=== Compression Ratio ===\n");
+    print!("{:<14}", "Compressor");
+    for (name, data) in &test_cases {
+        print!(" {:>18}", format!("{name} ({})", data.len()));
+    }
+    println!();
+    println!("{:-<71}", "");
+
+    for (compressor_name, compressor) in &compressors {
+        print!("{compressor_name:<14}");
+        for (_, data) in &test_cases {
+            let compressed = compressor.compress(data).unwrap();
+            let ratio = data.len() as f64 / compressed.len() as f64;
+            print!(" {:>17.2}x", ratio);
+        }
+        println!();
+    }
+    println!();
+}
+
+criterion_group!(benches, bench_compression);
+criterion_main!(benches);
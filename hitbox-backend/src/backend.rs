@@ -1,12 +1,13 @@
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{any::TypeId, future::Future, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use hitbox_core::{CacheKey, CacheValue, CacheableResponse};
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
-    BackendError, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
-    serializer::{Format, FormatExt, JsonFormat, Raw},
+    BackendError, CacheKeyFormat, Codec, Compressor, DeleteStatus, PassthroughCompressor,
+    serializer::{Format, FormatError, FormatExt, JsonFormat, Raw},
 };
 
 pub type BackendResult<T> = Result<T, BackendError>;
@@ -153,20 +154,42 @@ impl Backend for Arc<dyn Backend + Send + 'static> {
 // }
 
 pub trait CacheBackend: Backend {
+    /// Read and decode value of response `T`.
+    ///
+    /// Values in an [`Envelope`](crate::Envelope) are decoded with the format
+    /// and compressor named in its header. Formats with a typed path for the
+    /// cached type, like [`RkyvFormat`](crate::serializer::RkyvFormat), read it
+    /// straight from the buffer returned by the backend, which is not copied
+    /// when the data is not compressed.
     fn get<T>(
         &self,
         key: &CacheKey,
     ) -> impl Future<Output = BackendResult<Option<CacheValue<T::Cached>>>> + Send
     where
         T: CacheableResponse,
-        T::Cached: DeserializeOwned,
+        T::Cached: DeserializeOwned + 'static,
     {
         async move {
             match self.read(key).await? {
                 Some(value) => {
                     let (meta, value) = value.into_parts();
-                    let decompressed = self.compressor().decompress(&value)?;
-                    let deserialized = self.value_format().deserialize(&decompressed)?;
+                    let mut data = Bytes::from(value);
+                    let (format, compressor) = match self.value_format().envelope() {
+                        Some(envelope) => envelope.open(&mut data, self.compressor())?,
+                        None => (self.value_format(), self.compressor()),
+                    };
+                    if compressor.codec() != Codec::Passthrough {
+                        data = Bytes::from(compressor.decompress(&data)?);
+                    }
+                    let typed = format.deserialize_typed(&data, TypeId::of::<T::Cached>());
+                    let deserialized = match typed {
+                        Some(value) => *value?.downcast::<T::Cached>().map_err(|_| {
+                            FormatError::Deserialize(Box::new(std::io::Error::other(
+                                "typed deserialization produced another type",
+                            )))
+                        })?,
+                        None => format.deserialize(&data)?,
+                    };
                     Ok(Some(CacheValue::new(deserialized, meta.expire, meta.stale)))
                 }
                 None => Ok(None),
@@ -174,6 +197,8 @@ pub trait CacheBackend: Backend {
         }
     }
 
+    /// Encode and write value of response `T`, in an envelope if the value
+    /// format has one.
     fn set<T>(
         &self,
         key: &CacheKey,
@@ -182,11 +207,17 @@ pub trait CacheBackend: Backend {
     ) -> impl Future<Output = BackendResult<()>> + Send
     where
         T: CacheableResponse,
-        T::Cached: Serialize + Send + Sync,
+        T::Cached: Serialize + Send + Sync + 'static,
     {
         async move {
-            let serialized_value = self.value_format().serialize(&value.data)?;
-            let compressed_value = self.compressor().compress(&serialized_value)?;
+            let serialized_value = match self.value_format().serialize_typed(&value.data) {
+                Some(serialized) => serialized?,
+                None => self.value_format().serialize(&value.data)?,
+            };
+            let mut compressed_value = self.compressor().compress(&serialized_value)?;
+            if let Some(envelope) = self.value_format().envelope() {
+                compressed_value = envelope.seal(self.compressor(), &compressed_value);
+            }
             self.write(
                 key,
                 CacheValue::new(compressed_value, value.expire, value.stale),
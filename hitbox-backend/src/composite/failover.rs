@@ -0,0 +1,228 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use hitbox_core::{CacheKey, CacheValue};
+
+use super::{CompositionError, Member, MemberObserver, Operation, SharedBackend};
+use crate::{
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    PassthroughCompressor,
+    serializer::{Format, JsonFormat, Raw},
+};
+
+/// Backend sending every call to the primary member and falling back to the
+/// secondaries, in registration order, when it fails with
+/// [`BackendError::ConnectionError`].
+///
+/// Any other error is returned immediately. If all members are unreachable the
+/// connection error of the last one is returned, so failover backends can be
+/// nested.
+#[derive(Clone)]
+pub struct FailoverBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    members: Arc<[Member]>,
+    observer: Option<MemberObserver>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl<S, C> std::fmt::Debug for FailoverBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FailoverBackend")
+            .field("members", &self.members().collect::<Vec<_>>())
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish()
+    }
+}
+
+impl FailoverBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new FailoverBackend builder with default settings.
+    pub fn builder() -> FailoverBackendBuilder<JsonFormat, PassthroughCompressor> {
+        FailoverBackendBuilder::default()
+    }
+}
+
+impl<S, C> FailoverBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Identifiers of all members, primary first.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|member| member.id.as_str())
+    }
+
+    async fn call<'a, T, F>(&'a self, operation: Operation, f: F) -> BackendResult<T>
+    where
+        F: Fn(&'a SharedBackend) -> BoxFuture<'a, BackendResult<T>>,
+    {
+        let mut last_error = None;
+        for member in self.members.iter() {
+            match f(&member.backend).await {
+                Ok(result) => {
+                    if let Some(observer) = &self.observer {
+                        observer(operation, &member.id);
+                    }
+                    return Ok(result);
+                }
+                Err(error @ BackendError::ConnectionError(_)) => last_error = Some(error),
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| CompositionError::NoMembers.into()))
+    }
+}
+
+/// Part of builder pattern implementation for FailoverBackend.
+pub struct FailoverBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    members: Vec<Member>,
+    observer: Option<MemberObserver>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for FailoverBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            members: Vec::new(),
+            observer: None,
+            key_format: CacheKeyFormat::default(),
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> FailoverBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Add member. The first added member is the primary.
+    pub fn member(mut self, id: impl Into<String>, backend: SharedBackend) -> Self {
+        self.members.push(Member {
+            id: id.into(),
+            backend,
+        });
+        self
+    }
+
+    /// Set observer notified with the member which served each call.
+    pub fn observer<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(Operation, &str) + Send + Sync + 'static,
+    {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Set key serialization format.
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    /// Set value serialization format (JSON, Bincode, etc.)
+    pub fn value_format<NewS>(self, serializer: NewS) -> FailoverBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        FailoverBackendBuilder {
+            members: self.members,
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    /// Set compressor for value compression
+    pub fn compressor<NewC>(self, compressor: NewC) -> FailoverBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        FailoverBackendBuilder {
+            members: self.members,
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create new instance of failover backend with passed settings.
+    pub fn build(self) -> Result<FailoverBackend<S, C>, CompositionError> {
+        if self.members.is_empty() {
+            return Err(CompositionError::NoMembers);
+        }
+        let mut ids = HashSet::new();
+        if let Some(member) = self.members.iter().find(|member| !ids.insert(&member.id)) {
+            return Err(CompositionError::DuplicateMember(member.id.clone()));
+        }
+
+        Ok(FailoverBackend {
+            members: self.members.into(),
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for FailoverBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        self.call(Operation::Read, |backend| backend.read(key))
+            .await
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        self.call(Operation::Write, |backend| {
+            backend.write(key, value.clone(), ttl)
+        })
+        .await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.call(Operation::Remove, |backend| backend.remove(key))
+            .await
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
@@ -0,0 +1,77 @@
+//! Backends composed from several other backends.
+//!
+//! Each wrapper here owns a list of [`SharedBackend`] members and implements
+//! [`Backend`] itself, so it can be used anywhere a single backend is expected.
+//! Values are serialized and compressed once by the wrapper; members only see
+//! raw bytes and use their own key format for storage.
+//!
+//! [`Backend`]: crate::Backend
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::{Backend, BackendError};
+
+mod failover;
+mod replicated;
+mod sharded;
+
+pub use failover::{FailoverBackend, FailoverBackendBuilder};
+pub use replicated::{ReplicatedBackend, ReplicatedBackendBuilder};
+pub use sharded::{DEFAULT_VIRTUAL_NODES, ShardedBackend, ShardedBackendBuilder};
+
+/// Shared, type-erased backend used as a member of composite backends.
+pub type SharedBackend = Arc<dyn Backend + Send + 'static>;
+
+/// Observer notified with the id of the member which served an operation.
+///
+/// Intended for metrics, e.g. counting reads served by failover secondaries.
+pub type MemberObserver = Arc<dyn Fn(Operation, &str) + Send + Sync>;
+
+/// Backend operation reported to [`MemberObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// [`Backend::read`] call.
+    Read,
+    /// [`Backend::write`] call.
+    Write,
+    /// [`Backend::remove`] call.
+    Remove,
+}
+
+pub(crate) struct Member {
+    pub(crate) id: String,
+    pub(crate) backend: SharedBackend,
+}
+
+/// Errors specific to composite backends.
+#[derive(Debug, Error)]
+pub enum CompositionError {
+    /// Composite backend was built without any member.
+    #[error("composite backend requires at least one member")]
+    NoMembers,
+    /// Two members were registered with the same identifier.
+    #[error("duplicate member id: {0}")]
+    DuplicateMember(String),
+    /// Every member able to serve the call is marked unhealthy.
+    #[error("no healthy member available")]
+    NoHealthyMember,
+    /// Quorum is zero or greater than the number of members.
+    #[error("invalid quorum {quorum} for {members} members")]
+    InvalidQuorum { quorum: usize, members: usize },
+    /// Not enough members acknowledged the operation.
+    #[error("quorum not reached: {succeeded} of {required} members succeeded")]
+    QuorumNotReached { succeeded: usize, required: usize },
+    /// Shard weight times virtual nodes doesn't fit the hash ring.
+    #[error("weight {weight} of shard {id} overflows the number of ring points")]
+    WeightOverflow { id: String, weight: u32 },
+    /// Shard has weight zero and would never receive a key.
+    #[error("shard {id} has zero weight")]
+    ZeroWeight { id: String },
+}
+
+impl From<CompositionError> for BackendError {
+    fn from(error: CompositionError) -> Self {
+        Self::InternalError(Box::new(error))
+    }
+}
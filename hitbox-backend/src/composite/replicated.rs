@@ -0,0 +1,315 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{
+    StreamExt,
+    future::{FutureExt, join_all},
+    stream::FuturesUnordered,
+};
+use hitbox_core::{CacheKey, CacheValue};
+
+use super::{CompositionError, Member, MemberObserver, Operation, SharedBackend};
+use crate::{
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    PassthroughCompressor,
+    serializer::{Format, JsonFormat, Raw},
+};
+
+/// Backend writing every value to all members and reading from the first member
+/// which has the value.
+///
+/// Writes and removes run concurrently on every member and succeed once at least
+/// `quorum` members succeeded. By default the quorum equals the number of members.
+#[derive(Clone)]
+pub struct ReplicatedBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    members: Arc<[Member]>,
+    quorum: usize,
+    observer: Option<MemberObserver>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl<S, C> std::fmt::Debug for ReplicatedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplicatedBackend")
+            .field("members", &self.members().collect::<Vec<_>>())
+            .field("quorum", &self.quorum)
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish()
+    }
+}
+
+impl ReplicatedBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new ReplicatedBackend builder with default settings.
+    pub fn builder() -> ReplicatedBackendBuilder<JsonFormat, PassthroughCompressor> {
+        ReplicatedBackendBuilder::default()
+    }
+}
+
+impl<S, C> ReplicatedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Identifiers of all members in registration order.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|member| member.id.as_str())
+    }
+
+    /// Number of members which must acknowledge a write or remove.
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
+    fn notify(&self, operation: Operation, id: &str) {
+        if let Some(observer) = &self.observer {
+            observer(operation, id);
+        }
+    }
+
+    /// Check results of a fan-out call against the quorum.
+    ///
+    /// Quorum failure is reported as a connection error if every failed member was
+    /// unreachable, so the replicated backend itself can be a failover member.
+    fn collect<T>(
+        &self,
+        operation: Operation,
+        results: Vec<BackendResult<T>>,
+    ) -> BackendResult<Vec<T>> {
+        let mut succeeded = Vec::with_capacity(results.len());
+        let mut unreachable = true;
+        for (member, result) in self.members.iter().zip(results) {
+            match result {
+                Ok(value) => {
+                    self.notify(operation, &member.id);
+                    succeeded.push(value);
+                }
+                Err(BackendError::ConnectionError(_)) => {}
+                Err(_) => unreachable = false,
+            }
+        }
+
+        if succeeded.len() >= self.quorum {
+            return Ok(succeeded);
+        }
+        let error = CompositionError::QuorumNotReached {
+            succeeded: succeeded.len(),
+            required: self.quorum,
+        };
+        Err(match unreachable {
+            true => BackendError::ConnectionError(Box::new(error)),
+            false => error.into(),
+        })
+    }
+}
+
+/// Part of builder pattern implementation for ReplicatedBackend.
+pub struct ReplicatedBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    members: Vec<Member>,
+    quorum: Option<usize>,
+    observer: Option<MemberObserver>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for ReplicatedBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            members: Vec::new(),
+            quorum: None,
+            observer: None,
+            key_format: CacheKeyFormat::default(),
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> ReplicatedBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Add replica.
+    pub fn member(mut self, id: impl Into<String>, backend: SharedBackend) -> Self {
+        self.members.push(Member {
+            id: id.into(),
+            backend,
+        });
+        self
+    }
+
+    /// Set number of members which must acknowledge a write or remove.
+    pub fn quorum(mut self, quorum: usize) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Set observer notified with the members which served each call.
+    pub fn observer<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(Operation, &str) + Send + Sync + 'static,
+    {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Set key serialization format.
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    /// Set value serialization format (JSON, Bincode, etc.)
+    pub fn value_format<NewS>(self, serializer: NewS) -> ReplicatedBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        ReplicatedBackendBuilder {
+            members: self.members,
+            quorum: self.quorum,
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    /// Set compressor for value compression
+    pub fn compressor<NewC>(self, compressor: NewC) -> ReplicatedBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        ReplicatedBackendBuilder {
+            members: self.members,
+            quorum: self.quorum,
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create new instance of replicated backend with passed settings.
+    pub fn build(self) -> Result<ReplicatedBackend<S, C>, CompositionError> {
+        if self.members.is_empty() {
+            return Err(CompositionError::NoMembers);
+        }
+        let mut ids = HashSet::new();
+        if let Some(member) = self.members.iter().find(|member| !ids.insert(&member.id)) {
+            return Err(CompositionError::DuplicateMember(member.id.clone()));
+        }
+        let quorum = self.quorum.unwrap_or(self.members.len());
+        if quorum == 0 || quorum > self.members.len() {
+            return Err(CompositionError::InvalidQuorum {
+                quorum,
+                members: self.members.len(),
+            });
+        }
+
+        Ok(ReplicatedBackend {
+            members: self.members.into(),
+            quorum,
+            observer: self.observer,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for ReplicatedBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let mut reads = self
+            .members
+            .iter()
+            .map(|member| {
+                member
+                    .backend
+                    .read(key)
+                    .map(move |result| (member.id.as_str(), result))
+            })
+            .collect::<FuturesUnordered<_>>();
+        // A replica which missed a write answers with a miss, so a miss is
+        // only final once every member answered.
+        let mut missed = None;
+        let mut last_error = None;
+        while let Some((id, result)) = reads.next().await {
+            match result {
+                Ok(Some(value)) => {
+                    self.notify(Operation::Read, id);
+                    return Ok(Some(value));
+                }
+                Ok(None) => missed = missed.or(Some(id)),
+                Err(error) => last_error = Some(error),
+            }
+        }
+        match (missed, last_error) {
+            (Some(id), _) => {
+                self.notify(Operation::Read, id);
+                Ok(None)
+            }
+            (None, Some(error)) => Err(error),
+            (None, None) => Err(CompositionError::NoMembers.into()),
+        }
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let writes = self
+            .members
+            .iter()
+            .map(|member| member.backend.write(key, value.clone(), ttl));
+        self.collect(Operation::Write, join_all(writes).await)
+            .map(|_| ())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let removes = self.members.iter().map(|member| member.backend.remove(key));
+        let statuses = self.collect(Operation::Remove, join_all(removes).await)?;
+        let deleted = statuses
+            .iter()
+            .any(|status| matches!(status, DeleteStatus::Deleted(_)));
+        match deleted {
+            true => Ok(DeleteStatus::Deleted(1)),
+            false => Ok(DeleteStatus::Missing),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use hitbox_core::{CacheKey, CacheValue};
+
+use super::{CompositionError, SharedBackend};
+use crate::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    serializer::{Format, JsonFormat, Raw},
+};
+
+/// Default number of points each shard of weight 1 occupies on the ring.
+pub const DEFAULT_VIRTUAL_NODES: u32 = 160;
+
+struct Shard {
+    id: String,
+    backend: SharedBackend,
+    healthy: AtomicBool,
+}
+
+/// Backend distributing keys across independent backends with consistent hashing.
+///
+/// Every shard is placed on a hash ring as a number of virtual nodes proportional
+/// to its weight. A key is routed to the first healthy shard found clockwise from
+/// the hash of its serialized form, so adding or removing a shard only remaps the
+/// keys that fall into the affected ring segments.
+///
+/// Clones share the ring and the health state of the shards.
+#[derive(Clone)]
+pub struct ShardedBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    shards: Arc<[Shard]>,
+    ring: Arc<[(u64, usize)]>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl<S, C> std::fmt::Debug for ShardedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedBackend")
+            .field("shards", &self.shards().collect::<Vec<_>>())
+            .field("ring_size", &self.ring.len())
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish()
+    }
+}
+
+impl ShardedBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new ShardedBackend builder with default settings.
+    pub fn builder() -> ShardedBackendBuilder<JsonFormat, PassthroughCompressor> {
+        ShardedBackendBuilder::default()
+    }
+}
+
+impl<S, C> ShardedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Identifiers of all shards in registration order.
+    pub fn shards(&self) -> impl Iterator<Item = &str> {
+        self.shards.iter().map(|shard| shard.id.as_str())
+    }
+
+    /// Mark shard as healthy or unhealthy.
+    ///
+    /// Keys of an unhealthy shard are routed to the next healthy shard on the ring
+    /// until the shard is marked healthy again. Returns `false` if there is no
+    /// shard with such id.
+    pub fn set_healthy(&self, id: &str, healthy: bool) -> bool {
+        match self.shards.iter().find(|shard| shard.id == id) {
+            Some(shard) => {
+                shard.healthy.store(healthy, Ordering::Release);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Health state of the shard, `None` if there is no shard with such id.
+    pub fn is_healthy(&self, id: &str) -> Option<bool> {
+        self.shards
+            .iter()
+            .find(|shard| shard.id == id)
+            .map(|shard| shard.healthy.load(Ordering::Acquire))
+    }
+
+    /// Identifier of the shard currently serving the key.
+    pub fn shard_for(&self, key: &CacheKey) -> BackendResult<&str> {
+        self.route(key).map(|shard| shard.id.as_str())
+    }
+
+    fn route(&self, key: &CacheKey) -> BackendResult<&Shard> {
+        let hash = hash(&self.key_format.serialize(key)?);
+        let start = self.ring.partition_point(|(point, _)| *point < hash);
+        self.ring[start..]
+            .iter()
+            .chain(self.ring[..start].iter())
+            .map(|(_, index)| &self.shards[*index])
+            .find(|shard| shard.healthy.load(Ordering::Acquire))
+            .ok_or_else(|| CompositionError::NoHealthyMember.into())
+    }
+}
+
+/// Part of builder pattern implementation for ShardedBackend.
+pub struct ShardedBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    shards: Vec<(String, SharedBackend, u32)>,
+    virtual_nodes: u32,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for ShardedBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            shards: Vec::new(),
+            virtual_nodes: DEFAULT_VIRTUAL_NODES,
+            key_format: CacheKeyFormat::default(),
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> ShardedBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Add shard with weight 1.
+    ///
+    /// The id determines the shard position on the ring, so it must stay the same
+    /// between restarts and across all processes sharing the shards.
+    pub fn shard(self, id: impl Into<String>, backend: SharedBackend) -> Self {
+        self.weighted_shard(id, backend, 1)
+    }
+
+    /// Add shard receiving a share of keys proportional to its weight.
+    pub fn weighted_shard(
+        mut self,
+        id: impl Into<String>,
+        backend: SharedBackend,
+        weight: u32,
+    ) -> Self {
+        self.shards.push((id.into(), backend, weight));
+        self
+    }
+
+    /// Set number of ring points per unit of shard weight.
+    pub fn virtual_nodes(mut self, virtual_nodes: u32) -> Self {
+        self.virtual_nodes = virtual_nodes;
+        self
+    }
+
+    /// Set key serialization format used for hashing.
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    /// Set value serialization format (JSON, Bincode, etc.)
+    pub fn value_format<NewS>(self, serializer: NewS) -> ShardedBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        ShardedBackendBuilder {
+            shards: self.shards,
+            virtual_nodes: self.virtual_nodes,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    /// Set compressor for value compression
+    pub fn compressor<NewC>(self, compressor: NewC) -> ShardedBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        ShardedBackendBuilder {
+            shards: self.shards,
+            virtual_nodes: self.virtual_nodes,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create new instance of sharded backend with passed settings.
+    pub fn build(self) -> Result<ShardedBackend<S, C>, CompositionError> {
+        let mut ids = HashSet::new();
+        for (id, _, weight) in &self.shards {
+            if !ids.insert(id.as_str()) {
+                return Err(CompositionError::DuplicateMember(id.clone()));
+            }
+            if *weight == 0 {
+                return Err(CompositionError::ZeroWeight { id: id.clone() });
+            }
+        }
+
+        let mut ring = Vec::new();
+        for (index, (id, _, weight)) in self.shards.iter().enumerate() {
+            let points = self
+                .virtual_nodes
+                .max(1)
+                .checked_mul(*weight)
+                .ok_or_else(|| CompositionError::WeightOverflow {
+                    id: id.clone(),
+                    weight: *weight,
+                })?;
+            ring.extend((0..points).map(|point| (hash(format!("{id}-{point}").as_bytes()), index)));
+        }
+        ring.sort_unstable();
+        ring.dedup_by_key(|(point, _)| *point);
+        if ring.is_empty() {
+            return Err(CompositionError::NoMembers);
+        }
+
+        let shards = self
+            .shards
+            .into_iter()
+            .map(|(id, backend, _)| Shard {
+                id,
+                backend,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+
+        Ok(ShardedBackend {
+            shards,
+            ring: ring.into(),
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+/// FNV-1a followed by the murmur3 finalizer.
+///
+/// The result must be stable across processes and compiler versions, which rules
+/// out `std::hash::DefaultHasher`.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+#[async_trait]
+impl<S, C> Backend for ShardedBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        self.route(key)?.backend.read(key).await
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        self.route(key)?.backend.write(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.route(key)?.backend.remove(key).await
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
@@ -15,6 +15,11 @@ pub trait Compressor: Send + Sync + std::fmt::Debug {
 
     /// Decompress the input data
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError>;
+
+    /// Codec of the compressed data, recorded in the [`AdaptiveCompressor`] header
+    fn codec(&self) -> Codec {
+        Codec::Custom
+    }
 }
 
 // Blanket implementation for Arc<dyn Compressor>
@@ -26,6 +31,80 @@ impl Compressor for std::sync::Arc<dyn Compressor> {
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
         (**self).decompress(data)
     }
+
+    fn codec(&self) -> Codec {
+        (**self).codec()
+    }
+}
+
+/// Compression codec identified by the one-byte tag of [`AdaptiveCompressor`] values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// Data stored uncompressed
+    Passthrough,
+    Gzip,
+    Zstd,
+    Lz4,
+    Brotli,
+    Snappy,
+    /// Data in the [`AdaptiveCompressor`] header, naming its own codec
+    Adaptive,
+    /// Compressor outside this crate, decoded only by the configured compressor
+    Custom,
+}
+
+impl Codec {
+    /// Tag byte written in front of the data
+    pub fn tag(self) -> u8 {
+        match self {
+            Codec::Passthrough => 0,
+            Codec::Gzip => 1,
+            Codec::Zstd => 2,
+            Codec::Lz4 => 3,
+            Codec::Brotli => 4,
+            Codec::Snappy => 5,
+            Codec::Adaptive => 6,
+            Codec::Custom => 0xff,
+        }
+    }
+
+    /// Codec with the given tag byte
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        [
+            Codec::Passthrough,
+            Codec::Gzip,
+            Codec::Zstd,
+            Codec::Lz4,
+            Codec::Brotli,
+            Codec::Snappy,
+            Codec::Adaptive,
+            Codec::Custom,
+        ]
+        .into_iter()
+        .find(|codec| codec.tag() == tag)
+    }
+
+    /// Decompress data with the default settings of the codec
+    fn decompress(self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        match self {
+            Codec::Passthrough => PassthroughCompressor.decompress(data),
+            #[cfg(feature = "gzip")]
+            Codec::Gzip => GzipCompressor::new().decompress(data),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => ZstdCompressor::new().decompress(data),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => Lz4Compressor::new().decompress(data),
+            #[cfg(feature = "brotli")]
+            Codec::Brotli => BrotliCompressor::new().decompress(data),
+            #[cfg(feature = "snappy")]
+            Codec::Snappy => SnappyCompressor.decompress(data),
+            Codec::Adaptive => AdaptiveCompressor::new(PassthroughCompressor).decompress(data),
+            #[allow(unreachable_patterns)]
+            codec => Err(CompressionError::DecompressionFailed(format!(
+                "no decoder available for {codec:?} data"
+            ))),
+        }
+    }
 }
 
 /// No-op compressor that passes data through unchanged (default)
@@ -40,6 +119,10 @@ impl Compressor for PassthroughCompressor {
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
         Ok(data.to_vec())
     }
+
+    fn codec(&self) -> Codec {
+        Codec::Passthrough
+    }
 }
 
 /// Gzip compression with configurable level
@@ -98,20 +181,103 @@ impl Compressor for GzipCompressor {
             .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))?;
         Ok(decompressed)
     }
+
+    fn codec(&self) -> Codec {
+        Codec::Gzip
+    }
 }
 
-/// Zstd compression with configurable level
+/// Zstd dictionary, identified by the id stored in its header
+///
+/// Values compressed with a dictionary carry its id in the frame header, so a
+/// [`ZstdCompressor`] knowing several dictionary generations decodes each value
+/// with the one it was compressed with.
 #[cfg(feature = "zstd")]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZstdDictionary {
+    id: u32,
+    data: std::sync::Arc<[u8]>,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDictionary {
+    /// Create dictionary from the bytes of a trained dictionary, e.g. loaded from a file
+    ///
+    /// Raw content dictionaries have no id and are rejected.
+    pub fn new(data: impl Into<Vec<u8>>) -> Result<Self, CompressionError> {
+        let data = data.into();
+        let id = zstd::zstd_safe::get_dict_id(&data).ok_or_else(|| {
+            CompressionError::CompressionFailed("zstd dictionary has no id".to_string())
+        })?;
+        Ok(Self {
+            id: id.get(),
+            data: data.into(),
+        })
+    }
+
+    /// Train dictionary of at most `max_size` bytes on sample values
+    ///
+    /// Zstd needs many samples, hundreds or more, to produce a useful dictionary.
+    pub fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Self, CompressionError> {
+        let data = zstd::dict::from_samples(samples, max_size)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))?;
+        Self::new(data)
+    }
+
+    /// Train dictionary on values stored in `backend` under `keys`
+    ///
+    /// Envelope headers are stripped and values are decompressed with the
+    /// compressor they were written with, missing keys are skipped. Pass the
+    /// outermost backend, e.g. the encrypting or integrity checking wrapper,
+    /// so values are read decrypted and verified rather than as stored.
+    pub async fn train_from_backend<'a, B>(
+        backend: &B,
+        keys: impl IntoIterator<Item = &'a hitbox_core::CacheKey>,
+        max_size: usize,
+    ) -> crate::BackendResult<Self>
+    where
+        B: crate::Backend + ?Sized,
+    {
+        let mut samples = Vec::new();
+        for key in keys {
+            if let Some(value) = backend.read(key).await? {
+                let mut data = bytes::Bytes::from(value.data);
+                let compressor = match backend.value_format().envelope() {
+                    Some(envelope) => envelope.open(&mut data, backend.compressor())?.1,
+                    None => backend.compressor(),
+                };
+                samples.push(compressor.decompress(&data)?);
+            }
+        }
+        Ok(Self::train(&samples, max_size)?)
+    }
+
+    /// Dictionary id written into the values compressed with it
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Dictionary bytes, e.g. to save it to a file
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Zstd compression with configurable level and optional dictionaries
+#[cfg(feature = "zstd")]
+#[derive(Clone)]
 pub struct ZstdCompressor {
     level: i32,
+    dictionary: Option<(u32, std::sync::Arc<zstd::dict::EncoderDictionary<'static>>)>,
+    decoders:
+        std::collections::HashMap<u32, std::sync::Arc<zstd::dict::DecoderDictionary<'static>>>,
 }
 
 #[cfg(feature = "zstd")]
 impl ZstdCompressor {
     /// Create a new ZstdCompressor with default compression level (3)
     pub fn new() -> Self {
-        Self { level: 3 }
+        Self::with_level(3)
     }
 
     /// Create a new ZstdCompressor with specified compression level (-7 to 22)
@@ -120,8 +286,26 @@ impl ZstdCompressor {
     pub fn with_level(level: i32) -> Self {
         Self {
             level: level.clamp(-7, 22),
+            dictionary: None,
+            decoders: Default::default(),
         }
     }
+
+    /// Compress with the given dictionary, which is also used for decompression
+    pub fn dictionary(mut self, dictionary: &ZstdDictionary) -> Self {
+        let encoder = zstd::dict::EncoderDictionary::copy(dictionary.as_bytes(), self.level);
+        self.dictionary = Some((dictionary.id(), std::sync::Arc::new(encoder)));
+        self.previous_dictionary(dictionary)
+    }
+
+    /// Decompress values compressed with the given dictionary, e.g. a previous
+    /// generation still present in the cache
+    pub fn previous_dictionary(mut self, dictionary: &ZstdDictionary) -> Self {
+        let decoder = zstd::dict::DecoderDictionary::copy(dictionary.as_bytes());
+        self.decoders
+            .insert(dictionary.id(), std::sync::Arc::new(decoder));
+        self
+    }
 }
 
 #[cfg(feature = "zstd")]
@@ -131,15 +315,327 @@ impl Default for ZstdCompressor {
     }
 }
 
+#[cfg(feature = "zstd")]
+impl std::fmt::Debug for ZstdCompressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut dictionaries: Vec<_> = self.decoders.keys().collect();
+        dictionaries.sort();
+        f.debug_struct("ZstdCompressor")
+            .field("level", &self.level)
+            .field("dictionary", &self.dictionary.as_ref().map(|(id, _)| id))
+            .field("dictionaries", &dictionaries)
+            .finish()
+    }
+}
+
 #[cfg(feature = "zstd")]
 impl Compressor for ZstdCompressor {
     fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
-        zstd::encode_all(data, self.level)
+        use std::io::Write;
+
+        let Some((_, dictionary)) = &self.dictionary else {
+            return zstd::encode_all(data, self.level)
+                .map_err(|e| CompressionError::CompressionFailed(e.to_string()));
+        };
+        let mut encoder = zstd::stream::Encoder::with_prepared_dictionary(Vec::new(), dictionary)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))?;
+        encoder
+            .write_all(data)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))?;
+        encoder
+            .finish()
             .map_err(|e| CompressionError::CompressionFailed(e.to_string()))
     }
 
     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
-        zstd::decode_all(data).map_err(|e| CompressionError::DecompressionFailed(e.to_string()))
+        use std::io::Read;
+
+        let Some(id) = zstd::zstd_safe::get_dict_id_from_frame(data) else {
+            return zstd::decode_all(data)
+                .map_err(|e| CompressionError::DecompressionFailed(e.to_string()));
+        };
+        let dictionary = self.decoders.get(&id.get()).ok_or_else(|| {
+            CompressionError::DecompressionFailed(format!("unknown zstd dictionary {id}"))
+        })?;
+        let mut decoder = zstd::stream::Decoder::with_prepared_dictionary(data, dictionary)
+            .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))?;
+        let mut decompressed = Vec::new();
+        decoder
+            .read_to_end(&mut decompressed)
+            .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))?;
+        Ok(decompressed)
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::Zstd
+    }
+}
+
+/// LZ4 block compression with configurable level
+#[cfg(feature = "lz4")]
+#[derive(Debug, Clone, Copy)]
+pub struct Lz4Compressor {
+    level: i32,
+}
+
+#[cfg(feature = "lz4")]
+impl Lz4Compressor {
+    /// Create a new Lz4Compressor with default compression level (0)
+    pub fn new() -> Self {
+        Self { level: 0 }
+    }
+
+    /// Create a new Lz4Compressor with specified compression level (-16 to 12)
+    /// Negative values = fast mode with the given acceleration
+    /// Positive values = high compression mode, slower but better compression
+    pub fn with_level(level: i32) -> Self {
+        Self {
+            level: level.clamp(-16, 12),
+        }
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Default for Lz4Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Compressor for Lz4Compressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        use lz4::block::CompressionMode;
+
+        let mode = match self.level {
+            0 => CompressionMode::DEFAULT,
+            level if level < 0 => CompressionMode::FAST(-level),
+            level => CompressionMode::HIGHCOMPRESSION(level),
+        };
+        lz4::block::compress(data, Some(mode), true)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        lz4::block::decompress(data, None)
+            .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::Lz4
+    }
+}
+
+/// Brotli compression with configurable level
+#[cfg(feature = "brotli")]
+#[derive(Debug, Clone, Copy)]
+pub struct BrotliCompressor {
+    level: u32,
+}
+
+#[cfg(feature = "brotli")]
+impl BrotliCompressor {
+    /// Size of the internal buffers of the encoder and decoder.
+    const BUFFER_SIZE: usize = 4096;
+    /// Base two logarithm of the sliding window size.
+    const WINDOW_BITS: u32 = 22;
+
+    /// Create a new BrotliCompressor with default compression level (9)
+    pub fn new() -> Self {
+        Self { level: 9 }
+    }
+
+    /// Create a new BrotliCompressor with specified compression level (0-11)
+    pub fn with_level(level: u32) -> Self {
+        Self {
+            level: level.min(11),
+        }
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl Default for BrotliCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl Compressor for BrotliCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        use std::io::Write;
+
+        let mut encoder = brotli::CompressorWriter::new(
+            Vec::new(),
+            Self::BUFFER_SIZE,
+            self.level,
+            Self::WINDOW_BITS,
+        );
+        encoder
+            .write_all(data)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))?;
+        Ok(encoder.into_inner())
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        use std::io::Read;
+
+        let mut decoder = brotli::Decompressor::new(data, Self::BUFFER_SIZE);
+        let mut decompressed = Vec::new();
+        decoder
+            .read_to_end(&mut decompressed)
+            .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))?;
+        Ok(decompressed)
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::Brotli
+    }
+}
+
+/// Snappy raw compression, which has no compression levels
+#[cfg(feature = "snappy")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SnappyCompressor;
+
+#[cfg(feature = "snappy")]
+impl Compressor for SnappyCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        snap::raw::Encoder::new()
+            .compress_vec(data)
+            .map_err(|e| CompressionError::CompressionFailed(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|e| CompressionError::DecompressionFailed(e.to_string()))
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::Snappy
+    }
+}
+
+/// Default size below which [`AdaptiveCompressor`] stores data uncompressed
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 256;
+
+/// Prefix of [`AdaptiveCompressor`] values, in front of the codec tag.
+///
+/// `0xc1` never occurs in UTF-8 text and doesn't start the frames of any
+/// supported codec, so untagged values written before the wrapper was
+/// introduced aren't mistaken for tagged ones.
+const ADAPTIVE_MAGIC: [u8; 2] = [0xc1, 0xa7];
+
+/// Compressor wrapper skipping compression where it doesn't pay off and
+/// tagging every value with its [`Codec`].
+///
+/// Data shorter than the threshold, or which doesn't shrink by at least the
+/// minimal ratio, is stored uncompressed. Either way a two-byte magic prefix and
+/// a one-byte codec tag are written in front of the data, so values compressed
+/// with a different codec, e.g. before the configured compressor was changed,
+/// are still decoded. Values of
+/// [`Codec::Custom`] and of the codec of the wrapped compressor are decoded by
+/// the wrapped compressor, others with the default settings of their codec.
+///
+/// Values written without the header, before the wrapper was introduced, can
+/// be read by setting a [`fallback`](Self::fallback) compressor.
+#[derive(Debug, Clone)]
+pub struct AdaptiveCompressor<C = PassthroughCompressor> {
+    compressor: C,
+    threshold: usize,
+    min_ratio: f64,
+    fallback: Option<std::sync::Arc<dyn Compressor>>,
+}
+
+impl<C> AdaptiveCompressor<C>
+where
+    C: Compressor,
+{
+    /// Wrap compressor with default threshold and no minimal ratio
+    pub fn new(compressor: C) -> Self {
+        Self {
+            compressor,
+            threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            min_ratio: 1.0,
+            fallback: None,
+        }
+    }
+
+    /// Set size in bytes below which data is stored uncompressed
+    pub fn threshold(mut self, bytes: usize) -> Self {
+        self.threshold = bytes;
+        self
+    }
+
+    /// Set ratio of original to compressed size compression must reach,
+    /// otherwise data is stored uncompressed
+    pub fn min_ratio(mut self, ratio: f64) -> Self {
+        self.min_ratio = ratio;
+        self
+    }
+
+    /// Set compressor decoding values without a valid header, written before the
+    /// wrapper was introduced
+    pub fn fallback(mut self, compressor: impl Compressor + 'static) -> Self {
+        self.fallback = Some(std::sync::Arc::new(compressor));
+        self
+    }
+
+    fn tagged(codec: Codec, data: &[u8]) -> Vec<u8> {
+        let mut tagged = Vec::with_capacity(ADAPTIVE_MAGIC.len() + 1 + data.len());
+        tagged.extend_from_slice(&ADAPTIVE_MAGIC);
+        tagged.push(codec.tag());
+        tagged.extend_from_slice(data);
+        tagged
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let Some((&tag, payload)) = data
+            .strip_prefix(&ADAPTIVE_MAGIC)
+            .and_then(|data| data.split_first())
+        else {
+            return Err(CompressionError::DecompressionFailed(
+                "missing codec header".to_string(),
+            ));
+        };
+        match Codec::from_tag(tag) {
+            Some(codec) if codec == Codec::Custom || codec == self.compressor.codec() => {
+                self.compressor.decompress(payload)
+            }
+            Some(codec) => codec.decompress(payload),
+            None => Err(CompressionError::DecompressionFailed(format!(
+                "unknown codec tag {tag:#04x}"
+            ))),
+        }
+    }
+}
+
+impl<C> Compressor for AdaptiveCompressor<C>
+where
+    C: Compressor,
+{
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        let codec = self.compressor.codec();
+        if data.len() >= self.threshold && codec != Codec::Passthrough {
+            let compressed = self.compressor.compress(data)?;
+            let ratio = data.len() as f64 / compressed.len().max(1) as f64;
+            if compressed.len() < data.len() && ratio >= self.min_ratio {
+                return Ok(Self::tagged(codec, &compressed));
+            }
+        }
+        Ok(Self::tagged(Codec::Passthrough, data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        match (self.decode(data), &self.fallback) {
+            (Err(_), Some(fallback)) => fallback.decompress(data),
+            (result, _) => result,
+        }
+    }
+
+    fn codec(&self) -> Codec {
+        Codec::Adaptive
     }
 }
 
@@ -235,4 +731,299 @@ mod tests {
         assert_eq!(balanced.decompress(&balanced_compressed).unwrap(), data);
         assert_eq!(max.decompress(&max_compressed).unwrap(), data);
     }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_compressor() {
+        let compressor = Lz4Compressor::new();
+        let data = b"Hello, World! This is a test of lz4 compression.".repeat(10);
+
+        let compressed = compressor.compress(&data).unwrap();
+        assert!(
+            compressed.len() < data.len(),
+            "Compressed data should be smaller"
+        );
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_compression_levels() {
+        let data = b"Hello, World! This is a test of lz4 compression.".repeat(100);
+
+        let fast = Lz4Compressor::with_level(-16);
+        let balanced = Lz4Compressor::with_level(0);
+        let max = Lz4Compressor::with_level(12);
+
+        let fast_compressed = fast.compress(&data).unwrap();
+        let balanced_compressed = balanced.compress(&data).unwrap();
+        let max_compressed = max.compress(&data).unwrap();
+
+        // Higher compression level should produce smaller output
+        assert!(max_compressed.len() <= balanced_compressed.len());
+        assert!(balanced_compressed.len() <= fast_compressed.len());
+
+        // All should decompress to original
+        assert_eq!(fast.decompress(&fast_compressed).unwrap(), data);
+        assert_eq!(balanced.decompress(&balanced_compressed).unwrap(), data);
+        assert_eq!(max.decompress(&max_compressed).unwrap(), data);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_compressor() {
+        let compressor = BrotliCompressor::new();
+        let data = b"Hello, World! This is a test of brotli compression.".repeat(10);
+
+        let compressed = compressor.compress(&data).unwrap();
+        assert!(
+            compressed.len() < data.len(),
+            "Compressed data should be smaller"
+        );
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn test_brotli_compression_levels() {
+        let data = b"Hello, World! This is a test of brotli compression.".repeat(100);
+
+        let fast = BrotliCompressor::with_level(0);
+        let max = BrotliCompressor::with_level(11);
+
+        let fast_compressed = fast.compress(&data).unwrap();
+        let max_compressed = max.compress(&data).unwrap();
+
+        // Higher compression level should produce smaller output
+        assert!(max_compressed.len() <= fast_compressed.len());
+
+        // All should decompress to original
+        assert_eq!(fast.decompress(&fast_compressed).unwrap(), data);
+        assert_eq!(max.decompress(&max_compressed).unwrap(), data);
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn test_snappy_compressor() {
+        let compressor = SnappyCompressor;
+        let data = b"Hello, World! This is a test of snappy compression.".repeat(10);
+
+        let compressed = compressor.compress(&data).unwrap();
+        assert!(
+            compressed.len() < data.len(),
+            "Compressed data should be smaller"
+        );
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, data);
+        assert!(compressor.decompress(b"not snappy").is_err());
+    }
+
+    #[test]
+    fn test_adaptive_skips_small_data() {
+        let compressor = AdaptiveCompressor::new(PassthroughCompressor).threshold(16);
+        let data = b"small";
+
+        let stored = compressor.compress(data).unwrap();
+
+        assert_eq!(stored[..2], ADAPTIVE_MAGIC);
+        assert_eq!(stored[2], Codec::Passthrough.tag());
+        assert_eq!(&stored[3..], data);
+        assert_eq!(compressor.decompress(&stored).unwrap(), data);
+        assert!(compressor.decompress(&[]).is_err());
+        assert!(compressor.decompress(&ADAPTIVE_MAGIC).is_err());
+        assert!(compressor.decompress(&[0xc1, 0xa7, 0x7f, 1, 2]).is_err());
+        assert!(
+            compressor
+                .decompress(&[Codec::Passthrough.tag(), 1, 2])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_adaptive_fallback_decodes_untagged_data_starting_with_tag() {
+        let compressor =
+            AdaptiveCompressor::new(PassthroughCompressor).fallback(PassthroughCompressor);
+
+        for tag in [
+            Codec::Passthrough.tag(),
+            Codec::Zstd.tag(),
+            Codec::Custom.tag(),
+        ] {
+            let untagged = [tag, b'{', b'}'];
+            assert_eq!(compressor.decompress(&untagged).unwrap(), untagged);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_adaptive_threshold_and_ratio() {
+        let compressor = AdaptiveCompressor::new(ZstdCompressor::new()).threshold(64);
+        let small = b"{\"id\":1,\"name\":\"Alice\"}";
+        let large = b"Hello, World! This is a test of adaptive compression.".repeat(10);
+
+        let stored_small = compressor.compress(small).unwrap();
+        let stored_large = compressor.compress(&large).unwrap();
+
+        assert_eq!(stored_small[2], Codec::Passthrough.tag());
+        assert_eq!(stored_large[2], Codec::Zstd.tag());
+        assert!(stored_large.len() < large.len());
+        assert_eq!(compressor.decompress(&stored_small).unwrap(), small);
+        assert_eq!(compressor.decompress(&stored_large).unwrap(), large);
+
+        let demanding = AdaptiveCompressor::new(ZstdCompressor::new())
+            .threshold(0)
+            .min_ratio(1000.0);
+        assert_eq!(
+            demanding.compress(&large).unwrap()[2],
+            Codec::Passthrough.tag()
+        );
+    }
+
+    #[cfg(all(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_adaptive_decodes_values_of_other_codecs() {
+        let data = b"Hello, World! This is a test of adaptive compression.".repeat(10);
+        let before = AdaptiveCompressor::new(GzipCompressor::new());
+        let after = AdaptiveCompressor::new(ZstdCompressor::new()).fallback(GzipCompressor::new());
+
+        let old = before.compress(&data).unwrap();
+        let untagged = GzipCompressor::new().compress(&data).unwrap();
+
+        assert_eq!(old[2], Codec::Gzip.tag());
+        assert_eq!(after.decompress(&old).unwrap(), data);
+        assert_eq!(after.decompress(&untagged).unwrap(), data);
+        assert!(
+            AdaptiveCompressor::new(ZstdCompressor::new())
+                .decompress(&untagged)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    fn json_samples(generation: &str) -> Vec<Vec<u8>> {
+        (0..1000)
+            .map(|i| {
+                format!(
+                    r#"{{"id":{i},"generation":"{generation}","name":"user-{i}","email":"user-{i}@example.com","roles":["reader","writer"],"active":{}}}"#,
+                    i % 3 == 0
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_dictionary_improves_small_values() {
+        let samples = json_samples("first");
+        let dictionary = ZstdDictionary::train(&samples, 4096).unwrap();
+        let plain = ZstdCompressor::new();
+        let compressor = ZstdCompressor::new().dictionary(&dictionary);
+        let value = br#"{"id":5000,"generation":"first","name":"user-5000","email":"user-5000@example.com","roles":["reader","writer"],"active":false}"#;
+
+        let compressed = compressor.compress(value).unwrap();
+
+        assert!(compressed.len() < plain.compress(value).unwrap().len());
+        assert_eq!(
+            zstd::zstd_safe::get_dict_id_from_frame(&compressed).map(|id| id.get()),
+            Some(dictionary.id())
+        );
+        assert_eq!(compressor.decompress(&compressed).unwrap(), value);
+        assert!(plain.decompress(&compressed).is_err());
+        assert_eq!(
+            ZstdDictionary::new(dictionary.as_bytes().to_vec()).unwrap(),
+            dictionary
+        );
+        assert!(ZstdDictionary::new(b"raw content".to_vec()).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_dictionary_generations_coexist() {
+        let first = ZstdDictionary::train(&json_samples("first"), 4096).unwrap();
+        let second = ZstdDictionary::train(&json_samples("second"), 4096).unwrap();
+        let value = b"{\"id\":1,\"generation\":\"first\",\"name\":\"user-1\"}";
+
+        let old = ZstdCompressor::new()
+            .dictionary(&first)
+            .compress(value)
+            .unwrap();
+        let plain = ZstdCompressor::new().compress(value).unwrap();
+        let compressor = ZstdCompressor::new()
+            .dictionary(&second)
+            .previous_dictionary(&first);
+        let new = compressor.compress(value).unwrap();
+
+        assert_ne!(first.id(), second.id());
+        assert_eq!(compressor.decompress(&old).unwrap(), value);
+        assert_eq!(compressor.decompress(&new).unwrap(), value);
+        assert_eq!(compressor.decompress(&plain).unwrap(), value);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn test_zstd_dictionary_from_backend() {
+        use crate::Backend;
+        use crate::testing::HashMapBackend;
+        use hitbox_core::{CacheKey, CacheValue};
+
+        let backend = HashMapBackend::builder()
+            .compressor(ZstdCompressor::new())
+            .build();
+        let keys: Vec<_> = (0..1000)
+            .map(|i| CacheKey::from_str("user", &i.to_string()))
+            .collect();
+        for (key, sample) in keys.iter().zip(json_samples("stored")) {
+            let data = backend.compressor().compress(&sample).unwrap();
+            backend
+                .write(key, CacheValue::new(data, None, None), None)
+                .await
+                .unwrap();
+        }
+
+        let dictionary = ZstdDictionary::train_from_backend(&backend, &keys, 4096)
+            .await
+            .unwrap();
+
+        assert!(!dictionary.as_bytes().is_empty());
+        assert!(dictionary.as_bytes().len() <= 4096);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn test_zstd_dictionary_from_enveloped_backend() {
+        use crate::Backend;
+        use crate::envelope::Envelope;
+        use crate::serializer::JsonFormat;
+        use crate::testing::HashMapBackend;
+        use hitbox_core::{CacheKey, CacheValue};
+
+        let envelope = Envelope::new(JsonFormat);
+        let backend = HashMapBackend::builder()
+            .value_format(envelope.clone())
+            .compressor(ZstdCompressor::new())
+            .build();
+        let keys: Vec<_> = (0..1000)
+            .map(|i| CacheKey::from_str("user", &i.to_string()))
+            .collect();
+        for (key, sample) in keys.iter().zip(json_samples("stored")) {
+            let compressed = backend.compressor().compress(&sample).unwrap();
+            let data = envelope.seal(backend.compressor(), &compressed);
+            backend
+                .write(key, CacheValue::new(data, None, None), None)
+                .await
+                .unwrap();
+        }
+
+        let dictionary = ZstdDictionary::train_from_backend(&backend, &keys, 4096)
+            .await
+            .unwrap();
+
+        assert!(!dictionary.as_bytes().is_empty());
+    }
 }
@@ -0,0 +1,369 @@
+//! Encryption at rest of stored values.
+//!
+//! [`EncryptingBackend`] encrypts values after serialization and compression,
+//! right before they reach the wrapped backend, so a store shared with others
+//! only ever holds ciphertext.
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    sync::Arc,
+    time::Duration,
+};
+
+use aes_gcm::{
+    Aes256Gcm,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use async_trait::async_trait;
+use chacha20poly1305::ChaCha20Poly1305;
+use hitbox_core::{CacheKey, CacheValue};
+use thiserror::Error;
+
+use crate::{
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    serializer::{Format, Raw},
+};
+
+/// Size of encryption keys in bytes.
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+
+/// Authenticated cipher encrypting values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cipher {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+/// Error of encrypting or decrypting a value.
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("encryption key must be {ENCRYPTION_KEY_LEN} bytes, got {0}")]
+    InvalidKey(usize),
+    #[error("value is encrypted with unknown key {0}")]
+    UnknownKey(u32),
+    #[error("encryption key {0} is registered twice")]
+    DuplicateKey(u32),
+    #[error("encrypted value is truncated")]
+    Truncated,
+    #[error("failed to encrypt value")]
+    Encrypt,
+    #[error("failed to decrypt value, it was encrypted with another key or tampered with")]
+    Decrypt,
+}
+
+impl From<EncryptionError> for BackendError {
+    fn from(error: EncryptionError) -> Self {
+        BackendError::InternalError(Box::new(error))
+    }
+}
+
+enum Sealer {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(Box<ChaCha20Poly1305>),
+}
+
+/// Key encrypting values, recorded by its id in every value it encrypts.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    id: u32,
+    cipher: Cipher,
+    sealer: Arc<Sealer>,
+}
+
+impl EncryptionKey {
+    /// Key `id` of `cipher` made of [`ENCRYPTION_KEY_LEN`] bytes of key material.
+    pub fn new(id: u32, cipher: Cipher, key: &[u8]) -> Result<Self, EncryptionError> {
+        let invalid = |_| EncryptionError::InvalidKey(key.len());
+        let sealer = match cipher {
+            Cipher::Aes256Gcm => {
+                Sealer::Aes256Gcm(Box::new(Aes256Gcm::new_from_slice(key).map_err(invalid)?))
+            }
+            Cipher::ChaCha20Poly1305 => Sealer::ChaCha20Poly1305(Box::new(
+                ChaCha20Poly1305::new_from_slice(key).map_err(invalid)?,
+            )),
+        };
+        Ok(Self {
+            id,
+            cipher,
+            sealer: Arc::new(sealer),
+        })
+    }
+
+    /// Id of the key.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Cipher of the key.
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// Key id, random nonce and ciphertext of `data`, authenticating `aad`.
+    fn encrypt(&self, data: &[u8], aad: &[u8]) -> Result<Raw, EncryptionError> {
+        let payload = Payload { msg: data, aad };
+        let (nonce, ciphertext) = match self.sealer.as_ref() {
+            Sealer::Aes256Gcm(cipher) => {
+                let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                (nonce, cipher.encrypt(&nonce, payload))
+            }
+            Sealer::ChaCha20Poly1305(cipher) => {
+                let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+                (nonce, cipher.encrypt(&nonce, payload))
+            }
+        };
+        let ciphertext = ciphertext.map_err(|_| EncryptionError::Encrypt)?;
+
+        let mut sealed = Vec::with_capacity(KEY_ID_LEN + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&self.id.to_be_bytes());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypt `ciphertext` sealed with `nonce`, authenticating `aad`.
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Raw, EncryptionError> {
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
+        match self.sealer.as_ref() {
+            Sealer::Aes256Gcm(cipher) => cipher.decrypt(nonce.into(), payload),
+            Sealer::ChaCha20Poly1305(cipher) => cipher.decrypt(nonce.into(), payload),
+        }
+        .map_err(|_| EncryptionError::Decrypt)
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .field("cipher", &self.cipher)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Backend encrypting values stored in the wrapped backend.
+///
+/// Every value gets a fresh random nonce and is bound to its key: the key
+/// serialized with the key format of the wrapped backend is authenticated as
+/// associated data, so a value copied under another key fails to decrypt.
+/// Stored data starts with the id of the encrypting key, which lets keys be
+/// rotated: new values are encrypted with the current key, while values
+/// encrypted with a [`previous_key`](Self::previous_key) are still read until
+/// they expire.
+///
+/// ```ignore
+/// let backend = EncryptingBackend::new(RedisBackend::new(...)?, current)
+///     .previous_key(retired)?;
+/// ```
+pub struct EncryptingBackend<B> {
+    backend: B,
+    key: EncryptionKey,
+    keys: HashMap<u32, EncryptionKey>,
+}
+
+impl<B> EncryptingBackend<B> {
+    /// Wrap backend, encrypting values with `key`.
+    pub fn new(backend: B, key: EncryptionKey) -> Self {
+        let keys = HashMap::from([(key.id, key.clone())]);
+        Self { backend, key, keys }
+    }
+
+    /// Decrypt values encrypted with a previous key.
+    ///
+    /// Fails if a key with the same id is already registered, as values
+    /// encrypted with one of them could not be read.
+    pub fn previous_key(mut self, key: EncryptionKey) -> Result<Self, EncryptionError> {
+        match self.keys.entry(key.id) {
+            Entry::Occupied(_) => Err(EncryptionError::DuplicateKey(key.id)),
+            Entry::Vacant(entry) => {
+                entry.insert(key);
+                Ok(self)
+            }
+        }
+    }
+
+    /// Wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+}
+
+impl<B> EncryptingBackend<B>
+where
+    B: Backend,
+{
+    fn decrypt(&self, key: &CacheKey, data: &[u8]) -> BackendResult<Raw> {
+        if data.len() < KEY_ID_LEN + NONCE_LEN {
+            return Err(EncryptionError::Truncated.into());
+        }
+        let (id, rest) = data.split_at(KEY_ID_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let id = u32::from_be_bytes(id.try_into().expect("key id is four bytes"));
+        let encryption_key = self.keys.get(&id).ok_or(EncryptionError::UnknownKey(id))?;
+        let aad = self.backend.key_format().serialize(key)?;
+        Ok(encryption_key.decrypt(nonce, ciphertext, &aad)?)
+    }
+}
+
+impl<B> std::fmt::Debug for EncryptingBackend<B>
+where
+    B: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptingBackend")
+            .field("backend", &self.backend)
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<B> Backend for EncryptingBackend<B>
+where
+    B: Backend + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        match self.backend.read(key).await? {
+            Some(mut value) => {
+                value.data = self.decrypt(key, &value.data)?;
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        mut value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let aad = self.backend.key_format().serialize(key)?;
+        value.data = self.key.encrypt(&value.data, &aad)?;
+        self.backend.write(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.backend.remove(key).await
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        self.backend.value_format()
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        self.backend.key_format()
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        self.backend.compressor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HashMapBackend;
+
+    fn key() -> CacheKey {
+        CacheKey::from_str("key", "1")
+    }
+
+    fn value() -> CacheValue<Raw> {
+        CacheValue::new(b"{\"email\":\"user@example.com\"}".to_vec(), None, None)
+    }
+
+    fn encryption_key(id: u32, cipher: Cipher) -> EncryptionKey {
+        EncryptionKey::new(id, cipher, &[id as u8; ENCRYPTION_KEY_LEN]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_stores_ciphertext() {
+        for cipher in [Cipher::Aes256Gcm, Cipher::ChaCha20Poly1305] {
+            let backend = EncryptingBackend::new(HashMapBackend::new(), encryption_key(1, cipher));
+
+            backend.write(&key(), value(), None).await.unwrap();
+            backend
+                .write(&CacheKey::from_str("key", "2"), value(), None)
+                .await
+                .unwrap();
+
+            let stored = backend.inner().read(&key()).await.unwrap().unwrap();
+            let other = backend
+                .inner()
+                .read(&CacheKey::from_str("key", "2"))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(!stored.data.windows(5).any(|window| window == b"email"));
+            assert_ne!(stored.data, other.data, "nonces must differ per entry");
+            assert_eq!(backend.read(&key()).await.unwrap(), Some(value()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_value_is_bound_to_key() {
+        let backend =
+            EncryptingBackend::new(HashMapBackend::new(), encryption_key(1, Cipher::Aes256Gcm));
+        let other = CacheKey::from_str("key", "2");
+
+        backend.write(&key(), value(), None).await.unwrap();
+        let stored = backend.inner().read(&key()).await.unwrap().unwrap();
+        backend.inner().write(&other, stored, None).await.unwrap();
+
+        assert!(backend.read(&other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation() {
+        let storage = HashMapBackend::new();
+        let old = EncryptingBackend::new(storage.clone(), encryption_key(1, Cipher::Aes256Gcm));
+        let rotated =
+            EncryptingBackend::new(storage.clone(), encryption_key(2, Cipher::ChaCha20Poly1305))
+                .previous_key(encryption_key(1, Cipher::Aes256Gcm))
+                .unwrap();
+        let new = CacheKey::from_str("key", "2");
+
+        old.write(&key(), value(), None).await.unwrap();
+        rotated.write(&new, value(), None).await.unwrap();
+
+        assert_eq!(rotated.read(&key()).await.unwrap(), Some(value()));
+        assert_eq!(rotated.read(&new).await.unwrap(), Some(value()));
+        assert!(matches!(
+            old.read(&new).await,
+            Err(BackendError::InternalError(_))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_key_ids_are_rejected() {
+        let backend = || {
+            EncryptingBackend::new(HashMapBackend::new(), encryption_key(1, Cipher::Aes256Gcm))
+                .previous_key(encryption_key(2, Cipher::Aes256Gcm))
+                .unwrap()
+        };
+
+        assert!(matches!(
+            backend().previous_key(encryption_key(1, Cipher::ChaCha20Poly1305)),
+            Err(EncryptionError::DuplicateKey(1))
+        ));
+        assert!(matches!(
+            backend().previous_key(encryption_key(2, Cipher::ChaCha20Poly1305)),
+            Err(EncryptionError::DuplicateKey(2))
+        ));
+    }
+
+    #[test]
+    fn test_key_length_is_checked() {
+        assert!(matches!(
+            EncryptionKey::new(1, Cipher::Aes256Gcm, &[0; 16]),
+            Err(EncryptionError::InvalidKey(16))
+        ));
+    }
+}
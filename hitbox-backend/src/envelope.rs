@@ -0,0 +1,446 @@
+//! Self-describing envelope around stored values.
+//!
+//! An envelope header records the format and the compressor a value was
+//! written with, so values written before a format or compression change are
+//! still read after it, without flushing the cache.
+use std::{collections::HashMap, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::{
+    AdaptiveCompressor, BackendError, Codec, CompressionError, Compressor, PassthroughCompressor,
+    serializer::{BincodeFormat, Format, FormatError, FormatId, JsonFormat, Raw},
+};
+
+/// Magic bytes opening every envelope.
+pub const ENVELOPE_MAGIC: [u8; 4] = [0x89, b'H', b'B', b'E'];
+
+/// Version of the envelope layout written by this crate.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Header written in front of enveloped values.
+///
+/// Layout: four magic bytes, envelope version, [`FormatId`] tag and [`Codec`]
+/// tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub format: FormatId,
+    pub codec: Codec,
+}
+
+impl EnvelopeHeader {
+    /// Size of the header in bytes.
+    pub const LEN: usize = ENVELOPE_MAGIC.len() + 3;
+
+    /// Header of a value written with `format` and `codec`.
+    pub fn new(format: FormatId, codec: Codec) -> Self {
+        Self { format, codec }
+    }
+
+    /// Header bytes.
+    pub fn to_bytes(self) -> [u8; Self::LEN] {
+        let mut header = [0; Self::LEN];
+        header[..ENVELOPE_MAGIC.len()].copy_from_slice(&ENVELOPE_MAGIC);
+        header[ENVELOPE_MAGIC.len()..].copy_from_slice(&[
+            ENVELOPE_VERSION,
+            self.format.tag(),
+            self.codec.tag(),
+        ]);
+        header
+    }
+
+    /// Header in front of `data`, `None` if `data` doesn't start with the
+    /// envelope magic.
+    ///
+    /// # Errors
+    ///
+    /// Fails on an unknown envelope version, format or codec tag.
+    pub fn parse(data: &[u8]) -> Option<Result<Self, FormatError>> {
+        if !data.starts_with(&ENVELOPE_MAGIC) {
+            return None;
+        }
+        let header = match data.get(ENVELOPE_MAGIC.len()..Self::LEN) {
+            Some(&[ENVELOPE_VERSION, format, codec]) => {
+                match (FormatId::from_tag(format), Codec::from_tag(codec)) {
+                    (Some(format), Some(codec)) => Ok(Self { format, codec }),
+                    _ => Err(format!(
+                        "unknown format tag {format:#04x} or codec tag {codec:#04x}"
+                    )),
+                }
+            }
+            Some(&[version, ..]) => Err(format!("unsupported envelope version {version}")),
+            _ => Err("truncated envelope header".to_string()),
+        };
+        Some(
+            header
+                .map_err(|error| FormatError::Deserialize(Box::new(std::io::Error::other(error)))),
+        )
+    }
+}
+
+/// Format writing values in an envelope and reading them with the format and
+/// compressor recorded in its header.
+///
+/// Values are written with the wrapped format and the compressor of the
+/// backend. On read, a header naming them is served by them as well, other
+/// headers by the registered formats and compressors: the bundled ones with
+/// their default settings are registered from the start, and configured ones,
+/// e.g. a [`ZstdCompressor`](crate::ZstdCompressor) with dictionaries, replace
+/// them with [`register_compressor`](Self::register_compressor). Values without an
+/// envelope, written before it was introduced, are read with the
+/// [`fallback`](Self::fallback) format, the wrapped one by default, and the
+/// compressor of the backend.
+///
+/// ```ignore
+/// // Values written as JSON before the switch are still served.
+/// let backend = MokaBackend::builder(1024)
+///     .value_format(Envelope::new(BincodeFormat).fallback(JsonFormat))
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct Envelope {
+    format: Arc<dyn Format>,
+    fallback: Option<Arc<dyn Format>>,
+    formats: HashMap<FormatId, Arc<dyn Format>>,
+    compressors: HashMap<Codec, Arc<dyn Compressor>>,
+}
+
+impl Envelope {
+    /// Envelope writing values with `format`.
+    pub fn new(format: impl Format + 'static) -> Self {
+        let envelope = Self {
+            format: Arc::new(format),
+            fallback: None,
+            formats: HashMap::new(),
+            compressors: HashMap::new(),
+        }
+        .register_format(JsonFormat)
+        .register_format(BincodeFormat)
+        .register_compressor(PassthroughCompressor)
+        .register_compressor(AdaptiveCompressor::new(PassthroughCompressor));
+        #[cfg(feature = "msgpack")]
+        let envelope = envelope.register_format(crate::serializer::MessagePackFormat);
+        #[cfg(feature = "cbor")]
+        let envelope = envelope.register_format(crate::serializer::CborFormat);
+        #[cfg(feature = "postcard")]
+        let envelope = envelope.register_format(crate::serializer::PostcardFormat);
+        #[cfg(feature = "gzip")]
+        let envelope = envelope.register_compressor(crate::GzipCompressor::new());
+        #[cfg(feature = "zstd")]
+        let envelope = envelope.register_compressor(crate::ZstdCompressor::new());
+        #[cfg(feature = "lz4")]
+        let envelope = envelope.register_compressor(crate::Lz4Compressor::new());
+        #[cfg(feature = "brotli")]
+        let envelope = envelope.register_compressor(crate::BrotliCompressor::new());
+        #[cfg(feature = "snappy")]
+        let envelope = envelope.register_compressor(crate::SnappyCompressor);
+        envelope
+    }
+
+    /// Read values recorded with the id of `format` with it, replacing the
+    /// format registered for that id.
+    pub fn register_format(mut self, format: impl Format + 'static) -> Self {
+        self.formats.insert(format.format_id(), Arc::new(format));
+        self
+    }
+
+    /// Read values recorded with the codec of `compressor` with it, replacing
+    /// the compressor registered for that codec.
+    ///
+    /// Register compressors configured like the ones values were written with,
+    /// e.g. a [`ZstdCompressor`](crate::ZstdCompressor) knowing their
+    /// dictionaries or an [`AdaptiveCompressor`] wrapping it.
+    pub fn register_compressor(mut self, compressor: impl Compressor + 'static) -> Self {
+        self.compressors
+            .insert(compressor.codec(), Arc::new(compressor));
+        self
+    }
+
+    /// Set format reading values stored without an envelope.
+    pub fn fallback(mut self, format: impl Format + 'static) -> Self {
+        self.fallback = Some(Arc::new(format));
+        self
+    }
+
+    /// Prepend the header of values written with `compressor` to `data`.
+    pub(crate) fn seal(&self, compressor: &dyn Compressor, data: &[u8]) -> Raw {
+        let header = EnvelopeHeader::new(self.format.format_id(), compressor.codec());
+        let mut sealed = Vec::with_capacity(EnvelopeHeader::LEN + data.len());
+        sealed.extend_from_slice(&header.to_bytes());
+        sealed.extend_from_slice(data);
+        sealed
+    }
+
+    /// Strip the header from `data` and pick the format and compressor it was
+    /// written with, given the compressor of the backend.
+    pub(crate) fn open<'a>(
+        &'a self,
+        data: &mut Bytes,
+        compressor: &'a dyn Compressor,
+    ) -> Result<(&'a dyn Format, &'a dyn Compressor), BackendError> {
+        let header = match EnvelopeHeader::parse(data) {
+            Some(header) => header?,
+            None => return Ok((self.fallback.as_deref().unwrap_or(self), compressor)),
+        };
+        *data = data.slice(EnvelopeHeader::LEN..);
+
+        let format = if header.format == self.format.format_id() {
+            self.format.as_ref()
+        } else {
+            self.formats
+                .get(&header.format)
+                .map(AsRef::as_ref)
+                .ok_or_else(|| {
+                    FormatError::Deserialize(Box::new(std::io::Error::other(format!(
+                        "no format registered for {:?} values",
+                        header.format
+                    ))))
+                })?
+        };
+        let compressor = if header.codec == compressor.codec() {
+            compressor
+        } else {
+            self.compressors
+                .get(&header.codec)
+                .map(AsRef::as_ref)
+                .ok_or_else(|| {
+                    CompressionError::DecompressionFailed(format!(
+                        "no compressor registered for {:?} data",
+                        header.codec
+                    ))
+                })?
+        };
+        Ok((format, compressor))
+    }
+}
+
+impl std::fmt::Debug for Envelope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Envelope")
+            .field("format", &self.format)
+            .field("fallback", &self.fallback)
+            .field("formats", &self.formats.keys())
+            .field("compressors", &self.compressors.keys())
+            .finish()
+    }
+}
+
+impl Format for Envelope {
+    fn erased_serialize(&self, value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
+        self.format.erased_serialize(value)
+    }
+
+    fn with_deserializer(
+        &self,
+        data: &[u8],
+        f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+    ) -> Result<(), FormatError> {
+        self.format.with_deserializer(data, f)
+    }
+
+    fn serialize_typed(&self, value: &dyn std::any::Any) -> Option<Result<Raw, FormatError>> {
+        self.format.serialize_typed(value)
+    }
+
+    fn deserialize_typed(
+        &self,
+        data: &Bytes,
+        type_id: std::any::TypeId,
+    ) -> Option<Result<Box<dyn std::any::Any + Send>, FormatError>> {
+        self.format.deserialize_typed(data, type_id)
+    }
+
+    fn format_id(&self) -> FormatId {
+        self.format.format_id()
+    }
+
+    fn envelope(&self) -> Option<&Envelope> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use hitbox_core::{
+        CacheKey, CachePolicy, CacheValue, CacheableResponse, EntityPolicyConfig, Predicate,
+        ResponseCachePolicy,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{Backend, CacheBackend, testing::HashMapBackend};
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Value {
+        name: String,
+        index: u8,
+    }
+
+    #[async_trait]
+    impl CacheableResponse for Value {
+        type Cached = Self;
+        type Subject = Self;
+
+        async fn cache_policy<P>(self, _: P, _: &EntityPolicyConfig) -> ResponseCachePolicy<Self>
+        where
+            P: Predicate<Subject = Self::Subject> + Send + Sync,
+        {
+            unimplemented!()
+        }
+
+        async fn into_cached(self) -> CachePolicy<Self::Cached, Self> {
+            unimplemented!()
+        }
+
+        async fn from_cached(cached: Self::Cached) -> Self {
+            cached
+        }
+    }
+
+    fn key() -> CacheKey {
+        CacheKey::from_str("key", "1")
+    }
+
+    fn value() -> CacheValue<Value> {
+        CacheValue::new(
+            Value {
+                name: "value".to_owned(),
+                index: 7,
+            },
+            None,
+            None,
+        )
+    }
+
+    /// Copy the stored value of `from` into `to`, as if `to` was redeployed
+    /// over the same storage.
+    async fn migrate(from: &impl Backend, to: &impl Backend) {
+        let raw = from.read(&key()).await.unwrap().unwrap();
+        to.write(&key(), raw, None).await.unwrap();
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = EnvelopeHeader::new(FormatId::Bincode, Codec::Zstd);
+        let mut data = header.to_bytes().to_vec();
+        data.extend_from_slice(b"payload");
+
+        assert_eq!(EnvelopeHeader::parse(&data).unwrap().unwrap(), header);
+        assert!(EnvelopeHeader::parse(b"{\"name\":\"value\"}").is_none());
+
+        data[ENVELOPE_MAGIC.len()] = ENVELOPE_VERSION + 1;
+        assert!(EnvelopeHeader::parse(&data).unwrap().is_err());
+        assert!(EnvelopeHeader::parse(&ENVELOPE_MAGIC).unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_format_change_without_flush() {
+        let json = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .build();
+        let bincode = HashMapBackend::builder()
+            .value_format(Envelope::new(BincodeFormat))
+            .build();
+
+        json.set::<Value>(&key(), &value(), None).await.unwrap();
+        let stored = json.read(&key()).await.unwrap().unwrap();
+        assert_eq!(
+            EnvelopeHeader::parse(&stored.data).unwrap().unwrap(),
+            EnvelopeHeader::new(FormatId::Json, Codec::Passthrough)
+        );
+        migrate(&json, &bincode).await;
+
+        assert_eq!(bincode.get::<Value>(&key()).await.unwrap(), Some(value()));
+    }
+
+    #[tokio::test]
+    async fn test_values_without_envelope_use_fallback() {
+        let bare = HashMapBackend::new();
+        let enveloped = HashMapBackend::builder()
+            .value_format(Envelope::new(BincodeFormat).fallback(JsonFormat))
+            .build();
+
+        bare.set::<Value>(&key(), &value(), None).await.unwrap();
+        migrate(&bare, &enveloped).await;
+
+        assert_eq!(enveloped.get::<Value>(&key()).await.unwrap(), Some(value()));
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_codec_fails() {
+        let backend = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .build();
+        let mut data = EnvelopeHeader::new(FormatId::Json, Codec::Custom)
+            .to_bytes()
+            .to_vec();
+        data.extend_from_slice(b"{}");
+
+        backend
+            .write(&key(), CacheValue::new(data, None, None), None)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            backend.get::<Value>(&key()).await,
+            Err(BackendError::CompressionError(_))
+        ));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn test_adaptive_compressor_is_recorded() {
+        let adaptive = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .compressor(AdaptiveCompressor::new(crate::ZstdCompressor::new()).threshold(0))
+            .build();
+        let plain = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .build();
+
+        adaptive.set::<Value>(&key(), &value(), None).await.unwrap();
+        let stored = adaptive.read(&key()).await.unwrap().unwrap();
+        assert_eq!(
+            EnvelopeHeader::parse(&stored.data).unwrap().unwrap(),
+            EnvelopeHeader::new(FormatId::Json, Codec::Adaptive)
+        );
+        migrate(&adaptive, &plain).await;
+
+        assert_eq!(plain.get::<Value>(&key()).await.unwrap(), Some(value()));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[tokio::test]
+    async fn test_registered_compressor_with_dictionary() {
+        use crate::{ZstdCompressor, ZstdDictionary};
+
+        let samples: Vec<_> = (0..1000)
+            .map(|index| format!(r#"{{"name":"value-{index}","index":{}}}"#, index % 256))
+            .collect();
+        let dictionary = ZstdDictionary::train(&samples, 4096).unwrap();
+        let zstd = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .compressor(ZstdCompressor::new().dictionary(&dictionary))
+            .build();
+        let default = HashMapBackend::builder()
+            .value_format(Envelope::new(JsonFormat))
+            .build();
+        let registered = HashMapBackend::builder()
+            .value_format(
+                Envelope::new(JsonFormat)
+                    .register_compressor(ZstdCompressor::new().dictionary(&dictionary)),
+            )
+            .build();
+
+        zstd.set::<Value>(&key(), &value(), None).await.unwrap();
+        migrate(&zstd, &default).await;
+        migrate(&zstd, &registered).await;
+
+        assert!(default.get::<Value>(&key()).await.is_err());
+        assert_eq!(
+            registered.get::<Value>(&key()).await.unwrap(),
+            Some(value())
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Integrity checks of stored values.
+//!
+//! [`IntegrityBackend`] stores a checksum with every value and verifies it on
+//! read, so a truncated write or a flipped bit in the storage surfaces as an
+//! error instead of bytes which may still deserialize into garbage.
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use hitbox_core::{CacheKey, CacheValue};
+
+use crate::{
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, IntegrityError,
+    serializer::{Format, Raw},
+};
+
+/// Checksum algorithm of stored values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Checksum {
+    /// 64-bit XXH3.
+    #[default]
+    Xxh3,
+    /// CRC-32C (Castagnoli), hardware accelerated on most CPUs.
+    Crc32c,
+}
+
+impl Checksum {
+    /// Tag identifying the algorithm in stored data.
+    pub fn tag(self) -> u8 {
+        match self {
+            Checksum::Xxh3 => 0,
+            Checksum::Crc32c => 1,
+        }
+    }
+
+    /// Algorithm identified by `tag`.
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Checksum::Xxh3),
+            1 => Some(Checksum::Crc32c),
+            _ => None,
+        }
+    }
+
+    /// Size of the checksum in bytes.
+    fn len(self) -> usize {
+        match self {
+            Checksum::Xxh3 => 8,
+            Checksum::Crc32c => 4,
+        }
+    }
+
+    /// Checksum of `data`.
+    pub fn digest(self, data: &[u8]) -> u64 {
+        match self {
+            Checksum::Xxh3 => xxhash_rust::xxh3::xxh3_64(data),
+            Checksum::Crc32c => crc32c::crc32c(data).into(),
+        }
+    }
+}
+
+/// Observer notified with the key of every corrupted entry and what was wrong
+/// with it.
+///
+/// Intended for metrics and alerting, e.g. counting corrupted entries per
+/// backend.
+pub type CorruptionObserver = Arc<dyn Fn(&CacheKey, &IntegrityError) + Send + Sync>;
+
+/// Backend verifying integrity of values stored in the wrapped backend.
+///
+/// Stored data starts with the tag of the checksum algorithm followed by the
+/// checksum of the value, so the algorithm can change without flushing the
+/// cache. A value failing verification is evicted from the wrapped backend and
+/// reported as [`BackendError::IntegrityError`]. With the `metrics` feature
+/// corrupted entries are also counted by the `cache_corrupted_count` counter.
+///
+/// ```ignore
+/// let backend = IntegrityBackend::new(FeOxDbBackend::open(...)?, Checksum::Crc32c)
+///     .observer(|key, error| tracing::warn!(?key, %error, "corrupted cache entry"));
+/// ```
+pub struct IntegrityBackend<B> {
+    backend: B,
+    checksum: Checksum,
+    observer: Option<CorruptionObserver>,
+}
+
+impl<B> IntegrityBackend<B> {
+    /// Wrap backend, checksumming values with `checksum`.
+    pub fn new(backend: B, checksum: Checksum) -> Self {
+        Self {
+            backend,
+            checksum,
+            observer: None,
+        }
+    }
+
+    /// Set observer notified about every corrupted entry.
+    pub fn observer<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&CacheKey, &IntegrityError) + Send + Sync + 'static,
+    {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    /// Checksum tag, checksum and `data`.
+    fn seal(&self, data: &[u8]) -> Raw {
+        let checksum = self.checksum.digest(data).to_be_bytes();
+        let len = self.checksum.len();
+        let mut sealed = Vec::with_capacity(1 + len + data.len());
+        sealed.push(self.checksum.tag());
+        sealed.extend_from_slice(&checksum[checksum.len() - len..]);
+        sealed.extend_from_slice(data);
+        sealed
+    }
+
+    /// Value of sealed `data` if its checksum matches.
+    fn verify(data: &[u8]) -> Result<&[u8], IntegrityError> {
+        let (&tag, rest) = data.split_first().ok_or(IntegrityError::Truncated)?;
+        let checksum = Checksum::from_tag(tag).ok_or(IntegrityError::UnknownChecksum(tag))?;
+        if rest.len() < checksum.len() {
+            return Err(IntegrityError::Truncated);
+        }
+        let (stored, value) = rest.split_at(checksum.len());
+        let expected = stored
+            .iter()
+            .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+        let actual = checksum.digest(value);
+        if actual != expected {
+            return Err(IntegrityError::Mismatch { expected, actual });
+        }
+        Ok(value)
+    }
+}
+
+impl<B> IntegrityBackend<B>
+where
+    B: Backend,
+{
+    /// Evict corrupted entry and report it.
+    async fn corrupted(&self, key: &CacheKey, error: IntegrityError) -> BackendError {
+        // The entry is reported anyway, a failed eviction only means it will
+        // be reported again on the next read.
+        let _ = self.backend.remove(key).await;
+        #[cfg(feature = "metrics")]
+        metrics::counter!(*crate::metrics::CACHE_CORRUPTED_COUNTER).increment(1);
+        if let Some(observer) = &self.observer {
+            observer(key, &error);
+        }
+        error.into()
+    }
+}
+
+impl<B> std::fmt::Debug for IntegrityBackend<B>
+where
+    B: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IntegrityBackend")
+            .field("backend", &self.backend)
+            .field("checksum", &self.checksum)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<B> Backend for IntegrityBackend<B>
+where
+    B: Backend + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        match self.backend.read(key).await? {
+            Some(mut value) => match Self::verify(&value.data) {
+                Ok(data) => {
+                    value.data = data.to_vec();
+                    Ok(Some(value))
+                }
+                Err(error) => Err(self.corrupted(key, error).await),
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        mut value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        value.data = self.seal(&value.data);
+        self.backend.write(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.backend.remove(key).await
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        self.backend.value_format()
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        self.backend.key_format()
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        self.backend.compressor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::composite::Operation;
+    use crate::testing::{ChaosBackend, Fault, HashMapBackend, Trigger};
+
+    fn key() -> CacheKey {
+        CacheKey::from_str("key", "1")
+    }
+
+    fn value() -> CacheValue<Raw> {
+        CacheValue::new(b"{\"status\":200}".to_vec(), None, None)
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip() {
+        for checksum in [Checksum::Xxh3, Checksum::Crc32c] {
+            let backend = IntegrityBackend::new(HashMapBackend::new(), checksum);
+
+            backend.write(&key(), value(), None).await.unwrap();
+
+            let stored = backend.inner().read(&key()).await.unwrap().unwrap();
+            assert_eq!(stored.data[0], checksum.tag());
+            assert_eq!(stored.data.len(), value().data.len() + 1 + checksum.len());
+            assert_eq!(backend.read(&key()).await.unwrap(), Some(value()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checksum_change_reads_old_values() {
+        let storage = HashMapBackend::new();
+        let old = IntegrityBackend::new(storage.clone(), Checksum::Crc32c);
+        let new = IntegrityBackend::new(storage, Checksum::Xxh3);
+
+        old.write(&key(), value(), None).await.unwrap();
+
+        assert_eq!(new.read(&key()).await.unwrap(), Some(value()));
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_entry_is_evicted_and_reported() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let backend = IntegrityBackend::new(HashMapBackend::new(), Checksum::Xxh3).observer({
+            let reported = reported.clone();
+            move |key: &CacheKey, error: &IntegrityError| {
+                reported
+                    .lock()
+                    .unwrap()
+                    .push((key.clone(), error.to_string()))
+            }
+        });
+
+        backend.write(&key(), value(), None).await.unwrap();
+        let mut stored = backend.inner().read(&key()).await.unwrap().unwrap();
+        *stored.data.last_mut().unwrap() ^= 0x01;
+        backend.inner().write(&key(), stored, None).await.unwrap();
+
+        assert!(matches!(
+            backend.read(&key()).await,
+            Err(BackendError::IntegrityError(
+                IntegrityError::Mismatch { .. }
+            ))
+        ));
+        assert_eq!(backend.inner().read(&key()).await.unwrap(), None);
+        assert_eq!(backend.read(&key()).await.unwrap(), None);
+        assert_eq!(reported.lock().unwrap().len(), 1);
+        assert_eq!(reported.lock().unwrap()[0].0, key());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_and_unknown_values() {
+        let backend = IntegrityBackend::new(HashMapBackend::new(), Checksum::Crc32c);
+
+        for (data, expected) in [
+            (vec![], IntegrityError::Truncated),
+            (
+                vec![Checksum::Crc32c.tag(), 0, 0],
+                IntegrityError::Truncated,
+            ),
+            (
+                vec![0xff, 0, 0, 0, 0],
+                IntegrityError::UnknownChecksum(0xff),
+            ),
+        ] {
+            let stored = CacheValue::new(data, None, None);
+            backend.inner().write(&key(), stored, None).await.unwrap();
+
+            match backend.read(&key()).await {
+                Err(BackendError::IntegrityError(error)) => assert_eq!(error, expected),
+                result => panic!("expected integrity error, got {result:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detects_storage_corruption() {
+        let backend = IntegrityBackend::new(
+            ChaosBackend::new(HashMapBackend::new()).fault(
+                Operation::Read,
+                Trigger::Always,
+                Fault::Corrupt,
+            ),
+            Checksum::Xxh3,
+        );
+
+        backend.write(&key(), value(), None).await.unwrap();
+
+        assert!(matches!(
+            backend.read(&key()).await,
+            Err(BackendError::IntegrityError(_))
+        ));
+    }
+}
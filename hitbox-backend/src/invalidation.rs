@@ -0,0 +1,223 @@
+//! Invalidation of cached values across processes.
+//!
+//! When every process keeps its own in-memory cache, removing a key in one of
+//! them leaves stale copies in the others. An [`InvalidationBus`] carries
+//! [`Invalidation`] events between processes: [`InvalidatingBackend`] publishes
+//! them when keys are removed or the cache is cleared, and each process
+//! subscribes to the bus and evicts the keys from its local cache.
+//!
+//! Invalidation by tag or key prefix is out of scope: there is no event for it,
+//! so such keys have to be removed one by one or the cache cleared.
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream};
+use hitbox_core::{CacheKey, CacheValue};
+use tokio::sync::broadcast;
+
+use crate::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    serializer::{Format, FormatError, Raw},
+};
+
+/// Event published on an [`InvalidationBus`].
+#[derive(Debug, Clone, PartialEq, Eq, bitcode::Encode, bitcode::Decode)]
+pub enum Invalidation {
+    /// Single key was removed.
+    Key(CacheKey),
+    /// Every key must be evicted, e.g. after a clear or missed events.
+    All,
+}
+
+impl Invalidation {
+    /// Encode event for transports carrying raw bytes.
+    pub fn encode(&self) -> Raw {
+        bitcode::encode(self)
+    }
+
+    /// Decode event encoded with [`Invalidation::encode`].
+    pub fn decode(data: &[u8]) -> Result<Self, FormatError> {
+        bitcode::decode(data).map_err(|err| FormatError::Deserialize(Box::new(err)))
+    }
+}
+
+/// Stream of events received from an [`InvalidationBus`].
+///
+/// The stream ends when the subscription is lost; events published meanwhile
+/// are not delivered, so subscribers should resubscribe and evict everything.
+pub type InvalidationStream = BoxStream<'static, Invalidation>;
+
+/// Transport delivering invalidation events to every subscribed process.
+#[async_trait]
+pub trait InvalidationBus: Send + Sync {
+    /// Publish event to all subscribers, including the current process.
+    async fn publish(&self, invalidation: Invalidation) -> BackendResult<()>;
+
+    /// Subscribe to events published after this call.
+    async fn subscribe(&self) -> BackendResult<InvalidationStream>;
+}
+
+/// Shared, type-erased invalidation bus.
+pub type SharedBus = Arc<dyn InvalidationBus + 'static>;
+
+/// Default capacity of [`LocalBus`] channel.
+pub const DEFAULT_LOCAL_BUS_CAPACITY: usize = 1024;
+
+/// In-process invalidation bus based on a broadcast channel.
+///
+/// Useful for tests and for several caches living in one process. A subscriber
+/// which falls behind by more than the channel capacity receives
+/// [`Invalidation::All`] instead of the missed events.
+#[derive(Debug, Clone)]
+pub struct LocalBus {
+    sender: broadcast::Sender<Invalidation>,
+}
+
+impl LocalBus {
+    /// Create bus keeping up to `capacity` undelivered events per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+}
+
+impl Default for LocalBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCAL_BUS_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for LocalBus {
+    async fn publish(&self, invalidation: Invalidation) -> BackendResult<()> {
+        // Sending fails only when nobody is subscribed, which is fine.
+        let _ = self.sender.send(invalidation);
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> BackendResult<InvalidationStream> {
+        let receiver = self.sender.subscribe();
+        let events = stream::unfold(receiver, |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(invalidation) => Some((invalidation, receiver)),
+                Err(broadcast::error::RecvError::Lagged(_)) => Some((Invalidation::All, receiver)),
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+/// Backend publishing removed keys on an [`InvalidationBus`].
+///
+/// Reads and writes go straight to the wrapped backend. A key is published after
+/// it was removed from the wrapped backend, whether it was present or not, and
+/// [`clear`](Self::clear) publishes [`Invalidation::All`].
+pub struct InvalidatingBackend<B> {
+    backend: B,
+    bus: SharedBus,
+}
+
+impl<B> InvalidatingBackend<B> {
+    /// Wrap backend, publishing its removals on the bus.
+    pub fn new(backend: B, bus: SharedBus) -> Self {
+        Self { backend, bus }
+    }
+
+    /// Wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    /// Make every subscriber evict all its entries.
+    ///
+    /// [`Backend`] has no clear operation, so a shared wrapped backend is
+    /// cleared with its own API before calling this.
+    pub async fn clear(&self) -> BackendResult<()> {
+        self.bus.publish(Invalidation::All).await
+    }
+}
+
+impl<B> std::fmt::Debug for InvalidatingBackend<B>
+where
+    B: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InvalidatingBackend")
+            .field("backend", &self.backend)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<B> Backend for InvalidatingBackend<B>
+where
+    B: Backend + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        self.backend.read(key).await
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        self.backend.write(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let status = self.backend.remove(key).await?;
+        self.bus.publish(Invalidation::Key(key.clone())).await?;
+        Ok(status)
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        self.backend.value_format()
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        self.backend.key_format()
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        self.backend.compressor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let invalidation = Invalidation::Key(CacheKey::from_str("key", "1"));
+
+        let decoded = Invalidation::decode(&invalidation.encode()).unwrap();
+
+        assert_eq!(decoded, invalidation);
+        assert!(Invalidation::decode(b"garbage").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_bus_reports_lag() {
+        let bus = LocalBus::new(1);
+        let mut events = bus.subscribe().await.unwrap();
+
+        bus.publish(Invalidation::Key(CacheKey::from_str("key", "1")))
+            .await
+            .unwrap();
+        bus.publish(Invalidation::Key(CacheKey::from_str("key", "2")))
+            .await
+            .unwrap();
+
+        assert_eq!(events.next().await, Some(Invalidation::All));
+        assert_eq!(
+            events.next().await,
+            Some(Invalidation::Key(CacheKey::from_str("key", "2")))
+        );
+    }
+}
@@ -3,19 +3,53 @@
 //!
 //! If you want implement your own backend, you in the right place.
 mod backend;
+pub mod composite;
 pub mod compressor;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod envelope;
+#[cfg(feature = "integrity")]
+pub mod integrity;
+pub mod invalidation;
 mod key;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod serializer;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod testing;
+mod ttl;
 
 pub use backend::{Backend, BackendResult, CacheBackend};
+pub use composite::{
+    CompositionError, FailoverBackend, FailoverBackendBuilder, ReplicatedBackend,
+    ReplicatedBackendBuilder, ShardedBackend, ShardedBackendBuilder, SharedBackend,
+};
+#[cfg(feature = "brotli")]
+pub use compressor::BrotliCompressor;
 #[cfg(feature = "gzip")]
 pub use compressor::GzipCompressor;
+#[cfg(feature = "lz4")]
+pub use compressor::Lz4Compressor;
+#[cfg(feature = "snappy")]
+pub use compressor::SnappyCompressor;
+pub use compressor::{
+    AdaptiveCompressor, Codec, CompressionError, Compressor, DEFAULT_COMPRESSION_THRESHOLD,
+    PassthroughCompressor,
+};
 #[cfg(feature = "zstd")]
-pub use compressor::ZstdCompressor;
-pub use compressor::{CompressionError, Compressor, PassthroughCompressor};
+pub use compressor::{ZstdCompressor, ZstdDictionary};
+#[cfg(feature = "encryption")]
+pub use encryption::{Cipher, EncryptingBackend, EncryptionError, EncryptionKey};
+pub use envelope::{Envelope, EnvelopeHeader};
+#[cfg(feature = "integrity")]
+pub use integrity::{Checksum, CorruptionObserver, IntegrityBackend};
+pub use invalidation::{
+    InvalidatingBackend, Invalidation, InvalidationBus, InvalidationStream, LocalBus, SharedBus,
+};
 pub use key::{CacheKeyFormat, KeySerializer, UrlEncodedKeySerializer};
 use serializer::FormatError;
 use thiserror::Error;
+pub use ttl::{entry_deadline, entry_ttl};
 
 /// Proxy Error describes general groups of errors in backend interaction process.
 #[derive(Debug, Error)]
@@ -34,12 +68,27 @@ pub enum BackendError {
     /// Compressing\Decompressing data error.
     #[error(transparent)]
     CompressionError(#[from] CompressionError),
+    /// Stored value failed integrity verification and was evicted, see
+    /// `IntegrityBackend` of the `integrity` feature.
+    #[error(transparent)]
+    IntegrityError(#[from] IntegrityError),
 
     /// DEBUG @TODO: remove
     #[error("test")]
     Test(u8),
 }
 
+/// Error of verifying a stored value.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IntegrityError {
+    #[error("stored value is too short to hold a checksum")]
+    Truncated,
+    #[error("stored value has unknown checksum algorithm {0}")]
+    UnknownChecksum(u8),
+    #[error("stored value is corrupted: checksum {actual:#x}, expected {expected:#x}")]
+    Mismatch { expected: u64, actual: u64 },
+}
+
 /// Status of deleting result.
 #[derive(Debug, PartialEq, Eq)]
 pub enum DeleteStatus {
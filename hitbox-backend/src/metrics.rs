@@ -0,0 +1,13 @@
+//! Metrics declaration of backend stages.
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Track number of corrupted entries evicted by the integrity stage.
+    pub static ref CACHE_CORRUPTED_COUNTER: &'static str = {
+        metrics::describe_counter!(
+            "cache_corrupted_count",
+            "Total number of cache entries failing integrity verification."
+        );
+        "cache_corrupted_count"
+    };
+}
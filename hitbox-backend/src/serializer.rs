@@ -1,6 +1,11 @@
+use std::any::{Any, TypeId};
+
+use bytes::Bytes;
 use serde::{Serialize, de::DeserializeOwned};
 use thiserror::Error;
 
+use crate::envelope::Envelope;
+
 #[derive(Error, Debug)]
 pub enum FormatError {
     #[error(transparent)]
@@ -12,6 +17,49 @@ pub enum FormatError {
 
 pub type Raw = Vec<u8>;
 
+/// Value format identified by its tag in the [`Envelope`] header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormatId {
+    Json,
+    Bincode,
+    MessagePack,
+    Cbor,
+    Postcard,
+    Rkyv,
+    /// Format outside this crate, read only by the configured format
+    Custom,
+}
+
+impl FormatId {
+    /// Tag byte written in the envelope header
+    pub fn tag(self) -> u8 {
+        match self {
+            FormatId::Json => 0,
+            FormatId::Bincode => 1,
+            FormatId::MessagePack => 2,
+            FormatId::Cbor => 3,
+            FormatId::Postcard => 4,
+            FormatId::Rkyv => 5,
+            FormatId::Custom => 0xff,
+        }
+    }
+
+    /// Format with the given tag byte
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        [
+            FormatId::Json,
+            FormatId::Bincode,
+            FormatId::MessagePack,
+            FormatId::Cbor,
+            FormatId::Postcard,
+            FormatId::Rkyv,
+            FormatId::Custom,
+        ]
+        .into_iter()
+        .find(|format| format.tag() == tag)
+    }
+}
+
 /// Object-safe format trait (uses erased-serde for type erasure)
 /// This trait can be used with `Arc<dyn Format>` for dynamic dispatch
 pub trait Format: std::fmt::Debug + Send + Sync {
@@ -23,6 +71,36 @@ pub trait Format: std::fmt::Debug + Send + Sync {
         data: &[u8],
         f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
     ) -> Result<(), FormatError>;
+
+    /// Serialize value of a concrete type bypassing serde.
+    ///
+    /// Returns `None` if the format has no such path for the type, the value is
+    /// then serialized with [`erased_serialize`](Self::erased_serialize).
+    fn serialize_typed(&self, _value: &dyn Any) -> Option<Result<Raw, FormatError>> {
+        None
+    }
+
+    /// Read value of type `type_id` straight from `data` bypassing serde.
+    ///
+    /// The value may keep slices of `data` instead of copying them. Returns
+    /// `None` if the format has no such path for the type.
+    fn deserialize_typed(
+        &self,
+        _data: &Bytes,
+        _type_id: TypeId,
+    ) -> Option<Result<Box<dyn Any + Send>, FormatError>> {
+        None
+    }
+
+    /// Id of the format recorded in the [`Envelope`] header
+    fn format_id(&self) -> FormatId {
+        FormatId::Custom
+    }
+
+    /// Envelope written around values, `None` stores bare values
+    fn envelope(&self) -> Option<&Envelope> {
+        None
+    }
 }
 
 /// Extension trait providing generic serialize/deserialize methods
@@ -35,7 +113,7 @@ pub trait FormatExt: Format {
         self.erased_serialize(value as _)
     }
 
-    fn deserialize<T>(&self, data: &Raw) -> Result<T, FormatError>
+    fn deserialize<T>(&self, data: &[u8]) -> Result<T, FormatError>
     where
         T: DeserializeOwned,
     {
@@ -71,6 +149,40 @@ impl Format for std::sync::Arc<dyn Format> {
     ) -> Result<(), FormatError> {
         (**self).with_deserializer(data, f)
     }
+
+    fn serialize_typed(&self, value: &dyn Any) -> Option<Result<Raw, FormatError>> {
+        (**self).serialize_typed(value)
+    }
+
+    fn deserialize_typed(
+        &self,
+        data: &Bytes,
+        type_id: TypeId,
+    ) -> Option<Result<Box<dyn Any + Send>, FormatError>> {
+        (**self).deserialize_typed(data, type_id)
+    }
+
+    fn format_id(&self) -> FormatId {
+        (**self).format_id()
+    }
+
+    fn envelope(&self) -> Option<&Envelope> {
+        (**self).envelope()
+    }
+}
+
+/// Wrapper implementing `serde::Serialize` for erased values, for formats
+/// serializing through a generic entry point instead of a `Serializer`
+struct SerdeWrapper<'a>(&'a dyn erased_serde::Serialize);
+
+impl<'a> serde::Serialize for SerdeWrapper<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error;
+        erased_serde::serialize(self.0, serializer).map_err(S::Error::custom)
+    }
 }
 
 /// JSON format (default)
@@ -96,6 +208,10 @@ impl Format for JsonFormat {
         let mut erased = <dyn erased_serde::Deserializer>::erase(&mut deser);
         f(&mut erased).map_err(|error| FormatError::Deserialize(Box::new(error)))
     }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::Json
+    }
 }
 
 /// Bincode format
@@ -104,19 +220,6 @@ pub struct BincodeFormat;
 
 impl Format for BincodeFormat {
     fn erased_serialize(&self, value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
-        // Wrapper that implements serde::Serialize
-        struct SerdeWrapper<'a>(&'a dyn erased_serde::Serialize);
-
-        impl<'a> serde::Serialize for SerdeWrapper<'a> {
-            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-            where
-                S: serde::Serializer,
-            {
-                use serde::ser::Error;
-                erased_serde::serialize(self.0, serializer).map_err(S::Error::custom)
-            }
-        }
-
         bincode::serde::encode_to_vec(SerdeWrapper(value), bincode::config::standard())
             .map_err(|error| FormatError::Serialize(Box::new(error)))
     }
@@ -137,6 +240,245 @@ impl Format for BincodeFormat {
         let mut erased = <dyn erased_serde::Deserializer>::erase(deser);
         f(&mut erased).map_err(|error| FormatError::Deserialize(Box::new(error)))
     }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::Bincode
+    }
+}
+
+/// MessagePack format
+///
+/// Structs are encoded as maps with field names, readable by MessagePack
+/// libraries of other languages.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackFormat;
+
+#[cfg(feature = "msgpack")]
+impl Format for MessagePackFormat {
+    fn erased_serialize(&self, value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
+        let mut buf = Vec::new();
+        let mut ser = rmp_serde::Serializer::new(&mut buf).with_struct_map();
+        value
+            .erased_serialize(&mut <dyn erased_serde::Serializer>::erase(&mut ser))
+            .map_err(|error| FormatError::Serialize(Box::new(error)))?;
+        Ok(buf)
+    }
+
+    fn with_deserializer(
+        &self,
+        data: &[u8],
+        f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+    ) -> Result<(), FormatError> {
+        let mut deser = rmp_serde::Deserializer::from_read_ref(data);
+        let mut erased = <dyn erased_serde::Deserializer>::erase(&mut deser);
+        f(&mut erased).map_err(|error| FormatError::Deserialize(Box::new(error)))
+    }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::MessagePack
+    }
+}
+
+/// CBOR format
+///
+/// ciborium keeps its deserializer private, so values are read into an
+/// intermediate [`serde_value::Value`] which is then deserialized.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborFormat;
+
+#[cfg(feature = "cbor")]
+impl Format for CborFormat {
+    fn erased_serialize(&self, value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(&SerdeWrapper(value), &mut buf)
+            .map_err(|error| FormatError::Serialize(Box::new(error)))?;
+        Ok(buf)
+    }
+
+    fn with_deserializer(
+        &self,
+        data: &[u8],
+        f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+    ) -> Result<(), FormatError> {
+        let value: serde_value::Value = ciborium::from_reader(data)
+            .map_err(|error| FormatError::Deserialize(Box::new(error)))?;
+        let mut erased = <dyn erased_serde::Deserializer>::erase(value);
+        f(&mut erased).map_err(|error| FormatError::Deserialize(Box::new(error)))
+    }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::Cbor
+    }
+}
+
+/// Postcard format
+///
+/// Like bincode, postcard is not self-describing, values must be read with the
+/// type they were written with.
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostcardFormat;
+
+#[cfg(feature = "postcard")]
+impl Format for PostcardFormat {
+    fn erased_serialize(&self, value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
+        postcard::to_allocvec(&SerdeWrapper(value))
+            .map_err(|error| FormatError::Serialize(Box::new(error)))
+    }
+
+    fn with_deserializer(
+        &self,
+        data: &[u8],
+        f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+    ) -> Result<(), FormatError> {
+        let mut deser = postcard::Deserializer::from_bytes(data);
+        let mut erased = <dyn erased_serde::Deserializer>::erase(&mut deser);
+        f(&mut erased).map_err(|error| FormatError::Deserialize(Box::new(error)))
+    }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::Postcard
+    }
+}
+
+/// Value stored by [`RkyvFormat`] as an rkyv archive.
+///
+/// The archive is validated on read and the value is built from it in place,
+/// large fields can keep slices of the buffer returned by the backend instead
+/// of copying them.
+#[cfg(feature = "rkyv")]
+pub trait ZeroCopy: Sized + Send + 'static {
+    /// Archived representation of the value.
+    type Archive: for<'a> rkyv::Serialize<
+            rkyv::api::high::HighSerializer<
+                rkyv::util::AlignedVec,
+                rkyv::ser::allocator::ArenaHandle<'a>,
+                rkyv::rancor::Error,
+            >,
+        > + rkyv::Archive<
+            Archived: for<'a> rkyv::bytecheck::CheckBytes<
+                rkyv::api::high::HighValidator<'a, rkyv::rancor::Error>,
+            >,
+        >;
+
+    /// Representation of the value to archive.
+    fn to_archive(&self) -> Self::Archive;
+
+    /// Build the value from `archived`, validated in place in `data`.
+    fn from_archived(
+        archived: &<Self::Archive as rkyv::Archive>::Archived,
+        data: &Bytes,
+    ) -> Result<Self, FormatError>;
+}
+
+#[cfg(feature = "rkyv")]
+#[derive(Clone, Copy)]
+struct Archiver {
+    serialize: fn(&dyn Any) -> Result<Raw, FormatError>,
+    deserialize: fn(&Bytes) -> Result<Box<dyn Any + Send>, FormatError>,
+}
+
+/// rkyv format
+///
+/// Stores types registered with [`register`](RkyvFormat::register) as rkyv
+/// archives, which are read without a full deserialization. Archived fields
+/// are unaligned, so archives are accessed in whatever buffer the backend
+/// returns. Values of other types fail to serialize.
+#[cfg(feature = "rkyv")]
+#[derive(Clone, Default)]
+pub struct RkyvFormat {
+    types: std::collections::HashMap<TypeId, Archiver>,
+}
+
+#[cfg(feature = "rkyv")]
+impl RkyvFormat {
+    /// Format without registered types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store values of type `T`.
+    pub fn register<T: ZeroCopy>(mut self) -> Self {
+        self.types.insert(
+            TypeId::of::<T>(),
+            Archiver {
+                serialize: archive::<T>,
+                deserialize: access::<T>,
+            },
+        );
+        self
+    }
+}
+
+#[cfg(feature = "rkyv")]
+fn archive<T: ZeroCopy>(value: &dyn Any) -> Result<Raw, FormatError> {
+    let value = value
+        .downcast_ref::<T>()
+        .expect("archiver is registered under the type id of its type");
+    rkyv::to_bytes::<rkyv::rancor::Error>(&value.to_archive())
+        .map(|archive| archive.into_vec())
+        .map_err(|error| FormatError::Serialize(Box::new(error)))
+}
+
+#[cfg(feature = "rkyv")]
+fn access<T: ZeroCopy>(data: &Bytes) -> Result<Box<dyn Any + Send>, FormatError> {
+    let archived =
+        rkyv::access::<<T::Archive as rkyv::Archive>::Archived, rkyv::rancor::Error>(data)
+            .map_err(|error| FormatError::Deserialize(Box::new(error)))?;
+    Ok(Box::new(T::from_archived(archived, data)?))
+}
+
+#[cfg(feature = "rkyv")]
+impl std::fmt::Debug for RkyvFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RkyvFormat")
+            .field("types", &self.types.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "rkyv")]
+fn unregistered() -> Box<dyn std::error::Error + Send> {
+    Box::new(std::io::Error::other(
+        "value type is not registered with the rkyv format",
+    ))
+}
+
+#[cfg(feature = "rkyv")]
+impl Format for RkyvFormat {
+    fn erased_serialize(&self, _value: &dyn erased_serde::Serialize) -> Result<Raw, FormatError> {
+        Err(FormatError::Serialize(unregistered()))
+    }
+
+    fn with_deserializer(
+        &self,
+        _data: &[u8],
+        _f: &mut dyn FnMut(&mut dyn erased_serde::Deserializer) -> Result<(), erased_serde::Error>,
+    ) -> Result<(), FormatError> {
+        Err(FormatError::Deserialize(unregistered()))
+    }
+
+    fn serialize_typed(&self, value: &dyn Any) -> Option<Result<Raw, FormatError>> {
+        self.types
+            .get(&value.type_id())
+            .map(|archiver| (archiver.serialize)(value))
+    }
+
+    fn deserialize_typed(
+        &self,
+        data: &Bytes,
+        type_id: TypeId,
+    ) -> Option<Result<Box<dyn Any + Send>, FormatError>> {
+        self.types
+            .get(&type_id)
+            .map(|archiver| (archiver.deserialize)(data))
+    }
+
+    fn format_id(&self) -> FormatId {
+        FormatId::Rkyv
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,367 @@
+use std::{
+    io,
+    ops::Range,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use hitbox_core::{CacheKey, CacheValue};
+
+use crate::{
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    composite::Operation,
+    serializer::{Format, Raw},
+};
+
+/// Data of corrupted values, rejected by the bundled formats and compressors.
+const CORRUPTED: &[u8] = b"\xff\xfehitbox-chaos\xff\xfe";
+
+/// Calls of an operation a [`Fault`] applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// Every call.
+    Always,
+    /// Calls with index in the range, counted per operation from zero.
+    Calls(Range<u64>),
+    /// Each call independently with the given probability.
+    ///
+    /// Draws come from a generator seeded with [`ChaosBackend::seed`], so a
+    /// schedule is reproducible as long as calls happen in the same order.
+    Rate(f64),
+}
+
+/// Misbehaviour injected into a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Delay the call, then pass it on.
+    Latency(Duration),
+    /// Never complete the call.
+    Hang,
+    /// Wait, then fail with a timed out [`BackendError::ConnectionError`].
+    Timeout(Duration),
+    /// Fail with [`BackendError::ConnectionError`].
+    ConnectionError,
+    /// Fail with [`BackendError::InternalError`].
+    InternalError,
+    /// Replace value data with bytes which fail to decompress and deserialize.
+    ///
+    /// Reads return a corrupted value even on a miss, writes store one. Removals
+    /// are not affected.
+    Corrupt,
+}
+
+struct Rule {
+    trigger: Trigger,
+    fault: Fault,
+}
+
+/// Backend wrapper injecting faults into calls of the wrapped backend.
+///
+/// Faults are registered per [`Operation`]. Every call goes through the faults
+/// of its operation in registration order and applies those whose [`Trigger`]
+/// matches: latencies add up, a corruption is applied to the result, and the
+/// first failing fault ends the call without reaching the wrapped backend.
+///
+/// ```ignore
+/// let backend = ChaosBackend::new(HashMapBackend::new())
+///     .fault(Operation::Read, Trigger::Rate(0.2), Fault::ConnectionError)
+///     .fault(Operation::Write, Trigger::Always, Fault::Latency(Duration::from_millis(50)));
+/// ```
+pub struct ChaosBackend<B> {
+    backend: B,
+    rules: Mutex<[Vec<Rule>; 3]>,
+    calls: [AtomicU64; 3],
+    random: AtomicU64,
+}
+
+impl<B> ChaosBackend<B> {
+    /// Wrap backend, calls pass through until faults are added.
+    pub fn new(backend: B) -> Self {
+        Self {
+            backend,
+            rules: Default::default(),
+            calls: Default::default(),
+            random: AtomicU64::new(0),
+        }
+    }
+
+    /// Set seed of the generator used by [`Trigger::Rate`].
+    pub fn seed(self, seed: u64) -> Self {
+        self.random.store(seed, Ordering::Relaxed);
+        self
+    }
+
+    /// Add fault applied to calls of `operation` matching `trigger`.
+    pub fn fault(self, operation: Operation, trigger: Trigger, fault: Fault) -> Self {
+        self.inject(operation, trigger, fault);
+        self
+    }
+
+    /// Add fault to an already shared backend, see [`fault`](Self::fault).
+    pub fn inject(&self, operation: Operation, trigger: Trigger, fault: Fault) {
+        self.rules.lock().unwrap()[index(operation)].push(Rule { trigger, fault });
+    }
+
+    /// Remove all faults, calls pass through again.
+    pub fn heal(&self) {
+        *self.rules.lock().unwrap() = Default::default();
+    }
+
+    /// Number of calls of `operation` made so far, failed ones included.
+    pub fn calls(&self, operation: Operation) -> u64 {
+        self.calls[index(operation)].load(Ordering::Relaxed)
+    }
+
+    /// Wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    /// Apply delays and failures scheduled for the call, returning whether the
+    /// result should be corrupted.
+    async fn disturb(&self, operation: Operation) -> BackendResult<bool> {
+        let call = self.calls[index(operation)].fetch_add(1, Ordering::Relaxed);
+        let faults: Vec<Fault> = self.rules.lock().unwrap()[index(operation)]
+            .iter()
+            .filter(|rule| match &rule.trigger {
+                Trigger::Always => true,
+                Trigger::Calls(calls) => calls.contains(&call),
+                Trigger::Rate(rate) => self.draw() < *rate,
+            })
+            .map(|rule| rule.fault.clone())
+            .collect();
+
+        let mut corrupt = false;
+        for fault in faults {
+            match fault {
+                Fault::Latency(latency) => tokio::time::sleep(latency).await,
+                Fault::Hang => std::future::pending::<()>().await,
+                Fault::Timeout(timeout) => {
+                    tokio::time::sleep(timeout).await;
+                    return Err(BackendError::ConnectionError(Box::new(io::Error::from(
+                        io::ErrorKind::TimedOut,
+                    ))));
+                }
+                Fault::ConnectionError => {
+                    return Err(BackendError::ConnectionError(Box::new(io::Error::from(
+                        io::ErrorKind::ConnectionReset,
+                    ))));
+                }
+                Fault::InternalError => {
+                    return Err(BackendError::InternalError(Box::new(io::Error::other(
+                        "injected fault",
+                    ))));
+                }
+                Fault::Corrupt => corrupt = true,
+            }
+        }
+        Ok(corrupt)
+    }
+
+    /// Uniform number in `[0, 1)`, splitmix64 over the seed.
+    fn draw(&self) -> f64 {
+        let mut z = self
+            .random
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn index(operation: Operation) -> usize {
+    match operation {
+        Operation::Read => 0,
+        Operation::Write => 1,
+        Operation::Remove => 2,
+    }
+}
+
+impl<B> std::fmt::Debug for ChaosBackend<B>
+where
+    B: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChaosBackend")
+            .field("backend", &self.backend)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<B> Backend for ChaosBackend<B>
+where
+    B: Backend + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let corrupt = self.disturb(Operation::Read).await?;
+        let value = self.backend.read(key).await?;
+        if !corrupt {
+            return Ok(value);
+        }
+        Ok(Some(match value {
+            Some(value) => CacheValue::new(CORRUPTED.to_vec(), value.expire, value.stale),
+            None => CacheValue::new(CORRUPTED.to_vec(), None, None),
+        }))
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        mut value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        if self.disturb(Operation::Write).await? {
+            value.data = CORRUPTED.to_vec();
+        }
+        self.backend.write(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.disturb(Operation::Remove).await?;
+        self.backend.remove(key).await
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        self.backend.value_format()
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        self.backend.key_format()
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        self.backend.compressor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HashMapBackend;
+
+    fn key() -> CacheKey {
+        CacheKey::from_str("key", "1")
+    }
+
+    fn value() -> CacheValue<Raw> {
+        CacheValue::new(b"{\"name\":\"value\"}".to_vec(), None, None)
+    }
+
+    #[tokio::test]
+    async fn test_passes_through_without_faults() {
+        let backend = ChaosBackend::new(HashMapBackend::new());
+
+        backend.write(&key(), value(), None).await.unwrap();
+
+        assert_eq!(backend.read(&key()).await.unwrap(), Some(value()));
+        assert_eq!(backend.calls(Operation::Read), 1);
+        assert_eq!(backend.calls(Operation::Write), 1);
+    }
+
+    #[tokio::test]
+    async fn test_calls_trigger_and_heal() {
+        let backend = ChaosBackend::new(HashMapBackend::new())
+            .fault(
+                Operation::Write,
+                Trigger::Calls(1..2),
+                Fault::ConnectionError,
+            )
+            .fault(Operation::Remove, Trigger::Always, Fault::InternalError);
+
+        assert!(backend.write(&key(), value(), None).await.is_ok());
+        assert!(matches!(
+            backend.write(&key(), value(), None).await,
+            Err(BackendError::ConnectionError(_))
+        ));
+        assert!(backend.write(&key(), value(), None).await.is_ok());
+        assert!(matches!(
+            backend.remove(&key()).await,
+            Err(BackendError::InternalError(_))
+        ));
+
+        backend.heal();
+
+        assert_eq!(
+            backend.remove(&key()).await.unwrap(),
+            DeleteStatus::Deleted(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_is_reproducible() {
+        let failures = |seed| async move {
+            let backend = ChaosBackend::new(HashMapBackend::new()).seed(seed).fault(
+                Operation::Read,
+                Trigger::Rate(0.3),
+                Fault::ConnectionError,
+            );
+            let mut failures = Vec::new();
+            for _ in 0..1000 {
+                failures.push(backend.read(&key()).await.is_err());
+            }
+            failures
+        };
+
+        let first = failures(7).await;
+        let failed = first.iter().filter(|failed| **failed).count();
+
+        assert_eq!(first, failures(7).await);
+        assert!((200..400).contains(&failed), "{failed} failures");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_latency_timeout_and_hang() {
+        let backend = ChaosBackend::new(HashMapBackend::new())
+            .fault(
+                Operation::Read,
+                Trigger::Always,
+                Fault::Latency(Duration::from_secs(1)),
+            )
+            .fault(
+                Operation::Read,
+                Trigger::Calls(1..2),
+                Fault::Timeout(Duration::from_secs(5)),
+            )
+            .fault(Operation::Read, Trigger::Calls(2..3), Fault::Hang);
+
+        let start = tokio::time::Instant::now();
+        assert!(backend.read(&key()).await.unwrap().is_none());
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert!(matches!(
+            backend.read(&key()).await,
+            Err(BackendError::ConnectionError(_))
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(7));
+        let hang = tokio::time::timeout(Duration::from_secs(3600), backend.read(&key())).await;
+        assert!(hang.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_data_fails_to_deserialize() {
+        let backend = ChaosBackend::new(HashMapBackend::new()).fault(
+            Operation::Read,
+            Trigger::Always,
+            Fault::Corrupt,
+        );
+
+        backend.write(&key(), value(), None).await.unwrap();
+
+        assert_eq!(backend.inner().read(&key()).await.unwrap(), Some(value()));
+        let corrupted = backend.read(&key()).await.unwrap().unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&corrupted.data).is_err());
+        assert!(
+            backend
+                .read(&CacheKey::from_str("key", "2"))
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+}
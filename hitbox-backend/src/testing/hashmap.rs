@@ -0,0 +1,301 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hitbox_core::{CacheKey, CacheValue, TimeProvider};
+use tokio::sync::RwLock;
+
+use crate::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    entry_deadline,
+    serializer::{Format, JsonFormat, Raw},
+};
+
+struct SystemClock;
+
+impl TimeProvider for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+struct Entry {
+    value: CacheValue<Raw>,
+    deadline: Option<DateTime<Utc>>,
+}
+
+/// Reference in-memory backend storing entries in a `HashMap`.
+///
+/// Entries are keyed by the key serialized with the configured key format and
+/// follow the ttl rules of the bundled backends: an explicit ttl sets the
+/// lifetime of the entry, otherwise it lives until the value expires, and
+/// values written already expired are not stored. Expired entries are dropped
+/// on access. Clones share the same storage.
+#[derive(Clone)]
+pub struct HashMapBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    storage: Arc<RwLock<HashMap<Vec<u8>, Entry>>>,
+    clock: Arc<dyn TimeProvider>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl HashMapBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new HashMapBackend with default settings.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Creates new HashMapBackend builder with default settings.
+    pub fn builder() -> HashMapBackendBuilder<JsonFormat, PassthroughCompressor> {
+        HashMapBackendBuilder::default()
+    }
+}
+
+impl Default for HashMapBackend<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, C> HashMapBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Number of stored entries, expired ones included.
+    pub async fn len(&self) -> usize {
+        self.storage.read().await.len()
+    }
+
+    /// Whether no entries are stored.
+    pub async fn is_empty(&self) -> bool {
+        self.storage.read().await.is_empty()
+    }
+
+    /// Remove all entries.
+    pub async fn clear(&self) {
+        self.storage.write().await.clear();
+    }
+}
+
+impl<S, C> std::fmt::Debug for HashMapBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashMapBackend")
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Part of builder pattern implementation for HashMapBackend.
+pub struct HashMapBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    clock: Arc<dyn TimeProvider>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for HashMapBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> HashMapBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Set source of the current time used for ttl, the system clock by default.
+    pub fn time_provider(mut self, clock: impl TimeProvider + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    pub fn value_format<NewS>(self, serializer: NewS) -> HashMapBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        HashMapBackendBuilder {
+            clock: self.clock,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    pub fn compressor<NewC>(self, compressor: NewC) -> HashMapBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        HashMapBackendBuilder {
+            clock: self.clock,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    pub fn build(self) -> HashMapBackend<S, C> {
+        HashMapBackend {
+            storage: Default::default(),
+            clock: self.clock,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        }
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for HashMapBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let key = self.key_format.serialize(key)?;
+        let now = self.clock.now();
+        let mut storage = self.storage.write().await;
+        match storage.get(&key) {
+            Some(entry) if entry.deadline.is_some_and(|deadline| deadline <= now) => {
+                storage.remove(&key);
+                Ok(None)
+            }
+            entry => Ok(entry.map(|entry| entry.value.clone())),
+        }
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let Some(deadline) = entry_deadline(ttl, value.expire, self.clock.now()) else {
+            return Ok(());
+        };
+        let key = self.key_format.serialize(key)?;
+        self.storage
+            .write()
+            .await
+            .insert(key, Entry { value, deadline });
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let key = self.key_format.serialize(key)?;
+        let now = self.clock.now();
+        match self.storage.write().await.remove(&key) {
+            Some(entry) if entry.deadline.is_none_or(|deadline| deadline > now) => {
+                Ok(DeleteStatus::Deleted(1))
+            }
+            _ => Ok(DeleteStatus::Missing),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<DateTime<Utc>>>);
+
+    impl ManualClock {
+        fn advance(&self, by: TimeDelta) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl TimeProvider for ManualClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn value() -> CacheValue<Raw> {
+        CacheValue::new(b"value".to_vec(), None, None)
+    }
+
+    #[tokio::test]
+    async fn test_write_read_remove() {
+        let backend = HashMapBackend::new();
+        let key = CacheKey::from_str("key", "1");
+
+        backend.write(&key, value(), None).await.unwrap();
+
+        assert_eq!(backend.read(&key).await.unwrap(), Some(value()));
+        assert_eq!(
+            backend.remove(&key).await.unwrap(),
+            DeleteStatus::Deleted(1)
+        );
+        assert_eq!(backend.remove(&key).await.unwrap(), DeleteStatus::Missing);
+        assert_eq!(backend.read(&key).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_follows_clock() {
+        let clock = ManualClock(Arc::new(Mutex::new(Utc::now())));
+        let backend = HashMapBackend::builder()
+            .time_provider(clock.clone())
+            .build();
+        let key = CacheKey::from_str("key", "1");
+        let expired = CacheValue::new(b"value".to_vec(), Some(clock.now()), None);
+
+        backend
+            .write(&key, value(), Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        backend
+            .write(&CacheKey::from_str("key", "2"), expired, None)
+            .await
+            .unwrap();
+        clock.advance(TimeDelta::seconds(9));
+        assert!(backend.read(&key).await.unwrap().is_some());
+        clock.advance(TimeDelta::seconds(1));
+
+        assert_eq!(backend.read(&key).await.unwrap(), None);
+        assert!(backend.is_empty().await);
+    }
+}
@@ -0,0 +1,15 @@
+//! Backends for testing code built on top of hitbox backends.
+//!
+//! [`HashMapBackend`] is a minimal in-memory reference implementation of
+//! [`Backend`], and [`ChaosBackend`] wraps any backend to inject failures,
+//! latency and corrupted data, e.g. to exercise error handling of the caching
+//! state machine without a flaky network.
+//!
+//! Available with the `test-helpers` feature.
+//!
+//! [`Backend`]: crate::Backend
+mod chaos;
+mod hashmap;
+
+pub use chaos::{ChaosBackend, Fault, Trigger};
+pub use hashmap::{HashMapBackend, HashMapBackendBuilder};
@@ -0,0 +1,85 @@
+//! Lifetime of stored entries.
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Deadline of an entry written at `now` with the ttl passed to
+/// [`Backend::write`](crate::Backend::write).
+///
+/// An explicit ttl sets the lifetime of the entry, otherwise it lives until the
+/// value expires. Returns `None` when the value is already expired and must not
+/// be stored, `Some(None)` when the entry never expires.
+pub fn entry_deadline(
+    ttl: Option<Duration>,
+    expire: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<Option<DateTime<Utc>>> {
+    match (ttl, expire) {
+        (Some(ttl), _) => Some(
+            TimeDelta::from_std(ttl)
+                .ok()
+                .and_then(|ttl| now.checked_add_signed(ttl)),
+        ),
+        (None, Some(expire)) if expire <= now => None,
+        (None, expire) => Some(expire),
+    }
+}
+
+/// [`entry_deadline`] as a lifetime relative to `now`, for stores expiring
+/// entries by ttl.
+pub fn entry_ttl(
+    ttl: Option<Duration>,
+    expire: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<Option<Duration>> {
+    match ttl {
+        Some(ttl) => Some(Some(ttl)),
+        None => entry_deadline(None, expire, now)
+            .map(|deadline| deadline.and_then(|deadline| (deadline - now).to_std().ok())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_ttl_wins() {
+        let now = Utc::now();
+        let expired = Some(now - TimeDelta::seconds(1));
+
+        assert_eq!(
+            entry_deadline(Some(Duration::from_secs(5)), expired, now),
+            Some(Some(now + TimeDelta::seconds(5)))
+        );
+        assert_eq!(
+            entry_ttl(Some(Duration::from_secs(5)), expired, now),
+            Some(Some(Duration::from_secs(5)))
+        );
+    }
+
+    #[test]
+    fn test_lives_until_value_expires() {
+        let now = Utc::now();
+        let expire = now + TimeDelta::seconds(30);
+
+        assert_eq!(entry_deadline(None, Some(expire), now), Some(Some(expire)));
+        assert_eq!(
+            entry_ttl(None, Some(expire), now),
+            Some(Some(Duration::from_secs(30)))
+        );
+        assert_eq!(entry_deadline(None, None, now), Some(None));
+        assert_eq!(entry_ttl(None, None, now), Some(None));
+    }
+
+    #[test]
+    fn test_expired_value_is_not_stored() {
+        let now = Utc::now();
+
+        assert_eq!(entry_deadline(None, Some(now), now), None);
+        assert_eq!(
+            entry_ttl(None, Some(now - TimeDelta::seconds(1)), now),
+            None
+        );
+    }
+}
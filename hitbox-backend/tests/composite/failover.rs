@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use hitbox_backend::{
+    Backend, BackendError, CacheBackend, CompositionError, DeleteStatus, FailoverBackend,
+    SharedBackend, composite::Operation,
+};
+use hitbox_core::CacheKey;
+
+use crate::{FailingBackend, MemBackend, Value};
+
+type Served = Arc<Mutex<Vec<(Operation, String)>>>;
+
+fn observed(members: Vec<(&str, SharedBackend)>) -> (FailoverBackend, Served) {
+    let served = Served::default();
+    let log = served.clone();
+    let backend = members
+        .into_iter()
+        .fold(FailoverBackend::builder(), |builder, (id, backend)| {
+            builder.member(id, backend)
+        })
+        .observer(move |operation, id| log.lock().unwrap().push((operation, id.to_owned())))
+        .build()
+        .unwrap();
+    (backend, served)
+}
+
+#[tokio::test]
+async fn test_primary_serves_when_available() {
+    let primary = Arc::new(MemBackend::default());
+    let secondary = Arc::new(MemBackend::default());
+    let (backend, served) = observed(vec![
+        ("primary", primary.clone()),
+        ("secondary", secondary.clone()),
+    ]);
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .set::<Value>(&key, &Value::new(1), None)
+        .await
+        .unwrap();
+    let value = backend.get::<Value>(&key).await.unwrap().unwrap();
+
+    assert_eq!(value.data.index, 1);
+    assert_eq!(primary.len().await, 1);
+    assert_eq!(secondary.len().await, 0);
+    assert_eq!(
+        *served.lock().unwrap(),
+        vec![
+            (Operation::Write, "primary".to_owned()),
+            (Operation::Read, "primary".to_owned())
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_falls_back_on_connection_error() {
+    let secondary = Arc::new(MemBackend::default());
+    let (backend, served) = observed(vec![
+        ("primary", Arc::new(FailingBackend::unreachable())),
+        ("secondary", secondary.clone()),
+    ]);
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .set::<Value>(&key, &Value::new(2), None)
+        .await
+        .unwrap();
+    let value = backend.get::<Value>(&key).await.unwrap().unwrap();
+    let status = backend.delete(&key).await.unwrap();
+
+    assert_eq!(value.data.index, 2);
+    assert_eq!(status, DeleteStatus::Deleted(1));
+    assert!(
+        served
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, id)| id == "secondary")
+    );
+}
+
+#[tokio::test]
+async fn test_other_errors_are_not_retried() {
+    let secondary = Arc::new(MemBackend::default());
+    let (backend, served) = observed(vec![
+        ("primary", Arc::new(FailingBackend::broken())),
+        ("secondary", secondary.clone()),
+    ]);
+
+    let result = backend.read(&CacheKey::from_str("key", "1")).await;
+
+    assert!(matches!(result, Err(BackendError::InternalError(_))));
+    assert!(served.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_all_members_unreachable() {
+    let (backend, _) = observed(vec![
+        ("primary", Arc::new(FailingBackend::unreachable())),
+        ("secondary", Arc::new(FailingBackend::unreachable())),
+    ]);
+
+    let result = backend.read(&CacheKey::from_str("key", "1")).await;
+
+    assert!(matches!(result, Err(BackendError::ConnectionError(_))));
+}
+
+#[test]
+fn test_build_without_members() {
+    assert!(matches!(
+        FailoverBackend::builder().build(),
+        Err(CompositionError::NoMembers)
+    ));
+}
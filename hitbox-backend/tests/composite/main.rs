@@ -0,0 +1,133 @@
+use std::{collections::HashMap, io, time::Duration};
+
+use async_trait::async_trait;
+use hitbox_backend::{Backend, BackendError, BackendResult, DeleteStatus, serializer::Raw};
+use hitbox_core::{CacheKey, CachePolicy, CacheValue, CacheableResponse, EntityPolicyConfig};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+mod failover;
+mod replicated;
+mod sharded;
+
+#[derive(Debug, Default)]
+pub struct MemBackend {
+    storage: RwLock<HashMap<CacheKey, CacheValue<Raw>>>,
+}
+
+impl MemBackend {
+    async fn len(&self) -> usize {
+        self.storage.read().await.len()
+    }
+}
+
+#[async_trait]
+impl Backend for MemBackend {
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        Ok(self.storage.read().await.get(key).cloned())
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        _ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        self.storage.write().await.insert(key.clone(), value);
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        match self.storage.write().await.remove(key) {
+            Some(_) => Ok(DeleteStatus::Deleted(1)),
+            None => Ok(DeleteStatus::Missing),
+        }
+    }
+}
+
+/// Backend failing every call with the error produced by `error`.
+pub struct FailingBackend {
+    error: fn() -> BackendError,
+}
+
+impl FailingBackend {
+    pub fn unreachable() -> Self {
+        FailingBackend {
+            error: || {
+                BackendError::ConnectionError(Box::new(io::Error::from(
+                    io::ErrorKind::ConnectionRefused,
+                )))
+            },
+        }
+    }
+
+    pub fn broken() -> Self {
+        FailingBackend {
+            error: || BackendError::InternalError(Box::new(io::Error::other("broken"))),
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for FailingBackend {
+    async fn read(&self, _key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        Err((self.error)())
+    }
+
+    async fn write(
+        &self,
+        _key: &CacheKey,
+        _value: CacheValue<Raw>,
+        _ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        Err((self.error)())
+    }
+
+    async fn remove(&self, _key: &CacheKey) -> BackendResult<DeleteStatus> {
+        Err((self.error)())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Value {
+    pub name: String,
+    pub index: u32,
+}
+
+impl Value {
+    pub fn new(index: u32) -> CacheValue<Self> {
+        CacheValue::new(
+            Value {
+                name: "value".to_owned(),
+                index,
+            },
+            None,
+            None,
+        )
+    }
+}
+
+#[async_trait]
+impl CacheableResponse for Value {
+    type Cached = Self;
+    type Subject = Self;
+
+    async fn cache_policy<P>(
+        self,
+        _predicates: P,
+        _: &EntityPolicyConfig,
+    ) -> hitbox_core::ResponseCachePolicy<Self>
+    where
+        P: hitbox_core::Predicate<Subject = Self::Subject> + Send + Sync,
+    {
+        unimplemented!()
+    }
+
+    async fn into_cached(self) -> CachePolicy<Self::Cached, Self> {
+        CachePolicy::Cacheable(self)
+    }
+
+    async fn from_cached(cached: Self::Cached) -> Self {
+        cached
+    }
+}
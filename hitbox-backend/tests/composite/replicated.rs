@@ -0,0 +1,189 @@
+use std::sync::{Arc, Mutex};
+
+use hitbox_backend::{
+    Backend, BackendError, CacheBackend, CompositionError, DeleteStatus, ReplicatedBackend,
+    SharedBackend, composite::Operation,
+};
+use hitbox_core::CacheKey;
+
+use crate::{FailingBackend, MemBackend, Value};
+
+type Served = Arc<Mutex<Vec<(Operation, String)>>>;
+
+fn observed(
+    members: Vec<(&str, SharedBackend)>,
+    quorum: Option<usize>,
+) -> (ReplicatedBackend, Served) {
+    let served = Served::default();
+    let log = served.clone();
+    let builder = members
+        .into_iter()
+        .fold(ReplicatedBackend::builder(), |builder, (id, backend)| {
+            builder.member(id, backend)
+        })
+        .observer(move |operation, id| log.lock().unwrap().push((operation, id.to_owned())));
+    let builder = match quorum {
+        Some(quorum) => builder.quorum(quorum),
+        None => builder,
+    };
+    (builder.build().unwrap(), served)
+}
+
+#[tokio::test]
+async fn test_writes_fan_out_to_all_members() {
+    let replicas = [
+        Arc::new(MemBackend::default()),
+        Arc::new(MemBackend::default()),
+        Arc::new(MemBackend::default()),
+    ];
+    let (backend, served) = observed(
+        vec![
+            ("a", replicas[0].clone()),
+            ("b", replicas[1].clone()),
+            ("c", replicas[2].clone()),
+        ],
+        None,
+    );
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .set::<Value>(&key, &Value::new(1), None)
+        .await
+        .unwrap();
+    for replica in &replicas {
+        assert_eq!(replica.len().await, 1);
+    }
+
+    let value = backend.get::<Value>(&key).await.unwrap().unwrap();
+    assert_eq!(value.data.index, 1);
+
+    assert_eq!(
+        backend.delete(&key).await.unwrap(),
+        DeleteStatus::Deleted(1)
+    );
+    assert_eq!(backend.delete(&key).await.unwrap(), DeleteStatus::Missing);
+    for replica in &replicas {
+        assert_eq!(replica.len().await, 0);
+    }
+
+    let served = served.lock().unwrap();
+    let writes = served
+        .iter()
+        .filter(|(operation, _)| *operation == Operation::Write)
+        .count();
+    let reads = served
+        .iter()
+        .filter(|(operation, _)| *operation == Operation::Read)
+        .count();
+    assert_eq!(writes, 3);
+    assert_eq!(reads, 1);
+}
+
+#[tokio::test]
+async fn test_quorum_tolerates_failed_members() {
+    let replica = Arc::new(MemBackend::default());
+    let (backend, served) = observed(
+        vec![
+            ("down", Arc::new(FailingBackend::unreachable())),
+            ("up", replica.clone()),
+        ],
+        Some(1),
+    );
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .set::<Value>(&key, &Value::new(7), None)
+        .await
+        .unwrap();
+    let value = backend.get::<Value>(&key).await.unwrap().unwrap();
+
+    assert_eq!(value.data.index, 7);
+    assert!(served.lock().unwrap().iter().all(|(_, id)| id == "up"));
+}
+
+#[tokio::test]
+async fn test_quorum_not_reached() {
+    let (backend, _) = observed(
+        vec![
+            ("down", Arc::new(FailingBackend::unreachable())),
+            ("up", Arc::new(MemBackend::default())),
+        ],
+        None,
+    );
+    let key = CacheKey::from_str("key", "1");
+
+    let result = backend.set::<Value>(&key, &Value::new(1), None).await;
+    assert!(matches!(result, Err(BackendError::ConnectionError(_))));
+
+    let (backend, _) = observed(
+        vec![
+            ("broken", Arc::new(FailingBackend::broken())),
+            ("up", Arc::new(MemBackend::default())),
+        ],
+        None,
+    );
+    let result = backend.set::<Value>(&key, &Value::new(1), None).await;
+    assert!(matches!(result, Err(BackendError::InternalError(_))));
+}
+
+#[tokio::test]
+async fn test_read_fails_when_no_member_answers() {
+    let (backend, _) = observed(
+        vec![
+            ("a", Arc::new(FailingBackend::unreachable())),
+            ("b", Arc::new(FailingBackend::unreachable())),
+        ],
+        Some(1),
+    );
+
+    let result = backend.read(&CacheKey::from_str("key", "1")).await;
+    assert!(matches!(result, Err(BackendError::ConnectionError(_))));
+}
+
+#[test]
+fn test_invalid_quorum() {
+    let member: SharedBackend = Arc::new(MemBackend::default());
+    assert!(matches!(
+        ReplicatedBackend::builder()
+            .member("a", member.clone())
+            .quorum(2)
+            .build(),
+        Err(CompositionError::InvalidQuorum {
+            quorum: 2,
+            members: 1
+        })
+    ));
+    assert!(matches!(
+        ReplicatedBackend::builder()
+            .member("a", member)
+            .quorum(0)
+            .build(),
+        Err(CompositionError::InvalidQuorum { quorum: 0, .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_read_prefers_replica_with_value() {
+    let lagging = Arc::new(MemBackend::default());
+    let replica = Arc::new(MemBackend::default());
+    let (backend, served) = observed(
+        vec![("lagging", lagging.clone()), ("up", replica.clone())],
+        Some(1),
+    );
+    let key = CacheKey::from_str("key", "1");
+    let value = backend.get::<Value>(&key).await.unwrap();
+    assert!(value.is_none());
+
+    replica
+        .set::<Value>(&key, &Value::new(3), None)
+        .await
+        .unwrap();
+    let value = backend.get::<Value>(&key).await.unwrap().unwrap();
+
+    assert_eq!(value.data.index, 3);
+    assert_eq!(lagging.len().await, 0);
+    assert_eq!(
+        served.lock().unwrap().last(),
+        Some(&(Operation::Read, "up".to_owned()))
+    );
+}
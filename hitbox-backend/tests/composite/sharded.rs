@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use hitbox_backend::{
+    Backend, CacheBackend, CompositionError, DeleteStatus, ShardedBackend, SharedBackend,
+};
+use hitbox_core::CacheKey;
+
+use crate::{MemBackend, Value};
+
+fn keys(count: usize) -> Vec<CacheKey> {
+    (0..count)
+        .map(|index| CacheKey::from_str("key", &index.to_string()))
+        .collect()
+}
+
+fn members(count: usize) -> Vec<(String, Arc<MemBackend>)> {
+    (0..count)
+        .map(|index| (format!("shard-{index}"), Arc::new(MemBackend::default())))
+        .collect()
+}
+
+async fn total_len(members: &[(String, Arc<MemBackend>)]) -> usize {
+    let mut total = 0;
+    for (_, member) in members {
+        total += member.len().await;
+    }
+    total
+}
+
+fn sharded(members: &[(String, Arc<MemBackend>)]) -> ShardedBackend {
+    members
+        .iter()
+        .fold(ShardedBackend::builder(), |builder, (id, backend)| {
+            builder.shard(id.clone(), backend.clone() as SharedBackend)
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_roundtrip_through_shards() {
+    let members = members(3);
+    let backend = sharded(&members);
+
+    for (index, key) in keys(100).iter().enumerate() {
+        let value = Value::new(index as u32);
+        backend.set::<Value>(key, &value, None).await.unwrap();
+    }
+
+    for (index, key) in keys(100).iter().enumerate() {
+        let value = backend.get::<Value>(key).await.unwrap().unwrap();
+        assert_eq!(value.data.index, index as u32);
+    }
+
+    for (_, member) in &members {
+        assert!(member.len().await > 10, "keys should be spread over shards");
+    }
+    assert_eq!(total_len(&members).await, 100);
+
+    let key = &keys(1)[0];
+    assert_eq!(backend.delete(key).await.unwrap(), DeleteStatus::Deleted(1));
+    assert_eq!(backend.delete(key).await.unwrap(), DeleteStatus::Missing);
+}
+
+#[tokio::test]
+async fn test_adding_shard_remaps_fraction_of_keys() {
+    let before = sharded(&members(4));
+    let after = sharded(&members(5));
+
+    let keys = keys(2000);
+    let moved = keys
+        .iter()
+        .filter(|key| before.shard_for(key).unwrap() != after.shard_for(key).unwrap())
+        .count();
+
+    // Ideally 1/5 of the keys move to the new shard and nothing else changes.
+    assert!(moved < keys.len() * 3 / 10, "too many keys moved: {moved}");
+    for key in &keys {
+        let shard = after.shard_for(key).unwrap();
+        if shard != "shard-4" {
+            assert_eq!(before.shard_for(key).unwrap(), shard);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_unhealthy_shard_is_skipped() {
+    let backend = sharded(&members(3));
+    let keys = keys(500);
+
+    assert!(backend.set_healthy("shard-1", false));
+    assert_eq!(backend.is_healthy("shard-1"), Some(false));
+    for key in &keys {
+        assert_ne!(backend.shard_for(key).unwrap(), "shard-1");
+    }
+
+    assert!(backend.set_healthy("shard-1", true));
+    assert!(
+        keys.iter()
+            .any(|key| backend.shard_for(key).unwrap() == "shard-1")
+    );
+
+    assert!(!backend.set_healthy("unknown", false));
+    assert_eq!(backend.is_healthy("unknown"), None);
+}
+
+#[tokio::test]
+async fn test_no_healthy_shard() {
+    let backend = sharded(&members(1));
+    backend.set_healthy("shard-0", false);
+
+    let result = backend.read(&CacheKey::from_str("key", "1")).await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_build_errors() {
+    assert!(matches!(
+        ShardedBackend::builder().build(),
+        Err(CompositionError::NoMembers)
+    ));
+
+    let backend: SharedBackend = Arc::new(MemBackend::default());
+    assert!(matches!(
+        ShardedBackend::builder()
+            .shard("a", backend.clone())
+            .shard("a", backend.clone())
+            .build(),
+        Err(CompositionError::DuplicateMember(id)) if id == "a"
+    ));
+    assert!(matches!(
+        ShardedBackend::builder()
+            .weighted_shard("a", backend.clone(), u32::MAX)
+            .build(),
+        Err(CompositionError::WeightOverflow { id, weight: u32::MAX }) if id == "a"
+    ));
+    assert!(matches!(
+        ShardedBackend::builder()
+            .shard("a", backend.clone())
+            .weighted_shard("b", backend, 0)
+            .build(),
+        Err(CompositionError::ZeroWeight { id }) if id == "b"
+    ));
+}
@@ -0,0 +1,82 @@
+#![cfg(any(feature = "msgpack", feature = "cbor", feature = "postcard"))]
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use hitbox_backend::serializer::{Format, FormatExt};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum Kind {
+    Plain,
+    Tagged(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    kind: Kind,
+    etag: Option<String>,
+    created: DateTime<Utc>,
+}
+
+fn response() -> Response {
+    Response {
+        status: 200,
+        headers: vec![("content-type".to_owned(), "application/json".to_owned())],
+        body: vec![0, 159, 146, 150, 255],
+        kind: Kind::Tagged("v1".to_owned()),
+        etag: None,
+        created: DateTime::from_timestamp(1_700_000_000, 123_000_000).unwrap(),
+    }
+}
+
+fn assert_roundtrip(format: Arc<dyn Format>) {
+    let value = response();
+
+    let raw = format.serialize(&value).unwrap();
+
+    assert_eq!(format.deserialize::<Response>(&raw).unwrap(), value);
+    assert!(
+        format
+            .deserialize::<Response>(&raw[..raw.len() / 2])
+            .is_err()
+    );
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_message_pack_roundtrip() {
+    use hitbox_backend::serializer::MessagePackFormat;
+
+    assert_roundtrip(Arc::new(MessagePackFormat));
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_message_pack_uses_field_names() {
+    use hitbox_backend::serializer::MessagePackFormat;
+
+    let raw = MessagePackFormat.serialize(&response()).unwrap();
+
+    // Map key as fixstr of length 6
+    assert!(raw.windows(7).any(|window| window == b"\xa6status"));
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_roundtrip() {
+    use hitbox_backend::serializer::CborFormat;
+
+    assert_roundtrip(Arc::new(CborFormat));
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn test_postcard_roundtrip() {
+    use hitbox_backend::serializer::PostcardFormat;
+
+    assert_roundtrip(Arc::new(PostcardFormat));
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "rkyv")]
+
+use std::any::TypeId;
+
+use bytes::Bytes;
+use hitbox_backend::serializer::{Format, FormatError, FormatExt, RkyvFormat, ZeroCopy};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Page {
+    status: u16,
+    body: Bytes,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize)]
+struct PageArchive {
+    status: u16,
+    body: Bytes,
+}
+
+impl ZeroCopy for Page {
+    type Archive = PageArchive;
+
+    fn to_archive(&self) -> PageArchive {
+        PageArchive {
+            status: self.status,
+            body: self.body.clone(),
+        }
+    }
+
+    fn from_archived(archived: &ArchivedPageArchive, data: &Bytes) -> Result<Self, FormatError> {
+        Ok(Page {
+            status: archived.status.into(),
+            body: data.slice_ref(archived.body.as_slice()),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Unregistered {
+    status: u16,
+}
+
+fn page() -> Page {
+    Page {
+        status: 200,
+        body: Bytes::from(vec![7; 4096]),
+    }
+}
+
+fn read(format: &RkyvFormat, data: &Bytes) -> Result<Page, FormatError> {
+    format
+        .deserialize_typed(data, TypeId::of::<Page>())
+        .unwrap()
+        .map(|value| *value.downcast::<Page>().unwrap())
+}
+
+#[test]
+fn test_roundtrip_shares_buffer() {
+    let format = RkyvFormat::new().register::<Page>();
+
+    let raw = Bytes::from(format.serialize_typed(&page()).unwrap().unwrap());
+    let restored = read(&format, &raw).unwrap();
+
+    assert_eq!(restored, page());
+    assert!(raw.as_ptr_range().contains(&restored.body.as_ptr()));
+}
+
+#[test]
+fn test_rejects_invalid_archive() {
+    let format = RkyvFormat::new().register::<Page>();
+    let raw = format.serialize_typed(&page()).unwrap().unwrap();
+
+    assert!(read(&format, &Bytes::copy_from_slice(&raw[..raw.len() - 4])).is_err());
+    assert!(read(&format, &Bytes::from_static(b"\xff\xfe")).is_err());
+}
+
+#[test]
+fn test_rejects_unregistered_type() {
+    let format = RkyvFormat::new().register::<Page>();
+    let value = Unregistered { status: 200 };
+
+    assert!(format.serialize_typed(&value).is_none());
+    assert!(format.serialize(&value).is_err());
+    assert!(
+        format
+            .deserialize_typed(&Bytes::new(), TypeId::of::<Unregistered>())
+            .is_none()
+    );
+}
@@ -1,6 +1,6 @@
 use crate::error::ConfigError;
 use hitbox_backend::serializer::{BincodeFormat, Format, JsonFormat};
-use hitbox_backend::{Backend as BackendTrait, CacheKeyFormat};
+use hitbox_backend::{Backend as BackendTrait, CacheKeyFormat, Envelope};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -8,40 +8,106 @@ use std::sync::Arc;
 #[serde(tag = "type")]
 pub enum Backend {
     Moka(BackendConfig<Moka>),
+    Stretto(BackendConfig<Stretto>),
     FeOxDb(BackendConfig<FeOxDb>),
+    Fs(BackendConfig<Fs>),
+    Sqlite(BackendConfig<Sqlite>),
+    Postgres(BackendConfig<Postgres>),
     Redis(BackendConfig<Redis>),
+    Memcached(BackendConfig<Memcached>),
+    Tarantool(BackendConfig<Tarantool>),
+    Sharded(BackendConfig<Sharded>),
+    Failover(BackendConfig<Failover>),
+    Replicated(BackendConfig<Replicated>),
 }
 
 impl Backend {
     pub fn into_backend(self) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
+        let integrity = self.value().integrity.clone();
+        let encryption = self.value().encryption.clone();
+        let mut backend = self.build()?;
+        if let Some(integrity) = integrity {
+            backend = integrity.wrap(backend)?;
+        }
+        match encryption {
+            Some(encryption) => encryption.wrap(backend),
+            None => Ok(backend),
+        }
+    }
+
+    fn value(&self) -> &ValueFormat {
+        match self {
+            Backend::Moka(config) => &config.value,
+            Backend::Stretto(config) => &config.value,
+            Backend::FeOxDb(config) => &config.value,
+            Backend::Fs(config) => &config.value,
+            Backend::Sqlite(config) => &config.value,
+            Backend::Postgres(config) => &config.value,
+            Backend::Redis(config) => &config.value,
+            Backend::Memcached(config) => &config.value,
+            Backend::Tarantool(config) => &config.value,
+            Backend::Sharded(config) => &config.value,
+            Backend::Failover(config) => &config.value,
+            Backend::Replicated(config) => &config.value,
+        }
+    }
+
+    fn build(self) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
         match self {
             #[cfg(feature = "moka")]
             Backend::Moka(config) => {
                 use hitbox_moka::MokaBackend;
 
                 let key_format = config.key.format.to_cache_key_format();
-                let serializer = config.value.format.to_serializer();
+                let serializer = config.value.to_serializer()?;
                 let compressor = config.value.compression.to_compressor()?;
 
-                let backend = MokaBackend::builder(config.backend.max_capacity)
+                let mut builder = MokaBackend::builder(config.backend.max_capacity)
                     .key_format(key_format)
                     .value_format(serializer)
-                    .compressor(compressor)
-                    .build();
+                    .compressor(compressor);
 
-                Ok(Arc::new(backend))
+                if let Some(bytes) = config.backend.max_bytes {
+                    builder = builder.max_bytes(bytes);
+                }
+                if let Some(idle) = config.backend.time_to_idle_ms {
+                    builder = builder.time_to_idle(std::time::Duration::from_millis(idle));
+                }
+
+                Ok(Arc::new(builder.build()))
             }
             #[cfg(not(feature = "moka"))]
             Backend::Moka(_) => Err(ConfigError::BackendNotAvailable("Moka".to_string())),
+            #[cfg(feature = "stretto")]
+            Backend::Stretto(config) => {
+                use hitbox_stretto::StrettoBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let backend =
+                    StrettoBackend::builder(config.backend.max_items, config.backend.max_cost)
+                        .key_format(key_format)
+                        .value_format(serializer)
+                        .compressor(compressor)
+                        .build()
+                        .map_err(|e| ConfigError::BackendNotAvailable(format!("Stretto: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "stretto"))]
+            Backend::Stretto(_) => Err(ConfigError::BackendNotAvailable("Stretto".to_string())),
             #[cfg(feature = "feoxdb")]
             Backend::FeOxDb(config) => {
                 use hitbox_feoxdb::FeOxDbBackend;
 
                 let key_format = config.key.format.to_cache_key_format();
-                let serializer = config.value.format.to_serializer();
+                let serializer = config.value.to_serializer()?;
                 let compressor = config.value.compression.to_compressor()?;
 
                 let mut builder = FeOxDbBackend::builder()
+                    .sync(config.backend.sync.into_sync_policy())
                     .key_format(key_format)
                     .value_format(serializer)
                     .compressor(compressor);
@@ -49,6 +115,15 @@ impl Backend {
                 if let Some(path) = config.backend.path {
                     builder = builder.path(path);
                 }
+                if let Some(bytes) = config.backend.max_memory {
+                    builder = builder.max_memory(bytes);
+                }
+                if let Some(bytes) = config.backend.file_size {
+                    builder = builder.file_size(bytes);
+                }
+                if let Some(interval) = config.backend.sweep_interval_ms {
+                    builder = builder.sweep_interval(std::time::Duration::from_millis(interval));
+                }
 
                 let backend = builder
                     .build()
@@ -58,16 +133,103 @@ impl Backend {
             }
             #[cfg(not(feature = "feoxdb"))]
             Backend::FeOxDb(_) => Err(ConfigError::BackendNotAvailable("FeOxDb".to_string())),
+            #[cfg(feature = "fs")]
+            Backend::Fs(config) => {
+                use hitbox_fs::FsBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let mut builder = FsBackend::builder(config.backend.root)
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor);
+
+                if let Some(max_size) = config.backend.max_size {
+                    builder = builder.max_size(max_size);
+                }
+                if let Some(interval) = config.backend.sweep_interval_ms {
+                    builder = builder.sweep_interval(std::time::Duration::from_millis(interval));
+                }
+
+                let backend = builder
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Fs: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "fs"))]
+            Backend::Fs(_) => Err(ConfigError::BackendNotAvailable("Fs".to_string())),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite(config) => {
+                use hitbox_sqlite::SqliteBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let mut builder = SqliteBackend::builder()
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor);
+
+                if let Some(path) = config.backend.path {
+                    builder = builder.path(path);
+                }
+                if let Some(interval) = config.backend.sweep_interval_ms {
+                    builder = builder.sweep_interval(std::time::Duration::from_millis(interval));
+                }
+
+                let backend = builder
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Sqlite: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "sqlite"))]
+            Backend::Sqlite(_) => Err(ConfigError::BackendNotAvailable("Sqlite".to_string())),
+            #[cfg(feature = "postgres")]
+            Backend::Postgres(config) => {
+                use hitbox_postgres::PostgresBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let mut builder = PostgresBackend::builder()
+                    .connection_string(config.backend.connection_string)
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor);
+
+                if let Some(size) = config.backend.pool_size {
+                    builder = builder.pool_size(size);
+                }
+                if let Some(interval) = config.backend.sweep_interval_ms {
+                    builder = builder.sweep_interval(std::time::Duration::from_millis(interval));
+                }
+
+                let backend = builder
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Postgres: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "postgres"))]
+            Backend::Postgres(_) => Err(ConfigError::BackendNotAvailable("Postgres".to_string())),
             #[cfg(feature = "redis")]
             Backend::Redis(config) => {
                 use hitbox_redis::RedisBackend;
 
                 let key_format = config.key.format.to_cache_key_format();
-                let serializer = config.value.format.to_serializer();
+                let serializer = config.value.to_serializer()?;
                 let compressor = config.value.compression.to_compressor()?;
 
                 let backend = RedisBackend::builder()
-                    .server(config.backend.connection_string)
+                    .topology(config.backend.connection.into_topology())
+                    .connection_options(config.backend.options.into_connection_options()?)
+                    .hash_tag(config.backend.hash_tag.into_hash_tag())
                     .key_format(key_format)
                     .value_format(serializer)
                     .compressor(compressor)
@@ -78,6 +240,137 @@ impl Backend {
             }
             #[cfg(not(feature = "redis"))]
             Backend::Redis(_) => Err(ConfigError::BackendNotAvailable("Redis".to_string())),
+            #[cfg(feature = "memcached")]
+            Backend::Memcached(config) => {
+                use hitbox_memcached::MemcachedBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let mut builder = MemcachedBackend::builder()
+                    .servers(config.backend.servers)
+                    .oversized(config.backend.oversized.into_oversized())
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor);
+
+                if let Some(timeout) = config.backend.connection_timeout_ms {
+                    builder = builder.connection_timeout(std::time::Duration::from_millis(timeout));
+                }
+                if let Some(timeout) = config.backend.response_timeout_ms {
+                    builder = builder.response_timeout(std::time::Duration::from_millis(timeout));
+                }
+                if let Some(size) = config.backend.max_item_size {
+                    builder = builder.max_item_size(size);
+                }
+
+                Ok(Arc::new(builder.build()))
+            }
+            #[cfg(not(feature = "memcached"))]
+            Backend::Memcached(_) => Err(ConfigError::BackendNotAvailable("Memcached".to_string())),
+            #[cfg(feature = "tarantool")]
+            Backend::Tarantool(config) => {
+                use hitbox_tarantool::TarantoolBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let backend = TarantoolBackend::builder()
+                    .host(config.backend.host)
+                    .port(config.backend.port)
+                    .user(config.backend.user)
+                    .password(config.backend.password)
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor)
+                    .build();
+
+                Ok(Arc::new(backend))
+            }
+            #[cfg(not(feature = "tarantool"))]
+            Backend::Tarantool(_) => Err(ConfigError::BackendNotAvailable("Tarantool".to_string())),
+            Backend::Sharded(config) => {
+                use hitbox_backend::ShardedBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let builder = config.backend.shards.into_iter().try_fold(
+                    ShardedBackend::builder().virtual_nodes(config.backend.virtual_nodes),
+                    |builder, shard| {
+                        let backend = shard.backend.into_backend()?;
+                        Ok::<_, ConfigError>(builder.weighted_shard(
+                            shard.id,
+                            backend,
+                            shard.weight,
+                        ))
+                    },
+                )?;
+
+                let backend = builder
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor)
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Sharded: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            Backend::Failover(config) => {
+                use hitbox_backend::FailoverBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let builder = config.backend.members.into_iter().try_fold(
+                    FailoverBackend::builder(),
+                    |builder, member| {
+                        let backend = member.backend.into_backend()?;
+                        Ok::<_, ConfigError>(builder.member(member.id, backend))
+                    },
+                )?;
+
+                let backend = builder
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor)
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Failover: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
+            Backend::Replicated(config) => {
+                use hitbox_backend::ReplicatedBackend;
+
+                let key_format = config.key.format.to_cache_key_format();
+                let serializer = config.value.to_serializer()?;
+                let compressor = config.value.compression.to_compressor()?;
+
+                let mut builder = config.backend.members.into_iter().try_fold(
+                    ReplicatedBackend::builder(),
+                    |builder, member| {
+                        let backend = member.backend.into_backend()?;
+                        Ok::<_, ConfigError>(builder.member(member.id, backend))
+                    },
+                )?;
+
+                if let Some(quorum) = config.backend.quorum {
+                    builder = builder.quorum(quorum);
+                }
+
+                let backend = builder
+                    .key_format(key_format)
+                    .value_format(serializer)
+                    .compressor(compressor)
+                    .build()
+                    .map_err(|e| ConfigError::BackendNotAvailable(format!("Replicated: {}", e)))?;
+
+                Ok(Arc::new(backend))
+            }
         }
     }
 }
@@ -100,6 +393,178 @@ pub struct ValueFormat {
     pub format: ValueSerialization,
     #[serde(default)]
     pub compression: Compression,
+    #[serde(default)]
+    pub envelope: Option<ValueEnvelope>,
+    #[serde(default)]
+    pub integrity: Option<ValueIntegrity>,
+    #[serde(default)]
+    pub encryption: Option<ValueEncryption>,
+}
+
+impl ValueFormat {
+    /// Convert configuration value format to backend format, wrapped in an
+    /// envelope if one is configured
+    pub fn to_serializer(&self) -> Result<Arc<dyn Format>, ConfigError> {
+        let format = self.format.to_serializer()?;
+        let Some(config) = &self.envelope else {
+            return Ok(format);
+        };
+        let mut envelope = Envelope::new(format);
+        #[cfg(feature = "rkyv")]
+        {
+            envelope = envelope.register_format(ValueSerialization::Rkyv.to_serializer()?);
+        }
+        if let Some(fallback) = &config.fallback {
+            envelope = envelope.fallback(fallback.to_serializer()?);
+        }
+        Ok(Arc::new(envelope))
+    }
+}
+
+/// Envelope recording format and compression of every stored value, so they
+/// can change without flushing the cache
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub struct ValueEnvelope {
+    /// Format of values stored without an envelope, the configured one by default
+    #[serde(default)]
+    pub fallback: Option<ValueSerialization>,
+}
+
+/// Checksum stored with every value and verified on read, corrupted entries
+/// are evicted
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub struct ValueIntegrity {
+    #[serde(default)]
+    pub checksum: IntegrityChecksum,
+}
+
+impl ValueIntegrity {
+    /// Wrap backend into one verifying checksums of its values
+    #[cfg(feature = "integrity")]
+    fn wrap(
+        self,
+        backend: Arc<dyn BackendTrait + Send + 'static>,
+    ) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
+        use hitbox_backend::{Checksum, IntegrityBackend};
+
+        let checksum = match self.checksum {
+            IntegrityChecksum::Xxh3 => Checksum::Xxh3,
+            IntegrityChecksum::Crc32c => Checksum::Crc32c,
+        };
+        Ok(Arc::new(IntegrityBackend::new(backend, checksum)))
+    }
+
+    #[cfg(not(feature = "integrity"))]
+    fn wrap(
+        self,
+        _backend: Arc<dyn BackendTrait + Send + 'static>,
+    ) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
+        Err(ConfigError::BackendNotAvailable(
+            "Integrity requested but 'integrity' feature is not enabled".to_string(),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum IntegrityChecksum {
+    #[default]
+    Xxh3,
+    Crc32c,
+}
+
+/// Encryption of stored values. The first key encrypts new values, the others
+/// only decrypt values written before a key rotation
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ValueEncryption {
+    pub keys: Vec<EncryptionKey>,
+}
+
+impl ValueEncryption {
+    /// Wrap backend into one encrypting its values with the configured keys
+    #[cfg(feature = "encryption")]
+    fn wrap(
+        self,
+        backend: Arc<dyn BackendTrait + Send + 'static>,
+    ) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
+        use hitbox_backend::EncryptingBackend;
+
+        let mut keys = self.keys.into_iter().map(EncryptionKey::load);
+        let Some(current) = keys.next() else {
+            return Err(ConfigError::InvalidEncryptionKey(
+                "at least one key is required".to_string(),
+            ));
+        };
+        let backend =
+            keys.try_fold(EncryptingBackend::new(backend, current?), |backend, key| {
+                let key = key?;
+                let id = key.id();
+                backend
+                    .previous_key(key)
+                    .map_err(|e| ConfigError::InvalidEncryptionKey(format!("key {id}: {e}")))
+            })?;
+        Ok(Arc::new(backend))
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn wrap(
+        self,
+        _backend: Arc<dyn BackendTrait + Send + 'static>,
+    ) -> Result<Arc<dyn BackendTrait + Send + 'static>, ConfigError> {
+        Err(ConfigError::BackendNotAvailable(
+            "Encryption requested but 'encryption' feature is not enabled".to_string(),
+        ))
+    }
+}
+
+/// Encryption key, read as base64 from an environment variable or a file
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EncryptionKey {
+    pub id: u32,
+    #[serde(default)]
+    pub cipher: EncryptionCipher,
+    #[serde(flatten)]
+    pub source: EncryptionKeySource,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum EncryptionKeySource {
+    Env { env: String },
+    File { file: std::path::PathBuf },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+pub enum EncryptionCipher {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+#[cfg(feature = "encryption")]
+impl EncryptionKey {
+    fn load(self) -> Result<hitbox_backend::EncryptionKey, ConfigError> {
+        use base64::Engine;
+        use hitbox_backend::Cipher;
+
+        let invalid = |reason: String| {
+            ConfigError::InvalidEncryptionKey(format!("key {}: {reason}", self.id))
+        };
+        let encoded = match &self.source {
+            EncryptionKeySource::Env { env } => std::env::var(env)
+                .map_err(|e| invalid(format!("environment variable {env}: {e}")))?,
+            EncryptionKeySource::File { file } => std::fs::read_to_string(file)
+                .map_err(|e| invalid(format!("file {}: {e}", file.display())))?,
+        };
+        let key = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| invalid(format!("invalid base64: {e}")))?;
+        let cipher = match self.cipher {
+            EncryptionCipher::Aes256Gcm => Cipher::Aes256Gcm,
+            EncryptionCipher::ChaCha20Poly1305 => Cipher::ChaCha20Poly1305,
+        };
+        hitbox_backend::EncryptionKey::new(self.id, cipher, &key)
+            .map_err(|e| invalid(e.to_string()))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -122,13 +587,57 @@ impl KeySerialization {
 pub enum ValueSerialization {
     Json,
     Bincode,
+    MessagePack,
+    Cbor,
+    Postcard,
+    Rkyv,
 }
 
 impl ValueSerialization {
-    pub fn to_serializer(&self) -> Arc<dyn Format> {
+    /// Convert configuration value serialization format to backend format
+    pub fn to_serializer(&self) -> Result<Arc<dyn Format>, ConfigError> {
         match self {
-            ValueSerialization::Json => Arc::new(JsonFormat),
-            ValueSerialization::Bincode => Arc::new(BincodeFormat),
+            ValueSerialization::Json => Ok(Arc::new(JsonFormat)),
+            ValueSerialization::Bincode => Ok(Arc::new(BincodeFormat)),
+            #[cfg(feature = "msgpack")]
+            ValueSerialization::MessagePack => {
+                use hitbox_backend::serializer::MessagePackFormat;
+                Ok(Arc::new(MessagePackFormat))
+            }
+            #[cfg(not(feature = "msgpack"))]
+            ValueSerialization::MessagePack => Err(ConfigError::BackendNotAvailable(
+                "MessagePack format requested but 'msgpack' feature is not enabled".to_string(),
+            )),
+            #[cfg(feature = "cbor")]
+            ValueSerialization::Cbor => {
+                use hitbox_backend::serializer::CborFormat;
+                Ok(Arc::new(CborFormat))
+            }
+            #[cfg(not(feature = "cbor"))]
+            ValueSerialization::Cbor => Err(ConfigError::BackendNotAvailable(
+                "Cbor format requested but 'cbor' feature is not enabled".to_string(),
+            )),
+            #[cfg(feature = "postcard")]
+            ValueSerialization::Postcard => {
+                use hitbox_backend::serializer::PostcardFormat;
+                Ok(Arc::new(PostcardFormat))
+            }
+            #[cfg(not(feature = "postcard"))]
+            ValueSerialization::Postcard => Err(ConfigError::BackendNotAvailable(
+                "Postcard format requested but 'postcard' feature is not enabled".to_string(),
+            )),
+            #[cfg(feature = "rkyv")]
+            ValueSerialization::Rkyv => {
+                use hitbox_backend::serializer::RkyvFormat;
+                use hitbox_http::SerializableHttpResponse;
+                Ok(Arc::new(
+                    RkyvFormat::new().register::<SerializableHttpResponse>(),
+                ))
+            }
+            #[cfg(not(feature = "rkyv"))]
+            ValueSerialization::Rkyv => Err(ConfigError::BackendNotAvailable(
+                "Rkyv format requested but 'rkyv' feature is not enabled".to_string(),
+            )),
         }
     }
 }
@@ -146,6 +655,29 @@ pub enum Compression {
         #[serde(default = "default_zstd_level")]
         level: i32,
     },
+    Lz4 {
+        #[serde(default)]
+        level: i32,
+    },
+    Brotli {
+        #[serde(default = "default_brotli_level")]
+        level: u32,
+    },
+    Snappy,
+    /// Compress with `codec` only values of at least `threshold` bytes which
+    /// shrink, tagging every value with its codec. Untagged values written
+    /// before are decoded with `fallback`.
+    Adaptive {
+        #[serde(default = "default_compression_threshold")]
+        threshold: usize,
+        codec: Box<Compression>,
+        #[serde(default)]
+        fallback: Option<Box<Compression>>,
+    },
+}
+
+fn default_compression_threshold() -> usize {
+    hitbox_backend::DEFAULT_COMPRESSION_THRESHOLD
 }
 
 fn default_gzip_level() -> u32 {
@@ -156,6 +688,10 @@ fn default_zstd_level() -> i32 {
     3
 }
 
+fn default_brotli_level() -> u32 {
+    9
+}
+
 impl Compression {
     /// Convert configuration compression format to backend compressor
     pub fn to_compressor(
@@ -184,21 +720,413 @@ impl Compression {
             Compression::Zstd { .. } => Err(ConfigError::BackendNotAvailable(
                 "Zstd compression requested but 'zstd' feature is not enabled".to_string(),
             )),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 { level } => {
+                use hitbox_backend::Lz4Compressor;
+                Ok(Arc::new(Lz4Compressor::with_level(*level)))
+            }
+            #[cfg(not(feature = "lz4"))]
+            Compression::Lz4 { .. } => Err(ConfigError::BackendNotAvailable(
+                "Lz4 compression requested but 'lz4' feature is not enabled".to_string(),
+            )),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli { level } => {
+                use hitbox_backend::BrotliCompressor;
+                Ok(Arc::new(BrotliCompressor::with_level(*level)))
+            }
+            #[cfg(not(feature = "brotli"))]
+            Compression::Brotli { .. } => Err(ConfigError::BackendNotAvailable(
+                "Brotli compression requested but 'brotli' feature is not enabled".to_string(),
+            )),
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => {
+                use hitbox_backend::SnappyCompressor;
+                Ok(Arc::new(SnappyCompressor))
+            }
+            #[cfg(not(feature = "snappy"))]
+            Compression::Snappy => Err(ConfigError::BackendNotAvailable(
+                "Snappy compression requested but 'snappy' feature is not enabled".to_string(),
+            )),
+            Compression::Adaptive {
+                threshold,
+                codec,
+                fallback,
+            } => {
+                use hitbox_backend::AdaptiveCompressor;
+                let mut compressor =
+                    AdaptiveCompressor::new(codec.to_compressor()?).threshold(*threshold);
+                if let Some(fallback) = fallback {
+                    compressor = compressor.fallback(fallback.to_compressor()?);
+                }
+                Ok(Arc::new(compressor))
+            }
         }
     }
 }
 
+/// Moka in-memory backend, `max_capacity` is the number of cached entries.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Moka {
     pub max_capacity: u64,
+    /// Bound the cache by the size of entries in bytes instead, replacing `max_capacity`.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub time_to_idle_ms: Option<u64>,
+}
+
+/// Stretto backend, `max_cost` is the total size of cached values in bytes and
+/// `max_items` the expected number of entries, which sizes admission counters,
+/// 10000 by default.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Stretto {
+    #[serde(default = "default_stretto_max_items")]
+    pub max_items: usize,
+    pub max_cost: i64,
+}
+
+fn default_stretto_max_items() -> usize {
+    10_000
 }
 
+/// FeOxDB embedded backend, in-memory if `path` is not set.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct FeOxDb {
     pub path: Option<String>,
+    /// Memory limit of the store in bytes.
+    #[serde(default)]
+    pub max_memory: Option<usize>,
+    /// Size of the data file in bytes.
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    #[serde(default)]
+    pub sync: FeOxDbSync,
+    #[serde(default)]
+    pub sweep_interval_ms: Option<u64>,
+}
+
+/// When FeOxDB flushes written records to disk.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum FeOxDbSync {
+    #[default]
+    Background,
+    EveryWrite,
+    Interval {
+        interval_ms: u64,
+    },
+}
+
+#[cfg(feature = "feoxdb")]
+impl FeOxDbSync {
+    fn into_sync_policy(self) -> hitbox_feoxdb::SyncPolicy {
+        use hitbox_feoxdb::SyncPolicy;
+
+        match self {
+            FeOxDbSync::Background => SyncPolicy::Background,
+            FeOxDbSync::EveryWrite => SyncPolicy::EveryWrite,
+            FeOxDbSync::Interval { interval_ms } => {
+                SyncPolicy::Interval(std::time::Duration::from_millis(interval_ms))
+            }
+        }
+    }
 }
 
+/// Filesystem backend storing every entry in its own file under `root`.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
-pub struct Redis {
+pub struct Fs {
+    pub root: String,
+    /// Total size of stored files in bytes, unlimited if not set.
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    #[serde(default)]
+    pub sweep_interval_ms: Option<u64>,
+}
+
+/// SQLite backend, in-memory database if `path` is not set.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Sqlite {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub sweep_interval_ms: Option<u64>,
+}
+
+/// PostgreSQL backend storing entries in an `UNLOGGED` table.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Postgres {
     pub connection_string: String,
+    #[serde(default)]
+    pub pool_size: Option<usize>,
+    #[serde(default)]
+    pub sweep_interval_ms: Option<u64>,
+}
+
+/// Memcached backend, keys are spread over `servers` (`host:port`).
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Memcached {
+    pub servers: Vec<String>,
+    #[serde(default)]
+    pub connection_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub response_timeout_ms: Option<u64>,
+    /// Largest value stored in a single item in bytes.
+    #[serde(default)]
+    pub max_item_size: Option<usize>,
+    #[serde(default)]
+    pub oversized: MemcachedOversized,
+}
+
+/// Handling of values larger than the memcached item size limit.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum MemcachedOversized {
+    #[default]
+    Skip,
+    Chunk,
+}
+
+#[cfg(feature = "memcached")]
+impl MemcachedOversized {
+    fn into_oversized(self) -> hitbox_memcached::Oversized {
+        use hitbox_memcached::Oversized;
+
+        match self {
+            MemcachedOversized::Skip => Oversized::Skip,
+            MemcachedOversized::Chunk => Oversized::Chunk,
+        }
+    }
+}
+
+/// Tarantool backend, the cache space is created on first use.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Tarantool {
+    #[serde(default = "default_tarantool_host")]
+    pub host: String,
+    #[serde(default = "default_tarantool_port")]
+    pub port: u16,
+    #[serde(default = "default_tarantool_credential")]
+    pub user: String,
+    #[serde(default = "default_tarantool_credential")]
+    pub password: String,
+}
+
+fn default_tarantool_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_tarantool_port() -> u16 {
+    3301
+}
+
+fn default_tarantool_credential() -> String {
+    "hitbox".to_string()
+}
+
+/// Redis backend: a single node, Redis Cluster or Sentinel group.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Redis {
+    #[serde(flatten)]
+    pub connection: RedisConnection,
+    #[serde(flatten)]
+    pub options: RedisOptions,
+    #[serde(default)]
+    pub hash_tag: RedisHashTag,
+}
+
+/// Redis deployment, selected by the field present in the config.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RedisConnection {
+    Single { connection_string: String },
+    Cluster { cluster: RedisCluster },
+    Sentinel { sentinel: RedisSentinel },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RedisCluster {
+    pub nodes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RedisSentinel {
+    pub master: String,
+    pub nodes: Vec<String>,
+}
+
+/// Connection settings of the Redis backend.
+///
+/// `tls` requires the `redis-tls` feature, `client_cache` the
+/// `redis-client-cache` feature.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RedisOptions {
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub database: Option<i64>,
+    #[serde(default)]
+    pub tls: bool,
+    #[serde(default)]
+    pub connections: Option<usize>,
+    #[serde(default)]
+    pub connection_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub response_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub retry: Option<RedisRetry>,
+    #[serde(default)]
+    pub client_cache: Option<RedisClientCache>,
+}
+
+/// Reconnect policy with exponential backoff.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RedisRetry {
+    pub attempts: usize,
+    pub min_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+/// Local cache of recently read values kept coherent by Redis `CLIENT TRACKING`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RedisClientCache {
+    /// Maximum number of keys cached by each connection, 10000 by default.
+    #[serde(default)]
+    pub capacity: Option<std::num::NonZeroUsize>,
+    /// Upper bound of time a value stays in the local cache, 30 minutes by default.
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+}
+
+/// Redis Cluster hash tag applied to storage keys.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RedisHashTag {
+    #[default]
+    Disabled,
+    Prefix,
+    Fixed(String),
+}
+
+#[cfg(feature = "redis")]
+impl RedisConnection {
+    fn into_topology(self) -> hitbox_redis::Topology {
+        use hitbox_redis::Topology;
+
+        match self {
+            RedisConnection::Single { connection_string } => Topology::Single(connection_string),
+            RedisConnection::Cluster { cluster } => Topology::Cluster(cluster.nodes),
+            RedisConnection::Sentinel { sentinel } => Topology::Sentinel {
+                master: sentinel.master,
+                nodes: sentinel.nodes,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisOptions {
+    fn into_connection_options(self) -> Result<hitbox_redis::ConnectionOptions, ConfigError> {
+        use hitbox_redis::{ClientCache, ConnectionOptions, Retry};
+        use std::time::Duration;
+
+        #[cfg(not(feature = "redis-tls"))]
+        if self.tls {
+            return Err(ConfigError::BackendNotAvailable("Redis TLS".to_string()));
+        }
+        #[cfg(not(feature = "redis-client-cache"))]
+        if self.client_cache.is_some() {
+            return Err(ConfigError::BackendNotAvailable(
+                "Redis client cache".to_string(),
+            ));
+        }
+
+        Ok(ConnectionOptions {
+            connection_timeout: self.connection_timeout_ms.map(Duration::from_millis),
+            response_timeout: self.response_timeout_ms.map(Duration::from_millis),
+            retry: self.retry.map(|retry| Retry {
+                attempts: retry.attempts,
+                min_delay: Duration::from_millis(retry.min_delay_ms),
+                max_delay: Duration::from_millis(retry.max_delay_ms),
+            }),
+            connections: self.connections.unwrap_or(1),
+            username: self.username,
+            password: self.password,
+            database: self.database,
+            tls: self.tls,
+            client_cache: self.client_cache.map(|cache| {
+                let default = ClientCache::default();
+                ClientCache {
+                    capacity: cache.capacity.unwrap_or(default.capacity),
+                    ttl: cache
+                        .ttl_ms
+                        .map(Duration::from_millis)
+                        .unwrap_or(default.ttl),
+                }
+            }),
+        })
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisHashTag {
+    fn into_hash_tag(self) -> hitbox_redis::HashTag {
+        use hitbox_redis::HashTag;
+
+        match self {
+            RedisHashTag::Disabled => HashTag::Disabled,
+            RedisHashTag::Prefix => HashTag::Prefix,
+            RedisHashTag::Fixed(tag) => HashTag::Fixed(tag),
+        }
+    }
+}
+
+/// Consistent-hash sharding over several backends.
+///
+/// Values are serialized and compressed with the top-level `value` settings;
+/// only the `key` format of each shard is used for storage.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Sharded {
+    pub shards: Vec<Shard>,
+    #[serde(default = "default_virtual_nodes")]
+    pub virtual_nodes: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Shard {
+    pub id: String,
+    #[serde(default = "default_shard_weight")]
+    pub weight: u32,
+    pub backend: Backend,
+}
+
+/// Primary backend with secondaries used when it is unreachable.
+///
+/// Members are tried in the listed order.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Failover {
+    pub members: Vec<Member>,
+}
+
+/// Writes fanned out to every member, reads served by the first member that
+/// has the value; a miss is returned only after every member answered.
+///
+/// `quorum` defaults to the number of members.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Replicated {
+    pub members: Vec<Member>,
+    #[serde(default)]
+    pub quorum: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Member {
+    pub id: String,
+    pub backend: Backend,
+}
+
+fn default_virtual_nodes() -> u32 {
+    hitbox_backend::composite::DEFAULT_VIRTUAL_NODES
+}
+
+fn default_shard_weight() -> u32 {
+    1
 }
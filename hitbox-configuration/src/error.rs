@@ -31,6 +31,10 @@ pub enum ConfigError {
         error: regex::Error,
     },
 
+    /// Encryption key can't be loaded
+    #[error("Invalid encryption key: {0}")]
+    InvalidEncryptionKey(String),
+
     /// Backend not available (feature not enabled)
     #[error("Backend '{0}' is not available. Enable the corresponding feature flag.")]
     BackendNotAvailable(String),
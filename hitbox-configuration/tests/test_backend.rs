@@ -1,6 +1,10 @@
+use std::num::NonZeroUsize;
+
 use hitbox_configuration::backend::{
-    Backend, BackendConfig, Compression, KeyFormat, KeySerialization, Moka, ValueFormat,
-    ValueSerialization,
+    Backend, BackendConfig, Compression, FeOxDb, FeOxDbSync, Fs, KeyFormat, KeySerialization,
+    Memcached, MemcachedOversized, Moka, Postgres, RedisClientCache, RedisCluster, RedisConnection,
+    RedisHashTag, RedisOptions, RedisRetry, RedisSentinel, Sqlite, Stretto, Tarantool,
+    ValueEnvelope, ValueFormat, ValueSerialization,
 };
 
 #[test]
@@ -30,6 +34,60 @@ value:
     }
 }
 
+#[test]
+fn test_stretto_backend_deserialize() {
+    let yaml = r#"
+type: Stretto
+max_items: 10000
+max_cost: 1048576
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Stretto(config) => {
+            assert_eq!(
+                config.backend,
+                Stretto {
+                    max_items: 10000,
+                    max_cost: 1048576
+                }
+            );
+            assert_eq!(config.value.format, ValueSerialization::Bincode);
+        }
+        _ => panic!("expected Stretto backend"),
+    }
+}
+
+#[test]
+fn test_stretto_backend_default_max_items() {
+    let yaml = r#"
+type: Stretto
+max_cost: 1048576
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Stretto(config) => assert_eq!(
+            config.backend,
+            Stretto {
+                max_items: 10_000,
+                max_cost: 1048576
+            }
+        ),
+        _ => panic!("expected Stretto backend"),
+    }
+}
+
 #[test]
 fn test_feoxdb_backend_deserialize() {
     let yaml = r#"
@@ -57,6 +115,221 @@ value:
     }
 }
 
+#[test]
+fn test_feoxdb_backend_tuning_deserialize() {
+    let yaml = r#"
+type: FeOxDb
+path: "/var/cache/hitbox"
+max_memory: 268435456
+file_size: 1073741824
+sync:
+  Interval:
+    interval_ms: 500
+sweep_interval_ms: 10000
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::FeOxDb(config) => {
+            assert_eq!(
+                config.backend,
+                FeOxDb {
+                    path: Some("/var/cache/hitbox".to_string()),
+                    max_memory: Some(256 * 1024 * 1024),
+                    file_size: Some(1024 * 1024 * 1024),
+                    sync: FeOxDbSync::Interval { interval_ms: 500 },
+                    sweep_interval_ms: Some(10_000),
+                }
+            );
+        }
+        _ => panic!("expected FeOxDb backend"),
+    }
+}
+
+#[test]
+fn test_tarantool_backend_deserialize() {
+    let yaml = r#"
+type: Tarantool
+host: "tarantool.local"
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Tarantool(config) => {
+            assert_eq!(
+                config.backend,
+                Tarantool {
+                    host: "tarantool.local".to_string(),
+                    port: 3301,
+                    user: "hitbox".to_string(),
+                    password: "hitbox".to_string(),
+                }
+            );
+            assert_eq!(config.key.format, KeySerialization::Bitcode);
+        }
+        _ => panic!("expected Tarantool backend"),
+    }
+}
+
+#[test]
+fn test_fs_backend_deserialize() {
+    let yaml = r#"
+type: Fs
+root: "/var/cache/hitbox"
+max_size: 1073741824
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Fs(config) => {
+            assert_eq!(
+                config.backend,
+                Fs {
+                    root: "/var/cache/hitbox".to_string(),
+                    max_size: Some(1073741824),
+                    sweep_interval_ms: None,
+                }
+            );
+        }
+        _ => panic!("expected Fs backend"),
+    }
+}
+
+#[test]
+fn test_sqlite_backend_deserialize() {
+    let yaml = r#"
+type: Sqlite
+path: "/var/cache/hitbox.db"
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Sqlite(config) => {
+            assert_eq!(
+                config.backend,
+                Sqlite {
+                    path: Some("/var/cache/hitbox.db".to_string()),
+                    sweep_interval_ms: None,
+                }
+            );
+        }
+        _ => panic!("expected Sqlite backend"),
+    }
+}
+
+#[test]
+fn test_moka_backend_size_and_idle_deserialize() {
+    let yaml = r#"
+type: Moka
+max_capacity: 10000
+max_bytes: 67108864
+time_to_idle_ms: 60000
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Moka(config) => {
+            assert_eq!(
+                config.backend,
+                Moka {
+                    max_capacity: 10000,
+                    max_bytes: Some(64 * 1024 * 1024),
+                    time_to_idle_ms: Some(60000),
+                }
+            );
+        }
+        _ => panic!("expected Moka backend"),
+    }
+}
+
+#[test]
+fn test_postgres_backend_deserialize() {
+    let yaml = r#"
+type: Postgres
+connection_string: "postgres://hitbox@localhost/cache"
+pool_size: 8
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Postgres(config) => {
+            assert_eq!(
+                config.backend,
+                Postgres {
+                    connection_string: "postgres://hitbox@localhost/cache".to_string(),
+                    pool_size: Some(8),
+                    sweep_interval_ms: None,
+                }
+            );
+        }
+        _ => panic!("expected Postgres backend"),
+    }
+}
+
+#[test]
+fn test_memcached_backend_deserialize() {
+    let yaml = r#"
+type: Memcached
+servers:
+  - "cache-1:11211"
+  - "cache-2:11211"
+response_timeout_ms: 250
+oversized: Chunk
+key:
+  format: Bitcode
+value:
+  format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Memcached(config) => {
+            assert_eq!(
+                config.backend,
+                Memcached {
+                    servers: vec!["cache-1:11211".to_string(), "cache-2:11211".to_string()],
+                    connection_timeout_ms: None,
+                    response_timeout_ms: Some(250),
+                    max_item_size: None,
+                    oversized: MemcachedOversized::Chunk,
+                }
+            );
+        }
+        _ => panic!("expected Memcached backend"),
+    }
+}
+
 #[test]
 fn test_redis_backend_deserialize() {
     let yaml = r#"
@@ -72,7 +345,14 @@ value:
 
     match backend {
         Backend::Redis(config) => {
-            assert_eq!(config.backend.connection_string, "redis://localhost:6379");
+            assert_eq!(
+                config.backend.connection,
+                RedisConnection::Single {
+                    connection_string: "redis://localhost:6379".to_string()
+                }
+            );
+            assert_eq!(config.backend.hash_tag, RedisHashTag::Disabled);
+            assert_eq!(config.backend.options, RedisOptions::default());
             assert_eq!(config.key.format, KeySerialization::Bitcode);
             assert_eq!(config.value.format, ValueSerialization::Json);
             assert_eq!(config.value.compression, Compression::Disabled);
@@ -81,6 +361,137 @@ value:
     }
 }
 
+#[test]
+fn test_redis_connection_options_deserialize() {
+    let yaml = r#"
+type: Redis
+connection_string: "redis://localhost:6379"
+username: cache
+password: secret
+database: 2
+connections: 4
+connection_timeout_ms: 500
+response_timeout_ms: 200
+retry:
+  attempts: 3
+  min_delay_ms: 50
+  max_delay_ms: 1000
+client_cache:
+  capacity: 500
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Redis(config) => {
+            assert_eq!(
+                config.backend.options,
+                RedisOptions {
+                    username: Some("cache".to_string()),
+                    password: Some("secret".to_string()),
+                    database: Some(2),
+                    tls: false,
+                    connections: Some(4),
+                    connection_timeout_ms: Some(500),
+                    response_timeout_ms: Some(200),
+                    retry: Some(RedisRetry {
+                        attempts: 3,
+                        min_delay_ms: 50,
+                        max_delay_ms: 1000,
+                    }),
+                    client_cache: Some(RedisClientCache {
+                        capacity: NonZeroUsize::new(500),
+                        ttl_ms: None,
+                    }),
+                }
+            );
+        }
+        _ => panic!("expected Redis backend"),
+    }
+}
+
+#[test]
+fn test_redis_cluster_backend_deserialize() {
+    let yaml = r#"
+type: Redis
+cluster:
+  nodes:
+    - "redis://node-1:6379"
+    - "redis://node-2:6379"
+hash_tag: Prefix
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Redis(config) => {
+            assert_eq!(
+                config.backend.connection,
+                RedisConnection::Cluster {
+                    cluster: RedisCluster {
+                        nodes: vec![
+                            "redis://node-1:6379".to_string(),
+                            "redis://node-2:6379".to_string()
+                        ]
+                    }
+                }
+            );
+            assert_eq!(config.backend.hash_tag, RedisHashTag::Prefix);
+        }
+        _ => panic!("expected Redis backend"),
+    }
+}
+
+#[test]
+fn test_redis_sentinel_backend_deserialize() {
+    let yaml = r#"
+type: Redis
+sentinel:
+  master: mymaster
+  nodes:
+    - "redis://sentinel-1:26379"
+    - "redis://sentinel-2:26379"
+hash_tag:
+  Fixed: cache
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Redis(config) => {
+            assert_eq!(
+                config.backend.connection,
+                RedisConnection::Sentinel {
+                    sentinel: RedisSentinel {
+                        master: "mymaster".to_string(),
+                        nodes: vec![
+                            "redis://sentinel-1:26379".to_string(),
+                            "redis://sentinel-2:26379".to_string()
+                        ]
+                    }
+                }
+            );
+            assert_eq!(
+                config.backend.hash_tag,
+                RedisHashTag::Fixed("cache".to_string())
+            );
+        }
+        _ => panic!("expected Redis backend"),
+    }
+}
+
 #[test]
 fn test_backend_serialize_roundtrip() {
     let backend = Backend::Moka(BackendConfig {
@@ -90,8 +501,15 @@ fn test_backend_serialize_roundtrip() {
         value: ValueFormat {
             format: ValueSerialization::Json,
             compression: Compression::Zstd { level: 3 },
+            envelope: None,
+            integrity: None,
+            encryption: None,
+        },
+        backend: Moka {
+            max_capacity: 5000,
+            max_bytes: None,
+            time_to_idle_ms: None,
         },
-        backend: Moka { max_capacity: 5000 },
     });
 
     let yaml = serde_saphyr::to_string(&backend).expect("failed to serialize");
@@ -100,3 +518,262 @@ fn test_backend_serialize_roundtrip() {
 
     assert_eq!(backend, deserialized);
 }
+
+#[test]
+fn test_sharded_backend_deserialize() {
+    let yaml = r#"
+type: Sharded
+key:
+  format: Bitcode
+value:
+  format: Bincode
+shards:
+  - id: redis-a
+    backend:
+      type: Redis
+      connection_string: "redis://redis-a:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Bincode
+  - id: redis-b
+    weight: 2
+    backend:
+      type: Redis
+      connection_string: "redis://redis-b:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Bincode
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Sharded(config) => {
+            assert_eq!(config.value.format, ValueSerialization::Bincode);
+            assert_eq!(config.backend.virtual_nodes, 160);
+            assert_eq!(config.backend.shards.len(), 2);
+            assert_eq!(config.backend.shards[0].id, "redis-a");
+            assert_eq!(config.backend.shards[0].weight, 1);
+            assert_eq!(config.backend.shards[1].weight, 2);
+            match &config.backend.shards[1].backend {
+                Backend::Redis(redis) => assert_eq!(
+                    redis.backend.connection,
+                    RedisConnection::Single {
+                        connection_string: "redis://redis-b:6379".to_string()
+                    }
+                ),
+                _ => panic!("expected Redis shard"),
+            }
+        }
+        _ => panic!("expected Sharded backend"),
+    }
+}
+
+#[test]
+fn test_failover_backend_deserialize() {
+    let yaml = r#"
+type: Failover
+key:
+  format: Bitcode
+value:
+  format: Json
+members:
+  - id: primary
+    backend:
+      type: Redis
+      connection_string: "redis://primary:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Json
+  - id: local
+    backend:
+      type: Moka
+      max_capacity: 1000
+      key:
+        format: Bitcode
+      value:
+        format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Failover(config) => {
+            let ids: Vec<_> = config
+                .backend
+                .members
+                .iter()
+                .map(|m| m.id.as_str())
+                .collect();
+            assert_eq!(ids, ["primary", "local"]);
+            assert!(matches!(
+                config.backend.members[1].backend,
+                Backend::Moka(_)
+            ));
+        }
+        _ => panic!("expected Failover backend"),
+    }
+}
+
+#[test]
+fn test_replicated_backend_deserialize() {
+    let yaml = r#"
+type: Replicated
+quorum: 2
+key:
+  format: Bitcode
+value:
+  format: Json
+members:
+  - id: a
+    backend:
+      type: Redis
+      connection_string: "redis://a:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Json
+  - id: b
+    backend:
+      type: Redis
+      connection_string: "redis://b:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Json
+  - id: c
+    backend:
+      type: Redis
+      connection_string: "redis://c:6379"
+      key:
+        format: Bitcode
+      value:
+        format: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Replicated(config) => {
+            assert_eq!(config.backend.quorum, Some(2));
+            assert_eq!(config.backend.members.len(), 3);
+        }
+        _ => panic!("expected Replicated backend"),
+    }
+}
+
+#[test]
+fn test_compression_variants_deserialize() {
+    let cases = [
+        ("type: Lz4", Compression::Lz4 { level: 0 }),
+        ("{type: Lz4, level: 9}", Compression::Lz4 { level: 9 }),
+        ("type: Brotli", Compression::Brotli { level: 9 }),
+        ("{type: Brotli, level: 4}", Compression::Brotli { level: 4 }),
+        ("type: Snappy", Compression::Snappy),
+        (
+            "{type: Adaptive, codec: {type: Zstd}}",
+            Compression::Adaptive {
+                threshold: 256,
+                codec: Box::new(Compression::Zstd { level: 3 }),
+                fallback: None,
+            },
+        ),
+        (
+            "{type: Adaptive, threshold: 1024, codec: {type: Lz4, level: 4}}",
+            Compression::Adaptive {
+                threshold: 1024,
+                codec: Box::new(Compression::Lz4 { level: 4 }),
+                fallback: None,
+            },
+        ),
+        (
+            "{type: Adaptive, codec: {type: Zstd}, fallback: {type: Gzip}}",
+            Compression::Adaptive {
+                threshold: 256,
+                codec: Box::new(Compression::Zstd { level: 3 }),
+                fallback: Some(Box::new(Compression::Gzip { level: 6 })),
+            },
+        ),
+    ];
+
+    for (yaml, expected) in cases {
+        let compression: Compression = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+        assert_eq!(compression, expected, "{yaml}");
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_adaptive_compression_decodes_untagged_with_fallback() {
+    use hitbox_backend::Compressor;
+
+    let legacy = Compression::Gzip { level: 6 }
+        .to_compressor()
+        .expect("gzip compressor");
+    let adaptive = Compression::Adaptive {
+        threshold: 256,
+        codec: Box::new(Compression::Disabled),
+        fallback: Some(Box::new(Compression::Gzip { level: 6 })),
+    }
+    .to_compressor()
+    .expect("adaptive compressor");
+
+    let data = b"written before adaptive compression was enabled".repeat(4);
+    let stored = legacy.compress(&data).expect("compress");
+
+    assert_eq!(adaptive.decompress(&stored).expect("decompress"), data);
+}
+
+#[test]
+fn test_value_formats_deserialize() {
+    let cases = [
+        ("MessagePack", ValueSerialization::MessagePack),
+        ("Cbor", ValueSerialization::Cbor),
+        ("Postcard", ValueSerialization::Postcard),
+        ("Rkyv", ValueSerialization::Rkyv),
+    ];
+
+    for (yaml, expected) in cases {
+        let format: ValueSerialization =
+            serde_saphyr::from_str(yaml).expect("failed to deserialize");
+        assert_eq!(format, expected, "{yaml}");
+    }
+}
+
+#[test]
+fn test_value_envelope_deserialize() {
+    use hitbox_backend::serializer::Format;
+
+    let yaml = r#"
+type: Moka
+max_capacity: 100
+key:
+  format: Bitcode
+value:
+  format: Bincode
+  envelope:
+    fallback: Json
+"#;
+
+    let backend: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+
+    match backend {
+        Backend::Moka(config) => {
+            assert_eq!(
+                config.value.envelope,
+                Some(ValueEnvelope {
+                    fallback: Some(ValueSerialization::Json),
+                })
+            );
+            let format = config
+                .value
+                .to_serializer()
+                .expect("failed to create format");
+            assert!(format.envelope().is_some());
+        }
+        _ => panic!("expected Moka backend"),
+    }
+}
@@ -13,8 +13,15 @@ fn test_moka_backend_instantiation() {
         value: ValueFormat {
             format: ValueSerialization::Json,
             compression: Compression::Disabled,
+            envelope: None,
+            integrity: None,
+            encryption: None,
+        },
+        backend: Moka {
+            max_capacity: 1000,
+            max_bytes: None,
+            time_to_idle_ms: None,
         },
-        backend: Moka { max_capacity: 1000 },
     });
 
     let _backend = backend_config
@@ -23,3 +30,200 @@ fn test_moka_backend_instantiation() {
 
     // If we got here, the backend was successfully instantiated
 }
+
+#[cfg(feature = "moka")]
+#[test]
+fn test_sharded_backend_instantiation() {
+    use hitbox_configuration::backend::{
+        Backend, BackendConfig, Compression, KeyFormat, KeySerialization, Moka, Shard, Sharded,
+        ValueFormat, ValueSerialization,
+    };
+
+    let key = KeyFormat {
+        format: KeySerialization::Bitcode,
+    };
+    let value = ValueFormat {
+        format: ValueSerialization::Json,
+        compression: Compression::Disabled,
+        envelope: None,
+        integrity: None,
+        encryption: None,
+    };
+    let shard = |id: &str| Shard {
+        id: id.to_string(),
+        weight: 1,
+        backend: Backend::Moka(BackendConfig {
+            key: key.clone(),
+            value: value.clone(),
+            backend: Moka {
+                max_capacity: 1000,
+                max_bytes: None,
+                time_to_idle_ms: None,
+            },
+        }),
+    };
+
+    let backend_config = Backend::Sharded(BackendConfig {
+        key: key.clone(),
+        value: value.clone(),
+        backend: Sharded {
+            shards: vec![shard("a"), shard("b")],
+            virtual_nodes: 16,
+        },
+    });
+
+    let _backend = backend_config
+        .into_backend()
+        .expect("failed to instantiate backend");
+}
+
+#[cfg(all(feature = "moka", feature = "encryption"))]
+#[tokio::test]
+async fn test_encrypted_backend_instantiation() {
+    use hitbox_backend::Backend as _;
+    use hitbox_configuration::backend::Backend;
+    use hitbox_core::{CacheKey, CacheValue};
+
+    let path = std::env::temp_dir().join(format!("hitbox-key-{}", std::process::id()));
+    // Base64 of 32 bytes 0x2a
+    std::fs::write(&path, "KioqKioqKioqKioqKioqKioqKioqKioqKioqKioqKio=\n").unwrap();
+    let yaml = format!(
+        r#"
+type: Moka
+max_capacity: 100
+key:
+  format: Bitcode
+value:
+  format: Json
+  encryption:
+    keys:
+      - id: 2
+        cipher: ChaCha20Poly1305
+        file: {}
+"#,
+        path.display()
+    );
+
+    let config: Backend = serde_saphyr::from_str(&yaml).expect("failed to deserialize");
+    let backend = config
+        .into_backend()
+        .expect("failed to instantiate backend");
+    std::fs::remove_file(&path).unwrap();
+
+    let key = CacheKey::from_str("key", "1");
+    let value = CacheValue::new(b"value".to_vec(), None, None);
+    backend.write(&key, value.clone(), None).await.unwrap();
+    assert_eq!(backend.read(&key).await.unwrap(), Some(value));
+}
+
+#[cfg(all(feature = "moka", feature = "encryption"))]
+#[test]
+fn test_encryption_key_errors() {
+    use hitbox_configuration::{backend::Backend, error::ConfigError};
+
+    let config = |keys: &str| {
+        let yaml = format!(
+            r#"
+type: Moka
+max_capacity: 100
+key:
+  format: Bitcode
+value:
+  format: Json
+  encryption:
+    keys: {keys}
+"#
+        );
+        serde_saphyr::from_str::<Backend>(&yaml).expect("failed to deserialize")
+    };
+
+    let path = std::env::temp_dir().join(format!("hitbox-dup-key-{}", std::process::id()));
+    std::fs::write(&path, "KioqKioqKioqKioqKioqKioqKioqKioqKioqKioqKio=\n").unwrap();
+    let duplicate = format!(
+        "[{{ id: 1, file: {0} }}, {{ id: 1, file: {0} }}]",
+        path.display()
+    );
+
+    for keys in [
+        "[]",
+        "[{ id: 1, env: HITBOX_TEST_MISSING_ENCRYPTION_KEY }]",
+        "[{ id: 1, file: /nonexistent/hitbox/key }]",
+        &duplicate,
+    ] {
+        assert!(
+            matches!(
+                config(keys).into_backend(),
+                Err(ConfigError::InvalidEncryptionKey(_))
+            ),
+            "{keys}"
+        );
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(all(feature = "moka", feature = "integrity"))]
+#[tokio::test]
+async fn test_integrity_backend_instantiation() {
+    use hitbox_backend::Backend as _;
+    use hitbox_configuration::backend::{Backend, IntegrityChecksum, ValueIntegrity};
+    use hitbox_core::{CacheKey, CacheValue};
+
+    let yaml = r#"
+type: Moka
+max_capacity: 100
+key:
+  format: Bitcode
+value:
+  format: Json
+  integrity:
+    checksum: Crc32c
+"#;
+
+    let config: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+    let Backend::Moka(moka) = &config else {
+        panic!("expected moka backend");
+    };
+    assert_eq!(
+        moka.value.integrity,
+        Some(ValueIntegrity {
+            checksum: IntegrityChecksum::Crc32c
+        })
+    );
+    let backend = config
+        .into_backend()
+        .expect("failed to instantiate backend");
+
+    let key = CacheKey::from_str("key", "1");
+    let value = CacheValue::new(b"value".to_vec(), None, None);
+    backend.write(&key, value.clone(), None).await.unwrap();
+    assert_eq!(backend.read(&key).await.unwrap(), Some(value));
+}
+
+#[cfg(feature = "redis")]
+#[test]
+fn test_redis_client_cache_instantiation() {
+    use hitbox_configuration::backend::Backend;
+
+    let yaml = r#"
+type: Redis
+connection_string: "redis://127.0.0.1:6379"
+client_cache:
+  capacity: 100
+  ttl_ms: 60000
+key:
+  format: Bitcode
+value:
+  format: Json
+"#;
+
+    let config: Backend = serde_saphyr::from_str(yaml).expect("failed to deserialize");
+    let result = config.into_backend();
+
+    #[cfg(feature = "redis-client-cache")]
+    assert!(result.is_ok());
+    #[cfg(not(feature = "redis-client-cache"))]
+    assert!(matches!(
+        result,
+        Err(hitbox_configuration::error::ConfigError::BackendNotAvailable(_))
+    ));
+}
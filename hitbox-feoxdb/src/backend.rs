@@ -1,15 +1,23 @@
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use bincode::{
     config::standard as bincode_config,
     serde::{decode_from_slice, encode_to_vec},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use feoxdb::{FeoxError, FeoxStore};
 use hitbox_backend::serializer::{Format, JsonFormat};
 use hitbox_backend::{
-    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    entry_deadline, Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
     PassthroughCompressor,
 };
 use hitbox_core::{CacheKey, CacheValue};
@@ -19,22 +27,63 @@ use crate::FeOxDbError;
 
 type Raw = Vec<u8>;
 
+/// Default period of the expired records sweeper.
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of records removed at once by [`FeOxDbBackend::clear`].
+const CLEAR_BATCH: usize = 1000;
+
+/// Upper bound of the key range scanned by [`FeOxDbBackend::clear`].
+const LAST_KEY: [u8; 256] = [0xff; 256];
+
+/// How written records reach the disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Leave flushing to the FeOxDB background writer.
+    #[default]
+    Background,
+    /// Flush after every write, before the write returns.
+    EveryWrite,
+    /// Flush periodically.
+    Interval(Duration),
+}
+
+/// Store counters returned by [`FeOxDbBackend::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeOxDbStats {
+    /// Stored records, expired ones not swept yet included.
+    pub entries: usize,
+    /// Memory used by the store in bytes.
+    pub memory_usage: usize,
+    /// Records waiting for the sweeper.
+    pub expiring: usize,
+    /// Records removed by sweeps since the backend was built.
+    pub swept: u64,
+}
+
 #[derive(Serialize, Deserialize)]
 struct SerializableCacheValue {
     #[serde(with = "serde_bytes")]
     data: Vec<u8>,
     stale: Option<DateTime<Utc>>,
     expire: Option<DateTime<Utc>>,
+    /// Moment the record may be removed, from the write ttl or the value expiration.
+    deadline: Option<DateTime<Utc>>,
 }
 
-impl From<CacheValue<Raw>> for SerializableCacheValue {
-    fn from(value: CacheValue<Raw>) -> Self {
+impl SerializableCacheValue {
+    fn new(value: CacheValue<Raw>, deadline: Option<DateTime<Utc>>) -> Self {
         Self {
             data: value.data,
             stale: value.stale,
             expire: value.expire,
+            deadline,
         }
     }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
 }
 
 impl From<SerializableCacheValue> for CacheValue<Raw> {
@@ -43,13 +92,210 @@ impl From<SerializableCacheValue> for CacheValue<Raw> {
     }
 }
 
+/// Whole seconds of the native FeOxDB ttl, rounded up so the store never drops a
+/// record before its deadline.
+fn native_ttl(ttl: TimeDelta) -> u64 {
+    let millis = ttl.num_milliseconds().max(1) as u64;
+    millis.div_ceil(1000)
+}
+
+/// Deadlines of records written by this process, ordered for the sweeper.
+///
+/// Indexed by key as well, so overwriting or removing a record replaces or drops
+/// the deadline of the previous one instead of leaving it behind.
+#[derive(Default)]
+struct Deadlines {
+    queue: BTreeSet<(DateTime<Utc>, Vec<u8>)>,
+    keys: HashMap<Vec<u8>, DateTime<Utc>>,
+}
+
+impl Deadlines {
+    fn insert(&mut self, key: Vec<u8>, deadline: DateTime<Utc>) {
+        if let Some(previous) = self.keys.insert(key.clone(), deadline) {
+            self.queue.remove(&(previous, key.clone()));
+        }
+        self.queue.insert((deadline, key));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        if let Some(deadline) = self.keys.remove(key) {
+            self.queue.remove(&(deadline, key.to_vec()));
+        }
+    }
+
+    /// Stop tracking records whose deadline is before `now`, returning them.
+    fn take_due(&mut self, now: DateTime<Utc>) -> BTreeSet<(DateTime<Utc>, Vec<u8>)> {
+        let pending = self.queue.split_off(&(now, Vec::new()));
+        let due = std::mem::replace(&mut self.queue, pending);
+        for (_, key) in &due {
+            self.keys.remove(key);
+        }
+        due
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.keys.clear();
+    }
+}
+
+/// Store shared by all clones of the backend.
+///
+/// FeOxDB only expires records with a whole-second ttl, so every record carries
+/// its own deadline, checked on read. Deadlines of records written by this
+/// process are tracked for the sweeper; records left by a previous process are
+/// dropped by the native ttl.
+struct Store {
+    store: FeoxStore,
+    deadlines: Mutex<Deadlines>,
+    swept: AtomicU64,
+    sync: SyncPolicy,
+}
+
+impl Store {
+    fn new(store: FeoxStore, sync: SyncPolicy) -> Self {
+        Self {
+            store,
+            deadlines: Mutex::default(),
+            swept: AtomicU64::new(0),
+            sync,
+        }
+    }
+
+    fn deadlines(&self) -> std::sync::MutexGuard<'_, Deadlines> {
+        self.deadlines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn run<T, F>(self: &Arc<Self>, operation: F) -> Result<T, FeOxDbError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Store) -> Result<T, FeOxDbError> + Send + 'static,
+    {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || operation(&store)).await?
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<SerializableCacheValue>, FeOxDbError> {
+        match self.store.get(key) {
+            Ok(encoded) => match decode_from_slice(&encoded, bincode_config()) {
+                Ok((record, _)) => Ok(Some(record)),
+                // Records of another layout, e.g. written by a version without record
+                // deadlines, are dropped and read as a miss.
+                Err(err) => {
+                    tracing::debug!("Dropping undecodable FeOxDB record: {err}");
+                    self.delete(key)?;
+                    Ok(None)
+                }
+            },
+            Err(FeoxError::KeyNotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<(), FeOxDbError> {
+        self.deadlines().remove(key);
+        match self.store.delete(key) {
+            Ok(()) | Err(FeoxError::KeyNotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Remove records whose deadline has passed, returning the number of removed records.
+    fn sweep(&self) -> Result<usize, FeOxDbError> {
+        let now = Utc::now();
+        let due = self.deadlines().take_due(now);
+        let mut removed = 0;
+        for (deadline, key) in due {
+            // Skip records overwritten since, they are tracked under their new deadline.
+            let Some(record) = self.get(&key)? else {
+                continue;
+            };
+            if record.deadline == Some(deadline) {
+                self.delete(&key)?;
+                removed += 1;
+            }
+        }
+        self.swept.fetch_add(removed as u64, Ordering::Relaxed);
+        Ok(removed)
+    }
+
+    fn clear(&self) -> Result<usize, FeOxDbError> {
+        let mut removed = 0;
+        loop {
+            let batch = self.store.range_query(&[], &LAST_KEY, CLEAR_BATCH)?;
+            if batch.is_empty() {
+                break;
+            }
+            for (key, _) in batch {
+                self.delete(&key)?;
+                removed += 1;
+            }
+        }
+        self.deadlines().clear();
+        Ok(removed)
+    }
+}
+
+/// Start the sweeper, and the flusher with [`SyncPolicy::Interval`], on the
+/// current Tokio runtime.
+fn start(store: Store, sweep_interval: Duration) -> Arc<Store> {
+    let store = Arc::new(store);
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(sweeper(Arc::downgrade(&store), sweep_interval));
+            if let SyncPolicy::Interval(interval) = store.sync {
+                runtime.spawn(flusher(Arc::downgrade(&store), interval));
+            }
+        }
+        Err(_) => tracing::warn!("No Tokio runtime, FeOxDB cache sweeper is not started"),
+    }
+    store
+}
+
+async fn sweeper(store: Weak<Store>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(store) = store.upgrade() else {
+            break;
+        };
+        match store.run(|store| store.sweep()).await {
+            Ok(removed) => tracing::trace!("Removed {removed} expired cache records"),
+            Err(err) => tracing::warn!("Expired cache records sweep failed: {err}"),
+        }
+    }
+}
+
+async fn flusher(store: Weak<Store>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(store) = store.upgrade() else {
+            break;
+        };
+        let flushed = store
+            .run(|store| {
+                store.store.flush();
+                Ok(())
+            })
+            .await;
+        if let Err(err) = flushed {
+            tracing::warn!("FeOxDB flush failed: {err}");
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FeOxDbBackend<S = JsonFormat, C = PassthroughCompressor>
 where
     S: Format,
     C: Compressor,
 {
-    store: Arc<FeoxStore>,
+    store: Arc<Store>,
     key_format: CacheKeyFormat,
     serializer: S,
     compressor: C,
@@ -57,33 +303,22 @@ where
 
 impl FeOxDbBackend<JsonFormat, PassthroughCompressor> {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FeOxDbError> {
-        let mut path_buf = path.as_ref().to_path_buf();
-        if path_buf.is_dir() {
-            path_buf.push("cache.db");
-        }
-
-        let path_str = path_buf.to_string_lossy().to_string();
-
-        let store = FeoxStore::builder()
-            .device_path(path_str)
-            .enable_ttl(true)
-            .build()?;
-
-        Ok(Self {
-            store: Arc::new(store),
-            key_format: CacheKeyFormat::Bitcode,
-            serializer: JsonFormat,
-            compressor: PassthroughCompressor,
-        })
+        Self::builder()
+            .path(path.as_ref().to_string_lossy().into_owned())
+            .build()
     }
 
     pub fn builder() -> FeOxDbBackendBuilder<JsonFormat, PassthroughCompressor> {
         FeOxDbBackendBuilder::default()
     }
 
+    /// Wrap an already opened store, which must have ttl enabled.
     pub fn from_store(store: FeoxStore) -> Self {
         Self {
-            store: Arc::new(store),
+            store: start(
+                Store::new(store, SyncPolicy::default()),
+                DEFAULT_SWEEP_INTERVAL,
+            ),
             key_format: CacheKeyFormat::Bitcode,
             serializer: JsonFormat,
             compressor: PassthroughCompressor,
@@ -91,14 +326,35 @@ impl FeOxDbBackend<JsonFormat, PassthroughCompressor> {
     }
 
     pub fn in_memory() -> Result<Self, FeOxDbError> {
-        let store = FeoxStore::builder().enable_ttl(true).build()?;
+        Self::builder().build()
+    }
+}
 
-        Ok(Self {
-            store: Arc::new(store),
-            key_format: CacheKeyFormat::Bitcode,
-            serializer: JsonFormat,
-            compressor: PassthroughCompressor,
-        })
+impl<S, C> FeOxDbBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Remove expired records now, returning the number of removed records.
+    ///
+    /// The background sweeper calls it periodically.
+    pub async fn sweep(&self) -> Result<usize, FeOxDbError> {
+        self.store.run(|store| store.sweep()).await
+    }
+
+    /// Remove all records, returning the number of removed records.
+    pub async fn clear(&self) -> Result<usize, FeOxDbError> {
+        self.store.run(|store| store.clear()).await
+    }
+
+    /// Current store counters.
+    pub fn stats(&self) -> FeOxDbStats {
+        FeOxDbStats {
+            entries: self.store.store.len(),
+            memory_usage: self.store.store.memory_usage(),
+            expiring: self.store.deadlines().len(),
+            swept: self.store.swept.load(Ordering::Relaxed),
+        }
     }
 }
 
@@ -108,6 +364,10 @@ where
     C: Compressor,
 {
     path: Option<String>,
+    max_memory: Option<usize>,
+    file_size: Option<u64>,
+    sync: SyncPolicy,
+    sweep_interval: Duration,
     key_format: CacheKeyFormat,
     serializer: S,
     compressor: C,
@@ -117,6 +377,10 @@ impl Default for FeOxDbBackendBuilder<JsonFormat, PassthroughCompressor> {
     fn default() -> Self {
         Self {
             path: None,
+            max_memory: None,
+            file_size: None,
+            sync: SyncPolicy::default(),
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
             key_format: CacheKeyFormat::Bitcode,
             serializer: JsonFormat,
             compressor: PassthroughCompressor,
@@ -134,6 +398,30 @@ where
         self
     }
 
+    /// Set memory limit of the store in bytes, FeOxDB default if not set.
+    pub fn max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Set size of the data file in bytes, FeOxDB default if not set.
+    pub fn file_size(mut self, bytes: u64) -> Self {
+        self.file_size = Some(bytes);
+        self
+    }
+
+    /// Set when written records are flushed, [`SyncPolicy::Background`] by default.
+    pub fn sync(mut self, policy: SyncPolicy) -> Self {
+        self.sync = policy;
+        self
+    }
+
+    /// Set period of the expired records sweeper, [`DEFAULT_SWEEP_INTERVAL`] by default.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
     pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
         self.key_format = format;
         self
@@ -145,6 +433,10 @@ where
     {
         FeOxDbBackendBuilder {
             path: self.path,
+            max_memory: self.max_memory,
+            file_size: self.file_size,
+            sync: self.sync,
+            sweep_interval: self.sweep_interval,
             key_format: self.key_format,
             serializer,
             compressor: self.compressor,
@@ -157,29 +449,40 @@ where
     {
         FeOxDbBackendBuilder {
             path: self.path,
+            max_memory: self.max_memory,
+            file_size: self.file_size,
+            sync: self.sync,
+            sweep_interval: self.sweep_interval,
             key_format: self.key_format,
             serializer: self.serializer,
             compressor,
         }
     }
 
+    /// Open store and start the sweeper.
+    ///
+    /// The sweeper runs on the current Tokio runtime until the backend and all its
+    /// clones are dropped; without a runtime expired records are removed on read
+    /// or by the native FeOxDB ttl.
     pub fn build(self) -> Result<FeOxDbBackend<S, C>, FeOxDbError> {
-        let store = if let Some(path) = self.path {
-            let mut path_buf = std::path::PathBuf::from(path);
-            if path_buf.is_dir() {
-                path_buf.push("cache.db");
+        let mut builder = FeoxStore::builder().enable_ttl(true);
+        if let Some(path) = self.path {
+            let mut path = PathBuf::from(path);
+            if path.is_dir() {
+                path.push("cache.db");
             }
-            let path_str = path_buf.to_string_lossy().to_string();
-            FeoxStore::builder()
-                .device_path(path_str)
-                .enable_ttl(true)
-                .build()?
-        } else {
-            FeoxStore::builder().enable_ttl(true).build()?
-        };
+            builder = builder.device_path(path.to_string_lossy().into_owned());
+        }
+        if let Some(bytes) = self.max_memory {
+            builder = builder.max_memory(bytes);
+        }
+        if let Some(bytes) = self.file_size {
+            builder = builder.file_size(bytes);
+        }
+        let store = Store::new(builder.build()?, self.sync);
 
         Ok(FeOxDbBackend {
-            store: Arc::new(store),
+            store: start(store, self.sweep_interval),
             key_format: self.key_format,
             serializer: self.serializer,
             compressor: self.compressor,
@@ -194,32 +497,19 @@ where
     C: Compressor + Send + Sync,
 {
     async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
-        let store = self.store.clone();
-
-        let key_bytes = encode_to_vec(key, bincode_config())
-            .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-
-        tokio::task::spawn_blocking(move || match store.get(&key_bytes) {
-            Ok(encoded) => {
-                let (serializable, _): (SerializableCacheValue, _) =
-                    decode_from_slice(&encoded, bincode_config())
-                        .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-
-                let cache_value: CacheValue<Raw> = serializable.into();
-
-                if let Some(expire_time) = cache_value.expire {
-                    if expire_time < Utc::now() {
-                        return Ok(None);
-                    }
+        let key = encode_to_vec(key, bincode_config()).map_err(FeOxDbError::from)?;
+
+        let value = self
+            .store
+            .run(move |store| match store.get(&key)? {
+                Some(record) if record.is_expired(Utc::now()) => {
+                    store.delete(&key)?;
+                    Ok(None)
                 }
-
-                Ok(Some(cache_value))
-            }
-            Err(FeoxError::KeyNotFound) => Ok(None),
-            Err(e) => Err(BackendError::InternalError(Box::new(e))),
-        })
-        .await
-        .map_err(|e| BackendError::InternalError(Box::new(e)))?
+                record => Ok(record.map(CacheValue::from)),
+            })
+            .await?;
+        Ok(value)
     }
 
     async fn write(
@@ -228,46 +518,56 @@ where
         value: CacheValue<Raw>,
         ttl: Option<Duration>,
     ) -> BackendResult<()> {
-        let store = self.store.clone();
-
-        let key_bytes = encode_to_vec(key, bincode_config())
-            .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-
-        let serializable: SerializableCacheValue = value.into();
-        let value_bytes = encode_to_vec(&serializable, bincode_config())
-            .map_err(|e| BackendError::InternalError(Box::new(e)))?;
+        let now = Utc::now();
+        let Some(deadline) = entry_deadline(ttl, value.expire, now) else {
+            return Ok(());
+        };
 
-        tokio::task::spawn_blocking(move || {
-            ttl.map(|ttl_duration| ttl_duration.as_secs())
-                .map(|ttl_secs| store.insert_with_ttl(&key_bytes, &value_bytes, ttl_secs))
-                .unwrap_or_else(|| store.insert(&key_bytes, &value_bytes))
-                .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-            Ok(())
-        })
-        .await
-        .map_err(|e| BackendError::InternalError(Box::new(e)))?
+        let key = encode_to_vec(key, bincode_config()).map_err(FeOxDbError::from)?;
+        let record = SerializableCacheValue::new(value, deadline);
+        let encoded = encode_to_vec(&record, bincode_config()).map_err(FeOxDbError::from)?;
+
+        self.store
+            .run(move |store| {
+                match deadline {
+                    Some(deadline) => {
+                        store
+                            .store
+                            .insert_with_ttl(&key, &encoded, native_ttl(deadline - now))?;
+                        store.deadlines().insert(key, deadline);
+                    }
+                    None => {
+                        store.store.insert(&key, &encoded)?;
+                        store.deadlines().remove(&key);
+                    }
+                }
+                if store.sync == SyncPolicy::EveryWrite {
+                    store.store.flush();
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(())
     }
 
     async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
-        let store = self.store.clone();
-
-        let key_bytes = encode_to_vec(key, bincode_config())
-            .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-
-        tokio::task::spawn_blocking(move || {
-            let exists = store.contains_key(&key_bytes);
-
-            if exists {
-                store
-                    .delete(&key_bytes)
-                    .map_err(|e| BackendError::InternalError(Box::new(e)))?;
-                Ok(DeleteStatus::Deleted(1))
-            } else {
-                Ok(DeleteStatus::Missing)
-            }
-        })
-        .await
-        .map_err(|e| BackendError::InternalError(Box::new(e)))?
+        let key = encode_to_vec(key, bincode_config()).map_err(FeOxDbError::from)?;
+
+        let status = self
+            .store
+            .run(move |store| {
+                let Some(record) = store.get(&key)? else {
+                    return Ok(DeleteStatus::Missing);
+                };
+                store.delete(&key)?;
+                if record.is_expired(Utc::now()) {
+                    Ok(DeleteStatus::Missing)
+                } else {
+                    Ok(DeleteStatus::Deleted(1))
+                }
+            })
+            .await?;
+        Ok(status)
     }
 
     fn value_format(&self) -> &dyn Format {
@@ -441,4 +741,125 @@ mod tests {
         assert!(backend.read(&key1).await.unwrap().is_some());
         assert!(backend.read(&key2).await.unwrap().is_some());
     }
+
+    #[tokio::test]
+    async fn test_sub_second_ttl() {
+        let backend = FeOxDbBackend::in_memory().unwrap();
+        let key = CacheKey::from_str("short-ttl", "1");
+        let value = CacheValue::new(b"value".to_vec(), None, None);
+
+        backend
+            .write(&key, value, Some(Duration::from_millis(200)))
+            .await
+            .unwrap();
+        assert!(backend.read(&key).await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        assert!(backend.read(&key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_value_not_written() {
+        let backend = FeOxDbBackend::in_memory().unwrap();
+        let key = CacheKey::from_str("expired", "1");
+        let value = CacheValue::new(
+            b"value".to_vec(),
+            Some(Utc::now() - chrono::Duration::seconds(1)),
+            None,
+        );
+
+        backend.write(&key, value, None).await.unwrap();
+
+        assert_eq!(backend.stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_removes_expired_records() {
+        let backend = FeOxDbBackend::in_memory().unwrap();
+        let value = CacheValue::new(b"value".to_vec(), None, None);
+        let expiring = CacheKey::from_str("expiring", "1");
+        let overwritten = CacheKey::from_str("overwritten", "1");
+        let permanent = CacheKey::from_str("permanent", "1");
+
+        for key in [&expiring, &overwritten] {
+            backend
+                .write(key, value.clone(), Some(Duration::from_millis(100)))
+                .await
+                .unwrap();
+        }
+        backend
+            .write(&overwritten, value.clone(), None)
+            .await
+            .unwrap();
+        backend.write(&permanent, value, None).await.unwrap();
+        assert_eq!(backend.stats().expiring, 1);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert_eq!(backend.sweep().await.unwrap(), 1);
+        let stats = backend.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.expiring, 0);
+        assert_eq!(stats.swept, 1);
+        assert!(backend.read(&overwritten).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clear() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = FeOxDbBackend::builder()
+            .path(temp_dir.path().to_string_lossy().into_owned())
+            .sync(SyncPolicy::EveryWrite)
+            .build()
+            .unwrap();
+        for id in ["1", "2", "3"] {
+            backend
+                .write(
+                    &CacheKey::from_str("key", id),
+                    CacheValue::new(b"value".to_vec(), None, None),
+                    Some(Duration::from_secs(60)),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(backend.clear().await.unwrap(), 3);
+        assert_eq!(backend.stats().entries, 0);
+        assert_eq!(backend.stats().expiring, 0);
+        assert!(backend
+            .read(&CacheKey::from_str("key", "1"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_and_remove_replace_deadline() {
+        let backend = FeOxDbBackend::in_memory().unwrap();
+        let key = CacheKey::from_str("key", "1");
+        let value = CacheValue::new(b"value".to_vec(), None, None);
+
+        for ttl in [60, 120, 180] {
+            backend
+                .write(&key, value.clone(), Some(Duration::from_secs(ttl)))
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.stats().expiring, 1);
+
+        backend.remove(&key).await.unwrap();
+        assert_eq!(backend.stats().expiring, 0);
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_record_is_a_miss() {
+        let backend = FeOxDbBackend::in_memory().unwrap();
+        let key = CacheKey::from_str("key", "1");
+        let encoded_key = encode_to_vec(&key, bincode_config()).unwrap();
+        backend.store.store.insert(&encoded_key, &[0xff]).unwrap();
+
+        assert!(backend.read(&key).await.unwrap().is_none());
+        assert_eq!(backend.stats().entries, 0);
+    }
 }
@@ -1,5 +1,6 @@
 use bincode::error::{DecodeError, EncodeError};
 use feoxdb::FeoxError;
+use hitbox_backend::BackendError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -18,4 +19,13 @@ pub enum FeOxDbError {
 
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+
+    #[error("Background task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl From<FeOxDbError> for BackendError {
+    fn from(error: FeOxDbError) -> Self {
+        Self::InternalError(Box::new(error))
+    }
 }
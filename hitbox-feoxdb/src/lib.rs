@@ -1,5 +1,7 @@
 mod backend;
 mod error;
 
-pub use backend::FeOxDbBackend;
+pub use backend::{
+    FeOxDbBackend, FeOxDbBackendBuilder, FeOxDbStats, SyncPolicy, DEFAULT_SWEEP_INTERVAL,
+};
 pub use error::FeOxDbError;
@@ -0,0 +1,491 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use hitbox_backend::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    entry_deadline,
+};
+
+use crate::{
+    entry::{Entry, HEADER_LEN, Header},
+    error::Error,
+    index::Index,
+};
+
+/// Default period of the expired files sweeper.
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Directory for files being written, skipped by the sweeper and the index.
+const TMP_DIR: &str = "tmp";
+
+/// Length of a file name, hex encoded blake3 hash.
+const NAME_LEN: usize = 64;
+
+struct Storage {
+    root: PathBuf,
+    max_size: Option<u64>,
+    index: Mutex<Index>,
+    tmp_counter: AtomicU64,
+}
+
+impl Storage {
+    fn file_name(key: &[u8]) -> String {
+        blake3::hash(key).to_hex().to_string()
+    }
+
+    /// Files are sharded by the first two bytes of the hash: `root/ab/cd/abcd...`.
+    fn path(&self, name: &str) -> PathBuf {
+        self.root.join(&name[..2]).join(&name[2..4]).join(name)
+    }
+
+    fn tmp_path(&self, name: &str) -> PathBuf {
+        let counter = self.tmp_counter.fetch_add(1, Ordering::Relaxed);
+        self.root.join(TMP_DIR).join(format!("{name}.{counter}"))
+    }
+
+    fn index(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn remove_file(&self, name: &str) -> Result<bool, Error> {
+        self.index().remove(name);
+        match tokio::fs::remove_file(self.path(name)).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn evict(&self) -> Result<(), Error> {
+        let Some(max_size) = self.max_size else {
+            return Ok(());
+        };
+        let evicted = self.index().evict(max_size);
+        for name in evicted {
+            tracing::trace!("Evict cache file {name}");
+            self.remove_file(&name).await?;
+        }
+        Ok(())
+    }
+
+    async fn sweep(self: Arc<Self>) -> Result<usize, Error> {
+        Ok(tokio::task::spawn_blocking(move || self.sweep_expired(Utc::now())).await??)
+    }
+
+    /// Remove files expired at `now` or unreadable, returning their number.
+    ///
+    /// Files are moved into the tmp directory and checked again before removal,
+    /// so a file written in place after the first check is put back.
+    fn sweep_expired(&self, now: DateTime<Utc>) -> io::Result<usize> {
+        let mut removed = 0;
+        for (name, path) in stored_files(&self.root)? {
+            if is_expired(&path, now)? != Some(true) {
+                continue;
+            }
+            let swept = self.tmp_path(&name);
+            match fs::rename(&path, &swept) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            }
+            if is_expired(&swept, now)? == Some(false) {
+                // Put back unless an even newer file took its place meanwhile,
+                // and index it again as readers un-index missing files.
+                let restored = match fs::hard_link(&swept, &path) {
+                    Ok(()) => {
+                        let mut index = self.index();
+                        match fs::metadata(&path) {
+                            Ok(metadata) => {
+                                index.insert(name, metadata.len());
+                                Ok(())
+                            }
+                            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                            Err(err) => Err(err),
+                        }
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+                    Err(err) => Err(err),
+                };
+                fs::remove_file(&swept)?;
+                restored?;
+                continue;
+            }
+            match fs::remove_file(&swept) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            // Writers rename the file in place before indexing it, so a file
+            // written meanwhile keeps its entry.
+            let mut index = self.index();
+            if !path.exists() {
+                index.remove(&name);
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+/// Whether the file is expired at `now` or unreadable, `None` if it is missing.
+fn is_expired(path: &Path, now: DateTime<Utc>) -> io::Result<Option<bool>> {
+    let mut header = [0; HEADER_LEN];
+    match fs::File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => Ok(Some(
+            Header::decode(&header).map_or(true, |header| header.is_expired(now)),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(Some(true)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Stored files as `(name, path)` pairs.
+fn stored_files(root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    fn shards(dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut shards = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let is_shard = entry.file_name().to_str().is_some_and(|name| {
+                name.len() == 2 && name.bytes().all(|byte| byte.is_ascii_hexdigit())
+            });
+            if is_shard && entry.file_type()?.is_dir() {
+                shards.push(entry.path());
+            }
+        }
+        Ok(shards)
+    }
+
+    let mut files = Vec::new();
+    for shard in shards(root)? {
+        for subshard in shards(&shard)? {
+            for entry in fs::read_dir(subshard)? {
+                let entry = entry?;
+                if let Some(name) = entry.file_name().to_str()
+                    && name.len() == NAME_LEN
+                {
+                    files.push((name.to_owned(), entry.path()));
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Filesystem cache backend.
+///
+/// Each entry is stored in its own file under the root directory, which must not be
+/// shared with other backend instances: the size of stored files is tracked in memory
+/// and restored from the directory on [`FsBackendBuilder::build`].
+#[derive(Clone)]
+pub struct FsBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    storage: Arc<Storage>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl FsBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new FsBackend builder storing files under `root`.
+    pub fn builder(
+        root: impl Into<PathBuf>,
+    ) -> FsBackendBuilder<JsonFormat, PassthroughCompressor> {
+        FsBackendBuilder::new(root)
+    }
+}
+
+impl<S, C> FsBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Remove expired files now, returning the number of removed files.
+    ///
+    /// The background sweeper calls it periodically.
+    pub async fn sweep(&self) -> Result<usize, Error> {
+        self.storage.clone().sweep().await
+    }
+
+    /// Total size of stored files in bytes.
+    pub fn size(&self) -> u64 {
+        self.storage.index().total_size()
+    }
+}
+
+impl<S, C> std::fmt::Debug for FsBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FsBackend")
+            .field("root", &self.storage.root)
+            .field("max_size", &self.storage.max_size)
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish()
+    }
+}
+
+pub struct FsBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    root: PathBuf,
+    max_size: Option<u64>,
+    sweep_interval: Duration,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl FsBackendBuilder<JsonFormat, PassthroughCompressor> {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            max_size: None,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> FsBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Limit total size of stored files, least recently used files are evicted first.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Set period of the expired files sweeper, [`DEFAULT_SWEEP_INTERVAL`] by default.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
+    pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
+        self.key_format = format;
+        self
+    }
+
+    pub fn value_format<NewS>(self, serializer: NewS) -> FsBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        FsBackendBuilder {
+            root: self.root,
+            max_size: self.max_size,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    pub fn compressor<NewC>(self, compressor: NewC) -> FsBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        FsBackendBuilder {
+            root: self.root,
+            max_size: self.max_size,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create root directory, index already stored files and start the sweeper.
+    ///
+    /// The sweeper runs on the current Tokio runtime until the backend and all its
+    /// clones are dropped; without a runtime expired files are only removed on read.
+    pub fn build(self) -> Result<FsBackend<S, C>, Error> {
+        let tmp = self.root.join(TMP_DIR);
+        match fs::remove_dir_all(&tmp) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => fs::create_dir_all(&tmp)?,
+        }
+
+        let mut files = stored_files(&self.root)?
+            .into_iter()
+            .map(|(name, path)| {
+                let metadata = fs::metadata(path)?;
+                Ok((metadata.modified()?, metadata.len(), name))
+            })
+            .collect::<io::Result<Vec<(SystemTime, u64, String)>>>()?;
+        files.sort_unstable();
+        let mut index = Index::default();
+        files
+            .into_iter()
+            .for_each(|(_, size, name)| index.insert(name, size));
+
+        let storage = Arc::new(Storage {
+            root: self.root,
+            max_size: self.max_size,
+            index: Mutex::new(index),
+            tmp_counter: AtomicU64::new(0),
+        });
+        if let Some(max_size) = storage.max_size {
+            let evicted = storage.index().evict(max_size);
+            for name in evicted {
+                match fs::remove_file(storage.path(&name)) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => {}
+                }
+            }
+        }
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(sweeper(Arc::downgrade(&storage), self.sweep_interval));
+            }
+            Err(_) => tracing::warn!("No Tokio runtime, filesystem cache sweeper is not started"),
+        }
+
+        Ok(FsBackend {
+            storage,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+async fn sweeper(storage: Weak<Storage>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(storage) = storage.upgrade() else {
+            break;
+        };
+        match storage.sweep().await {
+            Ok(removed) => tracing::trace!("Removed {removed} expired cache files"),
+            Err(err) => tracing::warn!("Expired cache files sweep failed: {err}"),
+        }
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for FsBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let key = self.key_format.serialize(key)?;
+        let name = Storage::file_name(&key);
+        let bytes = match tokio::fs::read(self.storage.path(&name)).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.storage.index().remove(&name);
+                return Ok(None);
+            }
+            Err(err) => return Err(Error::from(err).into()),
+        };
+        let entry = match Entry::decode(bytes) {
+            Ok(entry) => entry,
+            Err(err) => {
+                tracing::warn!("Removing unreadable cache file {name}: {err}");
+                self.storage.remove_file(&name).await?;
+                return Ok(None);
+            }
+        };
+        // Different key with the same hash.
+        if entry.key != key {
+            return Ok(None);
+        }
+        if entry
+            .deadline
+            .is_some_and(|deadline| deadline <= Utc::now())
+        {
+            self.storage.remove_file(&name).await?;
+            return Ok(None);
+        }
+        self.storage.index().touch(&name);
+        Ok(Some(entry.value))
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let Some(deadline) = entry_deadline(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+
+        let key = self.key_format.serialize(key)?;
+        let name = Storage::file_name(&key);
+        let bytes = Entry {
+            key,
+            deadline,
+            value,
+        }
+        .encode();
+
+        let tmp = self.storage.tmp_path(&name);
+        let path = self.storage.path(&name);
+        tokio::fs::write(&tmp, &bytes).await.map_err(Error::from)?;
+        let moved = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::rename(&tmp, &path).await
+        }
+        .await;
+        if let Err(err) = moved {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(Error::from(err).into());
+        }
+
+        self.storage.index().insert(name, bytes.len() as u64);
+        self.storage.evict().await?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let key = self.key_format.serialize(key)?;
+        match self.storage.remove_file(&Storage::file_name(&key)).await? {
+            true => Ok(DeleteStatus::Deleted(1)),
+            false => Ok(DeleteStatus::Missing),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
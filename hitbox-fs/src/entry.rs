@@ -0,0 +1,177 @@
+//! On-disk layout of a cache entry.
+//!
+//! ```text
+//! | magic "HBFS" | version: u8 | expire: i64 | stale: i64 | deadline: i64 | key length: u32 | key | data |
+//! ```
+//!
+//! Numbers are little endian, timestamps are milliseconds since the Unix epoch and
+//! `i64::MIN` stands for a missing timestamp. `deadline` is the moment the file may
+//! be removed, derived from the write ttl. The serialized key is kept to tell apart
+//! keys with colliding hashes.
+use chrono::{DateTime, Utc};
+use hitbox::CacheValue;
+use hitbox_backend::serializer::Raw;
+
+use crate::error::Error;
+
+const MAGIC: &[u8; 4] = b"HBFS";
+const VERSION: u8 = 1;
+const NO_TIMESTAMP: i64 = i64::MIN;
+
+/// Length of the fixed part of the header, enough to decide whether a file expired.
+pub(crate) const HEADER_LEN: usize = MAGIC.len() + 1 + 8 * 3 + 4;
+
+/// Fixed part of the entry header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub expire: Option<DateTime<Utc>>,
+    pub stale: Option<DateTime<Utc>>,
+    pub deadline: Option<DateTime<Utc>>,
+    key_len: usize,
+}
+
+impl Header {
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let header = bytes
+            .get(..HEADER_LEN)
+            .ok_or(Error::Corrupted("truncated header"))?;
+        let (magic, rest) = header.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(Error::Corrupted("unknown file signature"));
+        }
+        let (version, rest) = rest.split_at(1);
+        if version[0] != VERSION {
+            return Err(Error::Corrupted("unsupported version"));
+        }
+        let (expire, rest) = rest.split_at(8);
+        let (stale, rest) = rest.split_at(8);
+        let (deadline, key_len) = rest.split_at(8);
+        Ok(Self {
+            expire: decode_timestamp(expire)?,
+            stale: decode_timestamp(stale)?,
+            deadline: decode_timestamp(deadline)?,
+            key_len: u32::from_le_bytes(key_len.try_into().expect("4 bytes")) as usize,
+        })
+    }
+
+    /// File may be removed at `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+/// Complete cache entry as stored in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub key: Vec<u8>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub value: CacheValue<Raw>,
+}
+
+impl Entry {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.key.len() + self.value.data.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&encode_timestamp(self.value.expire));
+        bytes.extend_from_slice(&encode_timestamp(self.value.stale));
+        bytes.extend_from_slice(&encode_timestamp(self.deadline));
+        bytes.extend_from_slice(&(self.key.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.key);
+        bytes.extend_from_slice(&self.value.data);
+        bytes
+    }
+
+    pub fn decode(mut bytes: Vec<u8>) -> Result<Self, Error> {
+        let header = Header::decode(&bytes)?;
+        let data_offset = HEADER_LEN + header.key_len;
+        if bytes.len() < data_offset {
+            return Err(Error::Corrupted("truncated key"));
+        }
+        let data = bytes.split_off(data_offset);
+        let key = bytes.split_off(HEADER_LEN);
+        Ok(Self {
+            key,
+            deadline: header.deadline,
+            value: CacheValue::new(data, header.expire, header.stale),
+        })
+    }
+}
+
+fn encode_timestamp(timestamp: Option<DateTime<Utc>>) -> [u8; 8] {
+    timestamp
+        .map_or(NO_TIMESTAMP, |timestamp| timestamp.timestamp_millis())
+        .to_le_bytes()
+}
+
+fn decode_timestamp(bytes: &[u8]) -> Result<Option<DateTime<Utc>>, Error> {
+    match i64::from_le_bytes(bytes.try_into().expect("8 bytes")) {
+        NO_TIMESTAMP => Ok(None),
+        millis => DateTime::from_timestamp_millis(millis)
+            .map(Some)
+            .ok_or(Error::Corrupted("timestamp out of range")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(millis: i64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(millis)
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let entry = Entry {
+            key: b"key".to_vec(),
+            deadline: timestamp(1_700_000_060_000),
+            value: CacheValue::new(
+                b"payload".to_vec(),
+                timestamp(1_700_000_030_000),
+                timestamp(1_700_000_000_000),
+            ),
+        };
+
+        let encoded = entry.encode();
+        let header = Header::decode(&encoded).unwrap();
+
+        assert_eq!(header.deadline, entry.deadline);
+        assert!(header.is_expired(timestamp(1_700_000_060_000).unwrap()));
+        assert!(!header.is_expired(timestamp(1_700_000_059_999).unwrap()));
+        assert_eq!(Entry::decode(encoded).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_decode_without_timestamps() {
+        let entry = Entry {
+            key: Vec::new(),
+            deadline: None,
+            value: CacheValue::new(Vec::new(), None, None),
+        };
+
+        let decoded = Entry::decode(entry.encode()).unwrap();
+
+        assert_eq!(decoded, entry);
+        assert!(
+            !Header::decode(&entry.encode())
+                .unwrap()
+                .is_expired(Utc::now())
+        );
+    }
+
+    #[test]
+    fn test_decode_corrupted() {
+        let mut encoded = Entry {
+            key: b"key".to_vec(),
+            deadline: None,
+            value: CacheValue::new(b"payload".to_vec(), None, None),
+        }
+        .encode();
+
+        assert!(Entry::decode(encoded[..HEADER_LEN + 1].to_vec()).is_err());
+        assert!(Entry::decode(encoded[..HEADER_LEN - 1].to_vec()).is_err());
+        encoded[0] = b'X';
+        assert!(Entry::decode(encoded).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use hitbox_backend::BackendError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Filesystem backend error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Filesystem backend error: corrupted entry, {0}")]
+    Corrupted(&'static str),
+    #[error("Filesystem backend error: background task failed, {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        Self::InternalError(Box::new(error))
+    }
+}
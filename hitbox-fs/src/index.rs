@@ -0,0 +1,102 @@
+//! In-memory index of stored files used for size limited LRU eviction.
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug)]
+struct File {
+    size: u64,
+    used: u64,
+}
+
+/// Sizes of stored files in the order of their last use.
+#[derive(Debug, Default)]
+pub(crate) struct Index {
+    files: HashMap<String, File>,
+    recency: BTreeMap<u64, String>,
+    total_size: u64,
+    clock: u64,
+}
+
+impl Index {
+    /// Total size of indexed files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Record written file as the most recently used one.
+    pub fn insert(&mut self, name: String, size: u64) {
+        self.remove(&name);
+        let used = self.tick();
+        self.recency.insert(used, name.clone());
+        self.files.insert(name, File { size, used });
+        self.total_size += size;
+    }
+
+    /// Mark file as the most recently used one.
+    pub fn touch(&mut self, name: &str) {
+        let used = self.tick();
+        if let Some(file) = self.files.get_mut(name) {
+            self.recency.remove(&file.used);
+            file.used = used;
+            self.recency.insert(used, name.to_owned());
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if let Some(file) = self.files.remove(name) {
+            self.recency.remove(&file.used);
+            self.total_size -= file.size;
+        }
+    }
+
+    /// Drop least recently used files until the total size fits into `max_size`.
+    ///
+    /// Returns names of dropped files, which must be removed from disk.
+    pub fn evict(&mut self, max_size: u64) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.total_size > max_size {
+            let Some((_, name)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(file) = self.files.remove(&name) {
+                self.total_size -= file.size;
+            }
+            evicted.push(name);
+        }
+        evicted
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let mut index = Index::default();
+        index.insert("a".to_owned(), 10);
+        index.insert("b".to_owned(), 10);
+        index.insert("c".to_owned(), 10);
+        index.touch("a");
+
+        assert_eq!(index.evict(15), vec!["b".to_owned(), "c".to_owned()]);
+        assert_eq!(index.total_size(), 10);
+        assert!(index.evict(15).is_empty());
+    }
+
+    #[test]
+    fn test_insert_replaces_size() {
+        let mut index = Index::default();
+        index.insert("a".to_owned(), 10);
+        index.insert("a".to_owned(), 4);
+        index.remove("missing");
+
+        assert_eq!(index.total_size(), 4);
+        index.remove("a");
+        assert_eq!(index.total_size(), 0);
+    }
+}
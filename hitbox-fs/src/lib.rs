@@ -0,0 +1,17 @@
+//! hitbox [Backend] implementation storing entries as files.
+//!
+//! Intended for large responses (reports, exports) which don't belong in Redis
+//! or memory. Every entry is a file under the root directory, sharded by the hash
+//! of its [`CacheKey`]. Files are written atomically via temp file and rename,
+//! expired files are removed by a background sweeper and the least recently used
+//! files are evicted when the total size exceeds the limit.
+//!
+//! [Backend]: hitbox_backend::Backend
+//! [`CacheKey`]: hitbox::CacheKey
+mod backend;
+mod entry;
+pub mod error;
+mod index;
+
+pub use crate::backend::{FsBackend, FsBackendBuilder};
+pub use crate::error::Error;
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::{Backend, DeleteStatus};
+use hitbox_backend_tests::{Conformance, SystemClock};
+use hitbox_fs::FsBackend;
+
+fn value(size: usize) -> CacheValue<Vec<u8>> {
+    CacheValue::new(vec![b'x'; size], None, None)
+}
+
+#[tokio::test]
+async fn test_write_read_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = FsBackend::builder(dir.path()).build().unwrap();
+    let key = CacheKey::from_str("key", "1");
+    let expire = Utc::now() + chrono::Duration::minutes(1);
+    let value = CacheValue::new(b"payload".to_vec(), Some(expire), None);
+
+    backend.write(&key, value.clone(), None).await.unwrap();
+
+    let cached = backend.read(&key).await.unwrap().unwrap();
+    assert_eq!(cached.data, value.data);
+    assert_eq!(
+        cached.expire.map(|expire| expire.timestamp_millis()),
+        Some(expire.timestamp_millis())
+    );
+    assert_eq!(
+        backend.remove(&key).await.unwrap(),
+        DeleteStatus::Deleted(1)
+    );
+    assert_eq!(backend.remove(&key).await.unwrap(), DeleteStatus::Missing);
+    assert!(backend.read(&key).await.unwrap().is_none());
+    assert_eq!(backend.size(), 0);
+}
+
+#[tokio::test]
+async fn test_ttl() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = FsBackend::builder(dir.path()).build().unwrap();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(8), Some(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    assert!(backend.read(&key).await.unwrap().is_some());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sweep_removes_expired_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = FsBackend::builder(dir.path()).build().unwrap();
+    let expiring = CacheKey::from_str("key", "1");
+    let permanent = CacheKey::from_str("key", "2");
+
+    backend
+        .write(&expiring, value(8), Some(Duration::from_millis(10)))
+        .await
+        .unwrap();
+    backend.write(&permanent, value(8), None).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(backend.sweep().await.unwrap(), 1);
+    assert!(backend.read(&permanent).await.unwrap().is_some());
+    assert_eq!(backend.sweep().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_background_sweeper() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = FsBackend::builder(dir.path())
+        .sweep_interval(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(8), Some(Duration::from_millis(10)))
+        .await
+        .unwrap();
+    assert!(backend.size() > 0);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(backend.size(), 0);
+}
+
+#[tokio::test]
+async fn test_lru_eviction() {
+    let dir = tempfile::tempdir().unwrap();
+    // every file is 1024 bytes of payload plus header and key
+    let backend = FsBackend::builder(dir.path())
+        .max_size(2500)
+        .build()
+        .unwrap();
+    let keys: Vec<_> = (0..3)
+        .map(|i| CacheKey::from_str("key", &i.to_string()))
+        .collect();
+
+    backend.write(&keys[0], value(1024), None).await.unwrap();
+    backend.write(&keys[1], value(1024), None).await.unwrap();
+    backend.read(&keys[0]).await.unwrap().unwrap();
+    backend.write(&keys[2], value(1024), None).await.unwrap();
+
+    assert!(backend.read(&keys[0]).await.unwrap().is_some());
+    assert!(backend.read(&keys[1]).await.unwrap().is_none());
+    assert!(backend.read(&keys[2]).await.unwrap().is_some());
+    assert!(backend.size() <= 2500);
+}
+
+#[tokio::test]
+async fn test_reopen_restores_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = CacheKey::from_str("key", "1");
+    let size = {
+        let backend = FsBackend::builder(dir.path()).build().unwrap();
+        backend.write(&key, value(1024), None).await.unwrap();
+        backend.size()
+    };
+
+    let backend = FsBackend::builder(dir.path()).build().unwrap();
+
+    assert_eq!(backend.size(), size);
+    assert!(backend.read(&key).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_conformance() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = FsBackend::builder(dir.path()).build().unwrap();
+
+    Conformance::new(&backend, SystemClock)
+        .ttl(Duration::from_millis(200))
+        .run()
+        .await;
+}
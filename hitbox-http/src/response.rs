@@ -6,7 +6,11 @@ use chrono::Utc;
 use hitbox::{
     CachePolicy, CacheValue, CacheableResponse, EntityPolicyConfig, predicate::PredicateResult,
 };
+#[cfg(feature = "rkyv")]
+use hitbox_backend::serializer::{FormatError, ZeroCopy};
 use http::{HeaderMap, Response, response::Parts};
+#[cfg(feature = "rkyv")]
+use http::{HeaderName, HeaderValue};
 use hyper::body::Body as HttpBody;
 use serde::{Deserialize, Serialize};
 
@@ -62,12 +66,59 @@ where
 pub struct SerializableHttpResponse {
     status: u16,
     version: String,
-    #[serde(with = "serde_bytes")]
-    body: Vec<u8>,
+    body: Bytes,
     #[serde(with = "http_serde::header_map")]
     headers: HeaderMap,
 }
 
+/// Form of [`SerializableHttpResponse`] stored by the rkyv format.
+#[cfg(feature = "rkyv")]
+#[derive(Debug, rkyv::Archive, rkyv::Serialize)]
+#[rkyv(archived = ArchivedHttpResponse)]
+pub struct HttpResponseArchive {
+    status: u16,
+    version: String,
+    headers: Vec<(String, Vec<u8>)>,
+    body: Bytes,
+}
+
+/// Reads the response in place: the body and header values are slices of
+/// the buffer returned by the backend.
+#[cfg(feature = "rkyv")]
+impl ZeroCopy for SerializableHttpResponse {
+    type Archive = HttpResponseArchive;
+
+    fn to_archive(&self) -> HttpResponseArchive {
+        HttpResponseArchive {
+            status: self.status,
+            version: self.version.clone(),
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str().to_owned(), value.as_bytes().to_vec()))
+                .collect(),
+            body: self.body.clone(),
+        }
+    }
+
+    fn from_archived(archived: &ArchivedHttpResponse, data: &Bytes) -> Result<Self, FormatError> {
+        let mut headers = HeaderMap::with_capacity(archived.headers.len());
+        for header in archived.headers.iter() {
+            let name = HeaderName::from_bytes(header.0.as_bytes())
+                .map_err(|error| FormatError::Deserialize(Box::new(error)))?;
+            let value = HeaderValue::from_maybe_shared(data.slice_ref(header.1.as_slice()))
+                .map_err(|error| FormatError::Deserialize(Box::new(error)))?;
+            headers.append(name, value);
+        }
+        Ok(SerializableHttpResponse {
+            status: archived.status.into(),
+            version: archived.version.as_str().to_owned(),
+            body: data.slice_ref(archived.body.as_slice()),
+            headers,
+        })
+    }
+}
+
 #[async_trait]
 impl<ResBody> CacheableResponse for CacheableHttpResponse<ResBody>
 where
@@ -108,8 +159,7 @@ where
             .collect()
             .await
             .unwrap()
-            .to_bytes()
-            .to_vec();
+            .to_bytes();
 
         // We can store the HeaderMap directly, including pseudo-headers
         // HeaderMap is designed to handle pseudo-headers and http-serde will serialize them correctly
@@ -122,7 +172,7 @@ where
     }
 
     async fn from_cached(cached: Self::Cached) -> Self {
-        let body = ResBody::from_bytes(Bytes::from(cached.body));
+        let body = ResBody::from_bytes(cached.body);
         let mut response = Response::builder()
             .status(cached.status)
             .body(body)
@@ -452,3 +452,44 @@ async fn test_comparison_detects_multivalue_count_mismatch() {
 
     assert_responses_equal(original, different);
 }
+
+#[cfg(feature = "rkyv")]
+#[tokio::test]
+async fn test_rkyv_roundtrip() {
+    use std::any::TypeId;
+
+    use bytes::Bytes;
+    use hitbox_backend::serializer::{Format, RkyvFormat};
+    use hitbox_http::SerializableHttpResponse;
+
+    let format = RkyvFormat::new().register::<SerializableHttpResponse>();
+    let response = || {
+        Response::builder()
+            .status(201)
+            .header("content-type", "application/json")
+            .header("set-cookie", "a=1")
+            .header("set-cookie", "b=2")
+            .body(r#"{"data":"test"}"#.to_string())
+            .unwrap()
+    };
+
+    let cached = match CacheableHttpResponse::from_response(response())
+        .into_cached()
+        .await
+    {
+        hitbox::CachePolicy::Cacheable(cached) => cached,
+        hitbox::CachePolicy::NonCacheable(_) => panic!("Expected cacheable"),
+    };
+    let archive = Bytes::from(format.serialize_typed(&cached).unwrap().unwrap());
+    let restored = format
+        .deserialize_typed(&archive, TypeId::of::<SerializableHttpResponse>())
+        .unwrap()
+        .unwrap()
+        .downcast::<SerializableHttpResponse>()
+        .unwrap();
+    let restored = CacheableHttpResponse::<String>::from_cached(*restored)
+        .await
+        .into_response();
+
+    assert_responses_equal(response(), restored);
+}
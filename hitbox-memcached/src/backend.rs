@@ -0,0 +1,522 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use hitbox_backend::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    entry_ttl,
+};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{
+    error::Error,
+    item::{self, Chunked, FLAG_CHUNKED, FLAG_VALUE, Stored, VALUE_HEADER_LEN},
+    key::{chunk_key, storage_key},
+    protocol::{self, Connection, Item},
+};
+
+/// Default item size limit, memcached's default `item_size_max` minus room for
+/// the key and item bookkeeping.
+pub const DEFAULT_MAX_ITEM_SIZE: usize = 1024 * 1024 - 1024;
+
+/// Default timeout of establishing a connection and of waiting for a response.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handling of values which don't fit into a single item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Oversized {
+    /// Don't cache the value, removing the previous value of the key.
+    #[default]
+    Skip,
+    /// Split the value into chunks stored as separate items on the same server.
+    ///
+    /// A chunked value is only readable while all its chunks are, so under memory
+    /// pressure the eviction of any chunk turns the value into a miss.
+    Chunk,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Timeouts {
+    connection: Duration,
+    response: Duration,
+}
+
+/// Memcached server with a single lazily established connection.
+struct Server {
+    address: String,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Server {
+    fn new(address: String) -> Self {
+        Self {
+            address,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Rendezvous hashing weight of the server for `key`.
+    fn weight(&self, key: &str) -> u64 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.address.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(key.as_bytes());
+        let hash = hasher.finalize();
+        u64::from_le_bytes(hash.as_bytes()[..8].try_into().expect("8 bytes"))
+    }
+
+    async fn connection(
+        &self,
+        timeouts: Timeouts,
+    ) -> Result<MutexGuard<'_, Option<Connection>>, Error> {
+        let mut guard = self.connection.lock().await;
+        if guard.is_none() {
+            let connection =
+                tokio::time::timeout(timeouts.connection, Connection::connect(&self.address))
+                    .await
+                    .map_err(|_| Error::Timeout)??;
+            *guard = Some(connection);
+        }
+        Ok(guard)
+    }
+
+    async fn get(&self, timeouts: Timeouts, keys: &[String]) -> Result<Vec<Item>, Error> {
+        let mut guard = self.connection(timeouts).await?;
+        let connection = guard.as_mut().expect("connection is established");
+        let items = timed(timeouts, connection.get(keys)).await;
+        discard_on_error(&mut guard, items)
+    }
+
+    async fn set(
+        &self,
+        timeouts: Timeouts,
+        key: &str,
+        flags: u32,
+        exptime: u32,
+        data: &[u8],
+    ) -> Result<protocol::Stored, Error> {
+        let mut guard = self.connection(timeouts).await?;
+        let connection = guard.as_mut().expect("connection is established");
+        let stored = timed(timeouts, connection.set(key, flags, exptime, data)).await;
+        discard_on_error(&mut guard, stored)
+    }
+
+    async fn delete(&self, timeouts: Timeouts, key: &str) -> Result<bool, Error> {
+        let mut guard = self.connection(timeouts).await?;
+        let connection = guard.as_mut().expect("connection is established");
+        let deleted = timed(timeouts, connection.delete(key)).await;
+        discard_on_error(&mut guard, deleted)
+    }
+}
+
+async fn timed<T>(
+    timeouts: Timeouts,
+    request: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    tokio::time::timeout(timeouts.response, request)
+        .await
+        .unwrap_or(Err(Error::Timeout))
+}
+
+/// State of the connection is unknown after a failed request, so it is reopened.
+fn discard_on_error<T>(
+    connection: &mut Option<Connection>,
+    result: Result<T, Error>,
+) -> Result<T, Error> {
+    if result.is_err() {
+        *connection = None;
+    }
+    result
+}
+
+/// Memcached cache backend.
+///
+/// Every server is used through one connection, requests to the same server are
+/// sent one at a time.
+#[derive(Clone)]
+pub struct MemcachedBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    servers: Arc<[Server]>,
+    timeouts: Timeouts,
+    max_item_size: usize,
+    oversized: Oversized,
+    generation: Arc<AtomicU64>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl MemcachedBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new MemcachedBackend builder with default settings.
+    pub fn builder() -> MemcachedBackendBuilder<JsonFormat, PassthroughCompressor> {
+        MemcachedBackendBuilder::default()
+    }
+}
+
+impl<S, C> MemcachedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn server(&self, key: &str) -> &Server {
+        self.servers
+            .iter()
+            .max_by_key(|server| server.weight(key))
+            .expect("at least one server is configured")
+    }
+
+    fn storage_key(&self, key: &CacheKey) -> BackendResult<String> {
+        Ok(storage_key(&self.key_format.serialize(key)?))
+    }
+
+    async fn read_chunks(
+        &self,
+        server: &Server,
+        key: &str,
+        head: Chunked,
+    ) -> Result<Option<CacheValue<Raw>>, Error> {
+        let keys: Vec<String> = (0..head.chunks)
+            .map(|index| chunk_key(key, head.generation, index))
+            .collect();
+        let mut chunks: HashMap<String, Vec<u8>> = server
+            .get(self.timeouts, &keys)
+            .await?
+            .into_iter()
+            .map(|item| (item.key, item.data))
+            .collect();
+        let mut data = Vec::with_capacity(head.length as usize);
+        for key in &keys {
+            match chunks.remove(key) {
+                Some(chunk) => data.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+        if data.len() as u64 != head.length {
+            return Err(Error::Corrupted("chunks don't match value length"));
+        }
+        Ok(Some(CacheValue::new(data, head.expire, head.stale)))
+    }
+
+    async fn write_chunks(
+        &self,
+        server: &Server,
+        key: &str,
+        value: CacheValue<Raw>,
+        exptime: u32,
+    ) -> Result<(), Error> {
+        let head = Chunked {
+            expire: value.expire,
+            stale: value.stale,
+            generation: self.generation.fetch_add(1, Ordering::Relaxed),
+            chunks: value.data.chunks(self.max_item_size).len() as u32,
+            length: value.data.len() as u64,
+        };
+        for (index, chunk) in value.data.chunks(self.max_item_size).enumerate() {
+            let chunk_key = chunk_key(key, head.generation, index as u32);
+            let stored = server
+                .set(self.timeouts, &chunk_key, 0, exptime, chunk)
+                .await?;
+            if stored == protocol::Stored::TooLarge {
+                tracing::debug!("Chunk of value {key} is too large for memcached");
+                return self.skip(server, key).await;
+            }
+        }
+        server
+            .set(
+                self.timeouts,
+                key,
+                FLAG_CHUNKED,
+                exptime,
+                &item::encode_chunked(&head),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Remove the previous value of a key whose new value isn't stored, so
+    /// reads miss instead of returning the stale value.
+    async fn skip(&self, server: &Server, key: &str) -> Result<(), Error> {
+        server.delete(self.timeouts, key).await?;
+        Ok(())
+    }
+}
+
+impl<S, C> std::fmt::Debug for MemcachedBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let servers: Vec<_> = self.servers.iter().map(|server| &server.address).collect();
+        f.debug_struct("MemcachedBackend")
+            .field("servers", &servers)
+            .field("timeouts", &self.timeouts)
+            .field("max_item_size", &self.max_item_size)
+            .field("oversized", &self.oversized)
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish()
+    }
+}
+
+/// Part of builder pattern implementation for MemcachedBackend.
+pub struct MemcachedBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    servers: Vec<String>,
+    timeouts: Timeouts,
+    max_item_size: usize,
+    oversized: Oversized,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for MemcachedBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            timeouts: Timeouts {
+                connection: DEFAULT_TIMEOUT,
+                response: DEFAULT_TIMEOUT,
+            },
+            max_item_size: DEFAULT_MAX_ITEM_SIZE,
+            oversized: Oversized::default(),
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> MemcachedBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Add server address (`host:port`), `127.0.0.1:11211` is used if none is added.
+    pub fn server(mut self, address: impl Into<String>) -> Self {
+        self.servers.push(address.into());
+        self
+    }
+
+    /// Add several server addresses.
+    pub fn servers<I>(mut self, addresses: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.servers.extend(addresses.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set timeout of establishing a connection.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.connection = timeout;
+        self
+    }
+
+    /// Set timeout of waiting for a response.
+    pub fn response_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.response = timeout;
+        self
+    }
+
+    /// Set largest value sent in a single item, [`DEFAULT_MAX_ITEM_SIZE`] by default.
+    pub fn max_item_size(mut self, size: usize) -> Self {
+        self.max_item_size = size;
+        self
+    }
+
+    /// Set handling of values larger than the item size limit.
+    pub fn oversized(mut self, oversized: Oversized) -> Self {
+        self.oversized = oversized;
+        self
+    }
+
+    /// Set key serialization format, `Bitcode` by default.
+    ///
+    /// Binary keys are always hashed, `UrlEncoded` keeps short keys readable but
+    /// leaves key prefix and version out.
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    /// Set value serialization format (JSON, Bincode, etc.)
+    pub fn value_format<NewS>(self, serializer: NewS) -> MemcachedBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        MemcachedBackendBuilder {
+            servers: self.servers,
+            timeouts: self.timeouts,
+            max_item_size: self.max_item_size,
+            oversized: self.oversized,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    /// Set compressor for value compression
+    pub fn compressor<NewC>(self, compressor: NewC) -> MemcachedBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        MemcachedBackendBuilder {
+            servers: self.servers,
+            timeouts: self.timeouts,
+            max_item_size: self.max_item_size,
+            oversized: self.oversized,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create new instance of memcached backend, connections are established lazily.
+    pub fn build(self) -> MemcachedBackend<S, C> {
+        let servers = if self.servers.is_empty() {
+            vec!["127.0.0.1:11211".to_owned()]
+        } else {
+            self.servers
+        };
+        MemcachedBackend {
+            servers: servers.into_iter().map(Server::new).collect(),
+            timeouts: self.timeouts,
+            max_item_size: self.max_item_size.max(VALUE_HEADER_LEN + 1),
+            oversized: self.oversized,
+            generation: Arc::new(AtomicU64::new(
+                Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64,
+            )),
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        }
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for MemcachedBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let key = self.storage_key(key)?;
+        let server = self.server(&key);
+        let Some(found) = server
+            .get(self.timeouts, std::slice::from_ref(&key))
+            .await?
+            .pop()
+        else {
+            return Ok(None);
+        };
+        let stored = match item::decode(found.flags, found.data) {
+            Ok(stored) => stored,
+            Err(Error::Corrupted(reason)) => {
+                tracing::warn!("Removing unreadable memcached item {key}: {reason}");
+                server.delete(self.timeouts, &key).await?;
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        match stored {
+            Stored::Value(value) => Ok(Some(value)),
+            Stored::Chunked(head) => Ok(self.read_chunks(server, &key, head).await?),
+        }
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let now = Utc::now();
+        let Some(ttl) = entry_ttl(ttl, value.expire, now) else {
+            return Ok(());
+        };
+        let exptime = protocol::exptime(ttl, now);
+        let key = self.storage_key(key)?;
+        let server = self.server(&key);
+
+        if VALUE_HEADER_LEN + value.data.len() > self.max_item_size {
+            return match self.oversized {
+                Oversized::Skip => {
+                    tracing::debug!("Value {key} exceeds memcached item size limit, skipped");
+                    Ok(self.skip(server, &key).await?)
+                }
+                Oversized::Chunk => Ok(self.write_chunks(server, &key, value, exptime).await?),
+            };
+        }
+
+        let stored = server
+            .set(
+                self.timeouts,
+                &key,
+                FLAG_VALUE,
+                exptime,
+                &item::encode_value(&value),
+            )
+            .await?;
+        if stored == protocol::Stored::TooLarge {
+            tracing::debug!("Value {key} is too large for memcached, skipped");
+            self.skip(server, &key).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let key = self.storage_key(key)?;
+        let server = self.server(&key);
+        // Chunks of a chunked value are removed too, before its head item.
+        let head = server
+            .get(self.timeouts, std::slice::from_ref(&key))
+            .await?
+            .pop()
+            .and_then(|found| match item::decode(found.flags, found.data) {
+                Ok(Stored::Chunked(head)) => Some(head),
+                _ => None,
+            });
+        if let Some(head) = head {
+            for index in 0..head.chunks {
+                let chunk_key = chunk_key(&key, head.generation, index);
+                server.delete(self.timeouts, &chunk_key).await?;
+            }
+        }
+        match server.delete(self.timeouts, &key).await? {
+            true => Ok(DeleteStatus::Deleted(1)),
+            false => Ok(DeleteStatus::Missing),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
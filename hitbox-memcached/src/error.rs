@@ -0,0 +1,24 @@
+use hitbox_backend::BackendError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Memcached backend error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Memcached backend error: operation timed out")]
+    Timeout,
+    #[error("Memcached backend error: unexpected response {0:?}")]
+    Protocol(String),
+    #[error("Memcached backend error: server responded {0:?}")]
+    Server(String),
+    #[error("Memcached backend error: corrupted item, {0}")]
+    Corrupted(&'static str),
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io(_) | Error::Timeout => Self::ConnectionError(Box::new(error)),
+            _ => Self::InternalError(Box::new(error)),
+        }
+    }
+}
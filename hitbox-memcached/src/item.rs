@@ -0,0 +1,142 @@
+//! Layout of cache values in memcached items.
+//!
+//! Item flags tell the layout apart. Timestamps are milliseconds since the Unix
+//! epoch, `i64::MIN` stands for a missing one, all numbers are little endian.
+//!
+//! ```text
+//! FLAG_VALUE:   | expire: i64 | stale: i64 | data |
+//! FLAG_CHUNKED: | expire: i64 | stale: i64 | generation: u64 | chunks: u32 | length: u64 |
+//! ```
+//!
+//! Chunks of a chunked value are stored as raw data in separate items, see
+//! [`chunk_key`](crate::key::chunk_key).
+use chrono::{DateTime, Utc};
+use hitbox::CacheValue;
+use hitbox_backend::serializer::Raw;
+
+use crate::error::Error;
+
+pub(crate) const FLAG_VALUE: u32 = 1;
+pub(crate) const FLAG_CHUNKED: u32 = 2;
+
+const NO_TIMESTAMP: i64 = i64::MIN;
+const TIMESTAMPS_LEN: usize = 16;
+const CHUNKED_LEN: usize = TIMESTAMPS_LEN + 8 + 4 + 8;
+
+/// Size of the value header, which counts towards the item size limit.
+pub(crate) const VALUE_HEADER_LEN: usize = TIMESTAMPS_LEN;
+
+/// Head item of a value split into chunks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Chunked {
+    pub expire: Option<DateTime<Utc>>,
+    pub stale: Option<DateTime<Utc>>,
+    pub generation: u64,
+    pub chunks: u32,
+    pub length: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Stored {
+    Value(CacheValue<Raw>),
+    Chunked(Chunked),
+}
+
+pub(crate) fn encode_value(value: &CacheValue<Raw>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(VALUE_HEADER_LEN + value.data.len());
+    encode_timestamps(&mut bytes, value.expire, value.stale);
+    bytes.extend_from_slice(&value.data);
+    bytes
+}
+
+pub(crate) fn encode_chunked(chunked: &Chunked) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(CHUNKED_LEN);
+    encode_timestamps(&mut bytes, chunked.expire, chunked.stale);
+    bytes.extend_from_slice(&chunked.generation.to_le_bytes());
+    bytes.extend_from_slice(&chunked.chunks.to_le_bytes());
+    bytes.extend_from_slice(&chunked.length.to_le_bytes());
+    bytes
+}
+
+pub(crate) fn decode(flags: u32, mut bytes: Vec<u8>) -> Result<Stored, Error> {
+    if bytes.len() < TIMESTAMPS_LEN {
+        return Err(Error::Corrupted("truncated header"));
+    }
+    let expire = decode_timestamp(&bytes[..8])?;
+    let stale = decode_timestamp(&bytes[8..16])?;
+    match flags {
+        FLAG_VALUE => {
+            let data = bytes.split_off(TIMESTAMPS_LEN);
+            Ok(Stored::Value(CacheValue::new(data, expire, stale)))
+        }
+        FLAG_CHUNKED if bytes.len() == CHUNKED_LEN => Ok(Stored::Chunked(Chunked {
+            expire,
+            stale,
+            generation: u64::from_le_bytes(bytes[16..24].try_into().expect("8 bytes")),
+            chunks: u32::from_le_bytes(bytes[24..28].try_into().expect("4 bytes")),
+            length: u64::from_le_bytes(bytes[28..36].try_into().expect("8 bytes")),
+        })),
+        FLAG_CHUNKED => Err(Error::Corrupted("malformed chunked header")),
+        _ => Err(Error::Corrupted("unknown flags")),
+    }
+}
+
+fn encode_timestamps(
+    bytes: &mut Vec<u8>,
+    expire: Option<DateTime<Utc>>,
+    stale: Option<DateTime<Utc>>,
+) {
+    for timestamp in [expire, stale] {
+        let millis = timestamp.map_or(NO_TIMESTAMP, |timestamp| timestamp.timestamp_millis());
+        bytes.extend_from_slice(&millis.to_le_bytes());
+    }
+}
+
+fn decode_timestamp(bytes: &[u8]) -> Result<Option<DateTime<Utc>>, Error> {
+    match i64::from_le_bytes(bytes.try_into().expect("8 bytes")) {
+        NO_TIMESTAMP => Ok(None),
+        millis => DateTime::from_timestamp_millis(millis)
+            .map(Some)
+            .ok_or(Error::Corrupted("timestamp out of range")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_roundtrip() {
+        let value = CacheValue::new(
+            b"data".to_vec(),
+            DateTime::from_timestamp_millis(1_700_000_030_000),
+            None,
+        );
+
+        let decoded = decode(FLAG_VALUE, encode_value(&value)).unwrap();
+
+        assert_eq!(decoded, Stored::Value(value));
+    }
+
+    #[test]
+    fn test_chunked_roundtrip() {
+        let chunked = Chunked {
+            expire: None,
+            stale: DateTime::from_timestamp_millis(1_700_000_000_000),
+            generation: 42,
+            chunks: 3,
+            length: 2_500_000,
+        };
+
+        let decoded = decode(FLAG_CHUNKED, encode_chunked(&chunked)).unwrap();
+
+        assert_eq!(decoded, Stored::Chunked(chunked));
+    }
+
+    #[test]
+    fn test_decode_corrupted() {
+        assert!(decode(FLAG_VALUE, vec![0; 4]).is_err());
+        assert!(decode(FLAG_CHUNKED, vec![0; 20]).is_err());
+        assert!(decode(0, vec![0; 20]).is_err());
+    }
+}
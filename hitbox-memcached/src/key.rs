@@ -0,0 +1,70 @@
+//! Mapping of serialized cache keys to memcached keys.
+
+/// Longest key accepted by memcached.
+pub const MAX_KEY_LEN: usize = 250;
+
+const HASHED_PREFIX: &str = "hitbox:";
+
+/// Memcached key for a serialized [`CacheKey`].
+///
+/// Keys which are too long or contain bytes memcached doesn't accept (whitespace,
+/// control characters and non-ASCII, e.g. binary key formats) are replaced by
+/// their blake3 hash.
+///
+/// [`CacheKey`]: hitbox::CacheKey
+pub(crate) fn storage_key(serialized: &[u8]) -> String {
+    let valid =
+        serialized.len() <= MAX_KEY_LEN && serialized.iter().all(|byte| byte.is_ascii_graphic());
+    match String::from_utf8(serialized.to_vec()) {
+        Ok(key) if valid => key,
+        _ => hashed(serialized),
+    }
+}
+
+/// Key of the `index`-th chunk of a value stored under `key`.
+///
+/// `generation` tells chunks of concurrent writes of the same key apart.
+pub(crate) fn chunk_key(key: &str, generation: u64, index: u32) -> String {
+    hashed(format!("{key}#{generation}#{index}").as_bytes())
+}
+
+fn hashed(bytes: &[u8]) -> String {
+    format!("{HASHED_PREFIX}{}", blake3::hash(bytes).to_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_key_is_kept() {
+        assert_eq!(
+            storage_key(b"method=GET&path=%2Fusers"),
+            "method=GET&path=%2Fusers"
+        );
+        let longest = "k".repeat(MAX_KEY_LEN);
+        assert_eq!(storage_key(longest.as_bytes()), longest);
+    }
+
+    #[test]
+    fn test_invalid_key_is_hashed() {
+        for key in [
+            "k".repeat(MAX_KEY_LEN + 1).into_bytes(),
+            b"with space".to_vec(),
+            b"binary\x00\x01".to_vec(),
+            "ключ".as_bytes().to_vec(),
+        ] {
+            let hashed = storage_key(&key);
+            assert!(hashed.starts_with(HASHED_PREFIX));
+            assert!(hashed.len() <= MAX_KEY_LEN);
+            assert_eq!(hashed, storage_key(&key));
+        }
+        assert_ne!(storage_key(b"a b"), storage_key(b"a  b"));
+    }
+
+    #[test]
+    fn test_chunk_keys_differ() {
+        assert_ne!(chunk_key("key", 1, 0), chunk_key("key", 1, 1));
+        assert_ne!(chunk_key("key", 1, 0), chunk_key("key", 2, 0));
+    }
+}
@@ -0,0 +1,18 @@
+//! hitbox [Backend] implementation for memcached.
+//!
+//! The backend speaks the memcached text protocol over plain TCP. Keys are spread
+//! over the configured servers with rendezvous hashing, serialized keys which are
+//! not valid memcached keys are hashed, and values larger than the item size limit
+//! are either skipped or split into chunks (see [`Oversized`]).
+//!
+//! [Backend]: hitbox_backend::Backend
+mod backend;
+pub mod error;
+mod item;
+mod key;
+mod protocol;
+
+pub use crate::backend::{MemcachedBackend, MemcachedBackendBuilder, Oversized};
+pub use crate::error::Error;
+pub use crate::key::MAX_KEY_LEN;
+pub use crate::protocol::{MAX_RELATIVE_EXPTIME, exptime};
@@ -0,0 +1,173 @@
+//! Client side of the memcached text protocol.
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream},
+    net::TcpStream,
+};
+
+use crate::error::Error;
+
+/// Longest expiration memcached treats as relative, larger values are Unix timestamps.
+pub const MAX_RELATIVE_EXPTIME: u64 = 60 * 60 * 24 * 30;
+
+/// Convert ttl into memcached expiration time, `0` means the item never expires.
+///
+/// Sub-second ttl is rounded up, since `0` would disable expiration, and ttl over
+/// 30 days is sent as an absolute Unix timestamp.
+pub fn exptime(ttl: Option<Duration>, now: DateTime<Utc>) -> u32 {
+    let Some(ttl) = ttl else {
+        return 0;
+    };
+    let seconds = (ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0)).max(1);
+    if seconds <= MAX_RELATIVE_EXPTIME {
+        seconds as u32
+    } else {
+        (now.timestamp().max(0) as u64)
+            .saturating_add(seconds)
+            .min(u64::from(u32::MAX)) as u32
+    }
+}
+
+/// Item returned by `get`.
+#[derive(Debug)]
+pub(crate) struct Item {
+    pub key: String,
+    pub flags: u32,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of `set`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Stored {
+    Stored,
+    TooLarge,
+}
+
+pub(crate) struct Connection {
+    stream: BufStream<TcpStream>,
+}
+
+impl Connection {
+    pub async fn connect(address: &str) -> Result<Self, Error> {
+        let stream = TcpStream::connect(address).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream: BufStream::new(stream),
+        })
+    }
+
+    /// Fetch present items among `keys`.
+    pub async fn get(&mut self, keys: &[String]) -> Result<Vec<Item>, Error> {
+        let mut command = String::from("get");
+        for key in keys {
+            command.push(' ');
+            command.push_str(key);
+        }
+        command.push_str("\r\n");
+        self.stream.write_all(command.as_bytes()).await?;
+        self.stream.flush().await?;
+
+        let mut items = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            if line == "END" {
+                return Ok(items);
+            }
+            let mut fields = line.split(' ');
+            let (Some("VALUE"), Some(key), Some(flags), Some(length)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(unexpected(line));
+            };
+            let (Ok(flags), Ok(length)) = (flags.parse(), length.parse::<usize>()) else {
+                return Err(unexpected(line));
+            };
+            let key = key.to_owned();
+            let mut data = vec![0; length + 2];
+            self.stream.read_exact(&mut data).await?;
+            if !data.ends_with(b"\r\n") {
+                return Err(Error::Protocol("data block without terminator".to_owned()));
+            }
+            data.truncate(length);
+            items.push(Item { key, flags, data });
+        }
+    }
+
+    pub async fn set(
+        &mut self,
+        key: &str,
+        flags: u32,
+        exptime: u32,
+        data: &[u8],
+    ) -> Result<Stored, Error> {
+        let header = format!("set {key} {flags} {exptime} {}\r\n", data.len());
+        self.stream.write_all(header.as_bytes()).await?;
+        self.stream.write_all(data).await?;
+        self.stream.write_all(b"\r\n").await?;
+        self.stream.flush().await?;
+
+        let line = self.read_line().await?;
+        match line.as_str() {
+            "STORED" => Ok(Stored::Stored),
+            line if line.starts_with("SERVER_ERROR object too large") => Ok(Stored::TooLarge),
+            _ => Err(unexpected(line)),
+        }
+    }
+
+    /// Returns whether the key was present.
+    pub async fn delete(&mut self, key: &str) -> Result<bool, Error> {
+        self.stream
+            .write_all(format!("delete {key}\r\n").as_bytes())
+            .await?;
+        self.stream.flush().await?;
+
+        let line = self.read_line().await?;
+        match line.as_str() {
+            "DELETED" => Ok(true),
+            "NOT_FOUND" => Ok(false),
+            _ => Err(unexpected(line)),
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String, Error> {
+        let mut line = Vec::new();
+        self.stream.read_until(b'\n', &mut line).await?;
+        if !line.ends_with(b"\r\n") {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        line.truncate(line.len() - 2);
+        String::from_utf8(line).map_err(|_| Error::Protocol("non UTF-8 response".to_owned()))
+    }
+}
+
+fn unexpected(line: String) -> Error {
+    if line == "ERROR" || line.starts_with("CLIENT_ERROR") || line.starts_with("SERVER_ERROR") {
+        Error::Server(line)
+    } else {
+        Error::Protocol(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exptime() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        assert_eq!(exptime(None, now), 0);
+        assert_eq!(exptime(Some(Duration::ZERO), now), 1);
+        assert_eq!(exptime(Some(Duration::from_millis(1500)), now), 2);
+        assert_eq!(
+            exptime(Some(Duration::from_secs(MAX_RELATIVE_EXPTIME)), now),
+            MAX_RELATIVE_EXPTIME as u32
+        );
+        assert_eq!(
+            exptime(Some(Duration::from_secs(MAX_RELATIVE_EXPTIME + 1)), now),
+            1_700_000_000 + MAX_RELATIVE_EXPTIME as u32 + 1
+        );
+    }
+}
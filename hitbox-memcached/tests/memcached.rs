@@ -0,0 +1,336 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::{Backend, BackendError, CacheKeyFormat, DeleteStatus, FailoverBackend};
+use hitbox_memcached::{MAX_KEY_LEN, MAX_RELATIVE_EXPTIME, MemcachedBackend, Oversized};
+
+mod support;
+
+use support::{FakeMemcached, ITEM_SIZE_MAX, StoredItem};
+
+fn value(data: Vec<u8>) -> CacheValue<Vec<u8>> {
+    CacheValue::new(data, None, None)
+}
+
+#[tokio::test]
+async fn test_write_read_remove() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder().server(&server.address).build();
+    let key = CacheKey::from_str("key", "1");
+    let expire = Utc::now() + chrono::Duration::minutes(1);
+    let stale = Utc::now() + chrono::Duration::seconds(30);
+    let value = CacheValue::new(b"payload".to_vec(), Some(expire), Some(stale));
+
+    backend.write(&key, value.clone(), None).await.unwrap();
+
+    let cached = backend.read(&key).await.unwrap().unwrap();
+    assert_eq!(cached.data, value.data);
+    assert_eq!(
+        cached.expire.map(|expire| expire.timestamp_millis()),
+        Some(expire.timestamp_millis())
+    );
+    assert_eq!(
+        cached.stale.map(|stale| stale.timestamp_millis()),
+        Some(stale.timestamp_millis())
+    );
+    assert_eq!(
+        backend.remove(&key).await.unwrap(),
+        DeleteStatus::Deleted(1)
+    );
+    assert_eq!(backend.remove(&key).await.unwrap(), DeleteStatus::Missing);
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_expiry_conversion() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder()
+        .server(&server.address)
+        .key_format(CacheKeyFormat::UrlEncoded)
+        .build();
+    let exptime = |key: &str| server.items()[key].exptime;
+
+    backend
+        .write(
+            &CacheKey::from_str("ttl", "short"),
+            value(vec![1]),
+            Some(Duration::from_millis(1500)),
+        )
+        .await
+        .unwrap();
+    backend
+        .write(
+            &CacheKey::from_str("ttl", "long"),
+            value(vec![1]),
+            Some(Duration::from_secs(MAX_RELATIVE_EXPTIME + 60)),
+        )
+        .await
+        .unwrap();
+    backend
+        .write(&CacheKey::from_str("ttl", "none"), value(vec![1]), None)
+        .await
+        .unwrap();
+    let expire = Utc::now() + chrono::Duration::seconds(10);
+    backend
+        .write(
+            &CacheKey::from_str("ttl", "expire"),
+            CacheValue::new(vec![1], Some(expire), None),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(exptime("ttl=short"), 2);
+    let absolute = i64::from(exptime("ttl=long")) - Utc::now().timestamp();
+    assert!((MAX_RELATIVE_EXPTIME as i64..=MAX_RELATIVE_EXPTIME as i64 + 60).contains(&absolute));
+    assert_eq!(exptime("ttl=none"), 0);
+    assert!((9..=10).contains(&exptime("ttl=expire")));
+}
+
+#[tokio::test]
+async fn test_expired_value_is_not_written() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder().server(&server.address).build();
+    let key = CacheKey::from_str("key", "1");
+    let expired = CacheValue::new(
+        b"value".to_vec(),
+        Some(Utc::now() - chrono::Duration::seconds(1)),
+        None,
+    );
+
+    backend.write(&key, expired, None).await.unwrap();
+
+    assert!(server.items().is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_keys_are_hashed() {
+    let server = FakeMemcached::start().await;
+    let long = CacheKey::from_str("key", &"x".repeat(MAX_KEY_LEN));
+    let spaced = CacheKey::from_str("key", "with space");
+    for key_format in [CacheKeyFormat::UrlEncoded, CacheKeyFormat::Bitcode] {
+        let backend = MemcachedBackend::builder()
+            .server(&server.address)
+            .key_format(key_format)
+            .build();
+        for key in [&long, &spaced] {
+            backend
+                .write(key, value(b"value".to_vec()), None)
+                .await
+                .unwrap();
+            assert_eq!(backend.read(key).await.unwrap().unwrap().data, b"value");
+        }
+    }
+
+    for key in server.items().keys() {
+        assert!(key.len() <= MAX_KEY_LEN);
+        assert!(key.bytes().all(|byte| byte.is_ascii_graphic()));
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_value_is_skipped() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder().server(&server.address).build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(vec![0; ITEM_SIZE_MAX]), None)
+        .await
+        .unwrap();
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+    assert!(server.items().is_empty());
+}
+
+#[tokio::test]
+async fn test_oversized_value_removes_previous_value() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder().server(&server.address).build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(b"previous".to_vec()), None)
+        .await
+        .unwrap();
+    backend
+        .write(&key, value(vec![0; ITEM_SIZE_MAX]), None)
+        .await
+        .unwrap();
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+    assert!(server.items().is_empty());
+}
+
+#[tokio::test]
+async fn test_server_rejecting_item_is_not_an_error() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder()
+        .server(&server.address)
+        .max_item_size(2 * ITEM_SIZE_MAX)
+        .build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(vec![0; ITEM_SIZE_MAX + 1]), None)
+        .await
+        .unwrap();
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_oversized_value_is_chunked() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder()
+        .server(&server.address)
+        .oversized(Oversized::Chunk)
+        .build();
+    let key = CacheKey::from_str("key", "1");
+    let data: Vec<u8> = (0..ITEM_SIZE_MAX * 2 + 17).map(|i| i as u8).collect();
+
+    backend
+        .write(&key, value(data.clone()), Some(Duration::from_secs(60)))
+        .await
+        .unwrap();
+
+    let items = server.items();
+    assert_eq!(items.len(), 4);
+    assert!(items.values().all(|item| item.exptime == 60));
+    assert_eq!(backend.read(&key).await.unwrap().unwrap().data, data);
+    assert_eq!(
+        backend.remove(&key).await.unwrap(),
+        DeleteStatus::Deleted(1)
+    );
+    assert!(server.items().is_empty());
+}
+
+#[tokio::test]
+async fn test_missing_chunk_is_a_miss() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder()
+        .server(&server.address)
+        .max_item_size(64)
+        .oversized(Oversized::Chunk)
+        .build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(vec![7; 1000]), None)
+        .await
+        .unwrap();
+    let chunk = server
+        .items()
+        .into_iter()
+        .find(|(_, item)| item.flags == 0)
+        .map(|(key, _)| key)
+        .unwrap();
+    server.evict(&chunk);
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_unreadable_item_is_a_miss() {
+    let server = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder().server(&server.address).build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(b"payload".to_vec()), None)
+        .await
+        .unwrap();
+    let (stored, _) = server.items().into_iter().next().unwrap();
+    for data in [vec![0; 32], vec![0; 4]] {
+        server.insert(
+            &stored,
+            StoredItem {
+                flags: 0xff,
+                exptime: 0,
+                data,
+            },
+        );
+
+        assert!(backend.read(&key).await.unwrap().is_none());
+        assert!(server.items().is_empty());
+    }
+}
+
+#[tokio::test]
+async fn test_keys_are_spread_over_servers() {
+    let first = FakeMemcached::start().await;
+    let second = FakeMemcached::start().await;
+    let backend = MemcachedBackend::builder()
+        .servers([&first.address, &second.address])
+        .build();
+    let keys: Vec<_> = (0..64)
+        .map(|i| CacheKey::from_str("key", &i.to_string()))
+        .collect();
+
+    for key in &keys {
+        backend
+            .write(key, value(b"value".to_vec()), None)
+            .await
+            .unwrap();
+    }
+
+    assert!(!first.items().is_empty());
+    assert!(!second.items().is_empty());
+    assert_eq!(first.items().len() + second.items().len(), keys.len());
+    for key in &keys {
+        assert!(backend.read(key).await.unwrap().is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_unreachable_server_is_an_error() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    drop(listener);
+    let backend = MemcachedBackend::builder()
+        .server(address)
+        .connection_timeout(Duration::from_millis(100))
+        .build();
+
+    assert!(matches!(
+        backend.read(&CacheKey::from_str("key", "1")).await,
+        Err(BackendError::ConnectionError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_failover_to_reachable_server() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed = listener.local_addr().unwrap().to_string();
+    drop(listener);
+    let server = FakeMemcached::start().await;
+    let backend = FailoverBackend::builder()
+        .member(
+            "primary",
+            Arc::new(
+                MemcachedBackend::builder()
+                    .server(closed)
+                    .connection_timeout(Duration::from_millis(100))
+                    .build(),
+            ),
+        )
+        .member(
+            "secondary",
+            Arc::new(MemcachedBackend::builder().server(&server.address).build()),
+        )
+        .build()
+        .unwrap();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, value(b"payload".to_vec()), None)
+        .await
+        .unwrap();
+
+    assert_eq!(server.items().len(), 1);
+    assert_eq!(
+        backend.read(&key).await.unwrap().map(|value| value.data),
+        Some(b"payload".to_vec())
+    );
+}
@@ -0,0 +1,109 @@
+//! In-process fake memcached speaking the subset of the text protocol used by the backend.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream},
+    net::{TcpListener, TcpStream},
+};
+
+/// Default memcached `item_size_max`.
+pub const ITEM_SIZE_MAX: usize = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct StoredItem {
+    pub flags: u32,
+    pub exptime: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Default)]
+pub struct FakeMemcached {
+    pub address: String,
+    items: Arc<Mutex<HashMap<String, StoredItem>>>,
+}
+
+impl FakeMemcached {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Self {
+            address: listener.local_addr().unwrap().to_string(),
+            items: Default::default(),
+        };
+        let items = server.items.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, items.clone()));
+            }
+        });
+        server
+    }
+
+    pub fn items(&self) -> HashMap<String, StoredItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn insert(&self, key: &str, item: StoredItem) {
+        self.items.lock().unwrap().insert(key.to_string(), item);
+    }
+
+    pub fn evict(&self, key: &str) {
+        self.items.lock().unwrap().remove(key);
+    }
+}
+
+async fn serve(stream: TcpStream, items: Arc<Mutex<HashMap<String, StoredItem>>>) {
+    let mut stream = BufStream::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+        let response = match fields.as_slice() {
+            ["get", keys @ ..] => {
+                let mut response = Vec::new();
+                let items = items.lock().unwrap();
+                for key in keys {
+                    if let Some(item) = items.get(*key) {
+                        response.extend_from_slice(
+                            format!("VALUE {key} {} {}\r\n", item.flags, item.data.len())
+                                .as_bytes(),
+                        );
+                        response.extend_from_slice(&item.data);
+                        response.extend_from_slice(b"\r\n");
+                    }
+                }
+                response.extend_from_slice(b"END\r\n");
+                response
+            }
+            ["set", key, flags, exptime, length] => {
+                let length: usize = length.parse().unwrap();
+                let mut data = vec![0; length + 2];
+                stream.read_exact(&mut data).await.unwrap();
+                data.truncate(length);
+                if length > ITEM_SIZE_MAX {
+                    b"SERVER_ERROR object too large for cache\r\n".to_vec()
+                } else {
+                    let item = StoredItem {
+                        flags: flags.parse().unwrap(),
+                        exptime: exptime.parse().unwrap(),
+                        data,
+                    };
+                    items.lock().unwrap().insert(key.to_string(), item);
+                    b"STORED\r\n".to_vec()
+                }
+            }
+            ["delete", key] => match items.lock().unwrap().remove(*key) {
+                Some(_) => b"DELETED\r\n".to_vec(),
+                None => b"NOT_FOUND\r\n".to_vec(),
+            },
+            _ => b"ERROR\r\n".to_vec(),
+        };
+        stream.write_all(&response).await.unwrap();
+        stream.flush().await.unwrap();
+    }
+}
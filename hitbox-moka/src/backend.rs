@@ -1,30 +1,74 @@
 use async_trait::async_trait;
 use chrono::Utc;
+use futures::StreamExt;
 use hitbox::{CacheKey, CacheValue};
 use hitbox_backend::Backend;
 use hitbox_backend::serializer::{Format, JsonFormat};
 use hitbox_backend::{
-    BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    BackendResult, CacheKeyFormat, Compressor, DeleteStatus, Invalidation, InvalidationStream,
+    PassthroughCompressor, SharedBus, entry_ttl,
 };
 use moka::{Expiry, future::Cache};
 use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
 
 type Raw = Vec<u8>;
 
+/// Delay before subscribing again after the invalidation subscription was lost.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Cached value together with its lifetime, computed when it was written.
+///
+/// The layout is internal, entries are only reachable through [`MokaBackend`].
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub(crate) value: CacheValue<Raw>,
+    pub(crate) ttl: Option<Duration>,
+}
+
+/// Reason an entry left the cache, reported to the eviction listener.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EvictionCause {
+    /// Evicted to keep the cache within its capacity.
+    Size,
+    /// Expired by ttl, value expiration or time-to-idle.
+    Expired,
+    /// Removed by [`Backend::remove`] or an invalidation.
+    Explicit,
+}
+
+/// Approximate size of an entry in bytes, used as its weight with `max_bytes`.
+pub(crate) fn weight(key: &CacheKey, entry: &Entry) -> u32 {
+    let key_size: usize = key.prefix().len()
+        + key
+            .parts()
+            .map(|part| part.key().len() + part.value().as_ref().map_or(0, String::len))
+            .sum::<usize>();
+    u32::try_from(key_size + entry.value.data.len()).unwrap_or(u32::MAX)
+}
+
+/// Per-entry expiration, recomputed on every write so an overwrite gets a new deadline.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Expiration;
 
-impl Expiry<CacheKey, CacheValue<Raw>> for Expiration {
+impl Expiry<CacheKey, Entry> for Expiration {
     fn expire_after_create(
         &self,
         _key: &CacheKey,
-        value: &CacheValue<Raw>,
+        entry: &Entry,
         _created_at: Instant,
     ) -> Option<Duration> {
-        value.expire.map(|expiration| {
-            let delta = expiration - Utc::now();
-            Duration::from_secs(delta.num_seconds() as u64)
-        })
+        entry.ttl
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &CacheKey,
+        entry: &Entry,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        entry.ttl
     }
 }
 
@@ -34,7 +78,7 @@ where
     S: Format,
     C: Compressor,
 {
-    pub cache: Cache<CacheKey, CacheValue<Raw>>,
+    pub cache: Cache<CacheKey, Entry>,
     pub key_format: CacheKeyFormat,
     pub serializer: S,
     pub compressor: C,
@@ -63,6 +107,45 @@ impl MokaBackend<JsonFormat, PassthroughCompressor> {
     }
 }
 
+impl<S, C> MokaBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Evict keys published on the invalidation bus by other processes.
+    ///
+    /// Subscribes to the bus and spawns a task evicting received keys until the
+    /// returned handle is aborted. If the subscription is lost, the task subscribes
+    /// again and evicts every entry, since events published meanwhile were missed.
+    pub async fn subscribe(&self, bus: SharedBus) -> BackendResult<JoinHandle<()>> {
+        let mut events = bus.subscribe().await?;
+        let cache = self.cache.clone();
+        Ok(tokio::spawn(async move {
+            loop {
+                evict(&cache, events).await;
+                tracing::warn!("Invalidation subscription lost, evicting all entries");
+                cache.invalidate_all();
+                events = loop {
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    match bus.subscribe().await {
+                        Ok(events) => break events,
+                        Err(error) => tracing::warn!("Invalidation subscription failed: {error}"),
+                    }
+                };
+            }
+        }))
+    }
+}
+
+async fn evict(cache: &Cache<CacheKey, Entry>, mut events: InvalidationStream) {
+    while let Some(invalidation) = events.next().await {
+        match invalidation {
+            Invalidation::Key(key) => cache.invalidate(&key).await,
+            Invalidation::All => cache.invalidate_all(),
+        }
+    }
+}
+
 #[async_trait]
 impl<S, C> Backend for MokaBackend<S, C>
 where
@@ -70,16 +153,19 @@ where
     C: Compressor + Send + Sync,
 {
     async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
-        self.cache.get(key).await.map(Ok).transpose()
+        Ok(self.cache.get(key).await.map(|entry| entry.value))
     }
 
     async fn write(
         &self,
         key: &CacheKey,
         value: CacheValue<Raw>,
-        _ttl: Option<Duration>,
+        ttl: Option<Duration>,
     ) -> BackendResult<()> {
-        self.cache.insert(key.clone(), value).await;
+        let Some(ttl) = entry_ttl(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+        self.cache.insert(key.clone(), Entry { value, ttl }).await;
         Ok(())
     }
 
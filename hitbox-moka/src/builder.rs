@@ -1,15 +1,17 @@
-use crate::backend::{Expiration, MokaBackend};
-use hitbox::{CacheKey, CacheValue};
-use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use crate::backend::{Entry, EvictionCause, Expiration, MokaBackend, weight};
+use hitbox::CacheKey;
+use hitbox_backend::serializer::{Format, JsonFormat};
 use hitbox_backend::{CacheKeyFormat, Compressor, PassthroughCompressor};
 use moka::future::{Cache, CacheBuilder};
+use moka::notification::RemovalCause;
+use std::time::Duration;
 
 pub struct MokaBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
 where
     S: Format,
     C: Compressor,
 {
-    builder: CacheBuilder<CacheKey, CacheValue<Raw>, Cache<CacheKey, CacheValue<Raw>>>,
+    builder: CacheBuilder<CacheKey, Entry, Cache<CacheKey, Entry>>,
     key_format: CacheKeyFormat,
     serializer: S,
     compressor: C,
@@ -32,6 +34,46 @@ where
     S: Format,
     C: Compressor,
 {
+    /// Bound the cache by the approximate size of entries in bytes instead of
+    /// their number, replacing the capacity passed to the builder.
+    ///
+    /// An entry weighs the length of its value data plus the prefix and parts
+    /// of its key.
+    pub fn max_bytes(self, bytes: u64) -> Self {
+        Self {
+            builder: self.builder.max_capacity(bytes).weigher(weight),
+            ..self
+        }
+    }
+
+    /// Expire entries not read or written for `duration`, on top of their ttl.
+    pub fn time_to_idle(self, duration: Duration) -> Self {
+        Self {
+            builder: self.builder.time_to_idle(duration),
+            ..self
+        }
+    }
+
+    /// Set listener notified with every key leaving the cache.
+    ///
+    /// Intended for metrics, e.g. counting entries evicted for lack of space.
+    /// Entries replaced by a write are not reported.
+    pub fn eviction_listener<F>(self, listener: F) -> Self
+    where
+        F: Fn(&CacheKey, EvictionCause) + Send + Sync + 'static,
+    {
+        let builder = self.builder.eviction_listener(move |key, _, cause| {
+            let cause = match cause {
+                RemovalCause::Size => EvictionCause::Size,
+                RemovalCause::Expired => EvictionCause::Expired,
+                RemovalCause::Explicit => EvictionCause::Explicit,
+                RemovalCause::Replaced => return,
+            };
+            listener(&key, cause);
+        });
+        Self { builder, ..self }
+    }
+
     pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
         self.key_format = format;
         self
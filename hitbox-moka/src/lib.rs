@@ -1,5 +1,5 @@
 mod backend;
 mod builder;
 
-pub use backend::MokaBackend;
+pub use backend::{Entry, EvictionCause, MokaBackend};
 pub use builder::MokaBackendBuilder;
@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::Backend;
+use hitbox_backend_tests::{Conformance, SystemClock};
+use hitbox_moka::{EvictionCause, MokaBackend};
+
+fn value(data: &[u8]) -> CacheValue<Vec<u8>> {
+    CacheValue::new(data.to_vec(), None, None)
+}
+
+#[tokio::test]
+async fn test_conformance() {
+    let backend = MokaBackend::builder(10_000).build();
+
+    Conformance::new(&backend, SystemClock)
+        .ttl(Duration::from_millis(200))
+        .run()
+        .await;
+}
+
+#[tokio::test]
+async fn test_sub_second_expire_and_overwrite_deadline() {
+    let backend = MokaBackend::builder(100).build();
+    let key = CacheKey::from_str("key", "1");
+    let expire = Utc::now() + chrono::Duration::milliseconds(300);
+
+    backend
+        .write(
+            &key,
+            CacheValue::new(b"value".to_vec(), Some(expire), None),
+            None,
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(backend.read(&key).await.unwrap().is_some());
+    backend
+        .write(&key, value(b"value"), Some(Duration::from_millis(100)))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_time_to_idle() {
+    let backend = MokaBackend::builder(100)
+        .time_to_idle(Duration::from_millis(200))
+        .build();
+    let key = CacheKey::from_str("key", "1");
+
+    backend.write(&key, value(b"value"), None).await.unwrap();
+    for _ in 0..3 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(backend.read(&key).await.unwrap().is_some());
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_max_bytes_and_eviction_listener() {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let backend = MokaBackend::builder(1)
+        .max_bytes(4096)
+        .eviction_listener({
+            let evicted = evicted.clone();
+            move |key: &CacheKey, cause| evicted.lock().unwrap().push((key.clone(), cause))
+        })
+        .build();
+    let key = |id: usize| CacheKey::from_str("key", &id.to_string());
+
+    for id in 0..4 {
+        backend
+            .write(&key(id), value(&[0; 100]), None)
+            .await
+            .unwrap();
+    }
+    backend.cache.run_pending_tasks().await;
+    assert_eq!(backend.cache.entry_count(), 4);
+
+    backend
+        .write(&key(4), value(&[0; 8192]), None)
+        .await
+        .unwrap();
+    backend
+        .write(&key(0), value(&[1; 100]), None)
+        .await
+        .unwrap();
+    backend
+        .write(&key(5), value(b"value"), Some(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    backend.remove(&key(1)).await.unwrap();
+    // Expired entries are dropped by moka's timer wheel with a granularity of about a second.
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        backend.cache.run_pending_tasks().await;
+        if evicted.lock().unwrap().len() >= 3 {
+            break;
+        }
+    }
+
+    let evicted = evicted.lock().unwrap();
+    assert!(evicted.contains(&(key(4), EvictionCause::Size)));
+    assert!(evicted.contains(&(key(5), EvictionCause::Expired)));
+    assert!(evicted.contains(&(key(1), EvictionCause::Explicit)));
+    assert!(!evicted.iter().any(|(evicted, _)| *evicted == key(0)));
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::{
+    Backend, DeleteStatus, InvalidatingBackend, Invalidation, InvalidationBus, LocalBus, SharedBus,
+};
+use hitbox_moka::MokaBackend;
+
+async fn evicted(backend: &MokaBackend, key: &CacheKey) -> bool {
+    for _ in 0..100 {
+        if backend.read(key).await.unwrap().is_none() {
+            return true;
+        }
+        tokio::task::yield_now().await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_remove_evicts_key_in_every_process() {
+    let bus: SharedBus = Arc::new(LocalBus::default());
+    let first = MokaBackend::builder(100).build();
+    let second = MokaBackend::builder(100).build();
+    let subscriptions = [
+        first.subscribe(bus.clone()).await.unwrap(),
+        second.subscribe(bus.clone()).await.unwrap(),
+    ];
+    let key = CacheKey::from_str("key", "1");
+    let other = CacheKey::from_str("key", "2");
+    for backend in [&first, &second] {
+        for key in [&key, &other] {
+            backend
+                .write(key, CacheValue::new(b"value".to_vec(), None, None), None)
+                .await
+                .unwrap();
+        }
+    }
+
+    let publisher = InvalidatingBackend::new(first.clone(), bus);
+    let status = publisher.remove(&key).await.unwrap();
+
+    assert_eq!(status, DeleteStatus::Deleted(1));
+    assert!(evicted(&second, &key).await);
+    assert!(second.read(&other).await.unwrap().is_some());
+    subscriptions.iter().for_each(|handle| handle.abort());
+}
+
+#[tokio::test]
+async fn test_clear_evicts_every_process() {
+    let bus: SharedBus = Arc::new(LocalBus::default());
+    let first = MokaBackend::builder(100).build();
+    let second = MokaBackend::builder(100).build();
+    let subscription = second.subscribe(bus.clone()).await.unwrap();
+    let key = CacheKey::from_str("key", "1");
+    second
+        .write(&key, CacheValue::new(b"value".to_vec(), None, None), None)
+        .await
+        .unwrap();
+
+    InvalidatingBackend::new(first, bus).clear().await.unwrap();
+
+    assert!(evicted(&second, &key).await);
+    subscription.abort();
+}
+
+#[tokio::test]
+async fn test_invalidate_all() {
+    let bus = Arc::new(LocalBus::default());
+    let backend = MokaBackend::builder(100).build();
+    let subscription = backend.subscribe(bus.clone()).await.unwrap();
+    let key = CacheKey::from_str("key", "1");
+    backend
+        .write(&key, CacheValue::new(b"value".to_vec(), None, None), None)
+        .await
+        .unwrap();
+
+    bus.publish(Invalidation::All).await.unwrap();
+
+    assert!(evicted(&backend, &key).await);
+    subscription.abort();
+}
@@ -0,0 +1,503 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod, Runtime};
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use hitbox_backend::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, LockStatus,
+    PassthroughCompressor, entry_deadline,
+};
+use tokio::sync::{Mutex, OnceCell};
+use tokio_postgres::{Client, NoTls};
+
+use crate::error::Error;
+
+/// Name of the table holding cache entries.
+pub const TABLE_NAME: &str = "hitbox_cache";
+
+/// Default period of the expired rows sweeper.
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default number of pooled connections.
+pub const DEFAULT_POOL_SIZE: usize = 16;
+
+/// Number of rows removed by one sweeper statement, keeps row locks short.
+const SWEEP_BATCH: i64 = 1000;
+
+/// `deadline` is the moment the row may be removed, derived from the write ttl.
+///
+/// Instances starting at the same time serialize schema creation on an advisory
+/// lock, since `IF NOT EXISTS` doesn't protect concurrent `CREATE TABLE`.
+const SCHEMA: &str = "
+BEGIN;
+SELECT pg_advisory_xact_lock(hashtext('hitbox_cache'));
+CREATE UNLOGGED TABLE IF NOT EXISTS hitbox_cache (
+    key bytea PRIMARY KEY,
+    prefix text NOT NULL,
+    data bytea NOT NULL,
+    expire timestamptz,
+    stale timestamptz,
+    deadline timestamptz
+);
+CREATE INDEX IF NOT EXISTS hitbox_cache_prefix ON hitbox_cache (prefix);
+CREATE INDEX IF NOT EXISTS hitbox_cache_deadline ON hitbox_cache (deadline) WHERE deadline IS NOT NULL;
+COMMIT;
+";
+
+/// Connection pool shared by all clones of the backend.
+struct Database {
+    pool: Pool,
+    initialized: OnceCell<()>,
+}
+
+impl Database {
+    /// Pooled connection, the table is created on first use.
+    async fn client(&self) -> Result<Object, Error> {
+        let client = self.pool.get().await?;
+        self.initialized
+            .get_or_try_init(|| async {
+                if let Err(error) = client.batch_execute(SCHEMA).await {
+                    // A failed statement leaves the transaction open and aborted, which
+                    // would break every later query of this connection once it returns
+                    // to the pool. A failed rollback means a closed connection, which
+                    // the pool discards on recycling.
+                    let _ = client.batch_execute("ROLLBACK").await;
+                    return Err(error.into());
+                }
+                Ok::<_, Error>(())
+            })
+            .await?;
+        Ok(client)
+    }
+
+    async fn sweep(&self) -> Result<u64, Error> {
+        let client = self.client().await?;
+        let statement = client
+            .prepare_cached(
+                "DELETE FROM hitbox_cache WHERE key IN (
+                    SELECT key FROM hitbox_cache WHERE deadline <= $1
+                    LIMIT $2 FOR UPDATE SKIP LOCKED
+                )",
+            )
+            .await?;
+        let now = Utc::now();
+        let mut removed = 0;
+        loop {
+            let batch = client.execute(&statement, &[&now, &SWEEP_BATCH]).await?;
+            removed += batch;
+            if batch < SWEEP_BATCH as u64 {
+                return Ok(removed);
+            }
+        }
+    }
+}
+
+/// Session holding advisory locks.
+///
+/// Advisory locks belong to a session, so they are taken on a dedicated
+/// connection rather than a pooled one, and are released by Postgres if the
+/// connection is lost or the backend is dropped.
+struct Locks {
+    config: tokio_postgres::Config,
+    session: Option<Client>,
+    held: HashSet<i64>,
+}
+
+impl Locks {
+    /// (Re)connect the session, locks of a lost session are gone.
+    async fn connect(&mut self) -> Result<(), Error> {
+        if self.session.as_ref().is_none_or(Client::is_closed) {
+            self.held.clear();
+            let (client, connection) = self.config.connect(NoTls).await?;
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    tracing::warn!("Postgres lock session failed: {err}");
+                }
+            });
+            self.session = Some(client);
+        }
+        Ok(())
+    }
+}
+
+/// Advisory lock id of a serialized cache key.
+fn lock_id(key: &[u8]) -> i64 {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(TABLE_NAME.as_bytes());
+    hasher.update(key);
+    let hash = hasher.finalize();
+    i64::from_le_bytes(hash.as_bytes()[..8].try_into().expect("8 bytes"))
+}
+
+/// PostgreSQL cache backend based on tokio-postgres and deadpool-postgres crates.
+#[derive(Clone)]
+pub struct PostgresBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    database: Arc<Database>,
+    locks: Arc<Mutex<Locks>>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl PostgresBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new PostgresBackend builder with default settings.
+    pub fn builder() -> PostgresBackendBuilder<JsonFormat, PassthroughCompressor> {
+        PostgresBackendBuilder::default()
+    }
+}
+
+impl<S, C> PostgresBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Create the cache table if it doesn't exist yet.
+    ///
+    /// Other methods call it on first use, calling it upfront surfaces connection
+    /// and permission errors early.
+    pub async fn init(&self) -> Result<(), Error> {
+        self.database.client().await.map(drop)
+    }
+
+    /// Remove expired rows now, returning the number of removed rows.
+    ///
+    /// The background sweeper calls it periodically.
+    pub async fn sweep(&self) -> Result<u64, Error> {
+        self.database.sweep().await
+    }
+
+    /// Remove all entries, returning the number of removed rows.
+    pub async fn clear(&self) -> Result<u64, Error> {
+        let client = self.database.client().await?;
+        Ok(client.execute("DELETE FROM hitbox_cache", &[]).await?)
+    }
+
+    /// Remove all entries with the given [`CacheKey::prefix`], returning the number of removed rows.
+    pub async fn remove_prefix(&self, prefix: &str) -> Result<u64, Error> {
+        let client = self.database.client().await?;
+        let statement = client
+            .prepare_cached("DELETE FROM hitbox_cache WHERE prefix = $1")
+            .await?;
+        Ok(client.execute(&statement, &[&prefix]).await?)
+    }
+
+    /// Number of stored entries with the given [`CacheKey::prefix`], expired ones included.
+    pub async fn count_prefix(&self, prefix: &str) -> Result<u64, Error> {
+        let client = self.database.client().await?;
+        let statement = client
+            .prepare_cached("SELECT count(*) FROM hitbox_cache WHERE prefix = $1")
+            .await?;
+        let count: i64 = client.query_one(&statement, &[&prefix]).await?.get(0);
+        Ok(count as u64)
+    }
+
+    /// Try to take the advisory lock of `key` without waiting.
+    ///
+    /// The lock is held by this backend and its clones until [`unlock`](Self::unlock),
+    /// so a key locked by the backend itself is reported as [`LockStatus::Locked`].
+    pub async fn lock(&self, key: &CacheKey) -> BackendResult<LockStatus> {
+        let id = lock_id(&self.key_format.serialize(key)?);
+        let mut locks = self.locks.lock().await;
+        locks.connect().await?;
+        if locks.held.contains(&id) {
+            return Ok(LockStatus::Locked);
+        }
+        let session = locks.session.as_ref().expect("session is established");
+        let acquired: bool = session
+            .query_one("SELECT pg_try_advisory_lock($1)", &[&id])
+            .await
+            .map_err(Error::from)?
+            .get(0);
+        if !acquired {
+            return Ok(LockStatus::Locked);
+        }
+        locks.held.insert(id);
+        Ok(LockStatus::Acquired)
+    }
+
+    /// Release the advisory lock of `key`, returning whether it was held.
+    pub async fn unlock(&self, key: &CacheKey) -> BackendResult<bool> {
+        let id = lock_id(&self.key_format.serialize(key)?);
+        let mut locks = self.locks.lock().await;
+        if !locks.held.remove(&id) {
+            return Ok(false);
+        }
+        let Some(session) = locks
+            .session
+            .as_ref()
+            .filter(|session| !session.is_closed())
+        else {
+            // Locks were released together with the lost session.
+            return Ok(false);
+        };
+        let released: bool = session
+            .query_one("SELECT pg_advisory_unlock($1)", &[&id])
+            .await
+            .map_err(Error::from)?
+            .get(0);
+        Ok(released)
+    }
+}
+
+impl<S, C> std::fmt::Debug for PostgresBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresBackend")
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct PostgresBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    connection_string: String,
+    pool_size: usize,
+    sweep_interval: Duration,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for PostgresBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            connection_string: "postgres://localhost/postgres".to_owned(),
+            pool_size: DEFAULT_POOL_SIZE,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> PostgresBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Set connection string, either a URL or `key=value` pairs as accepted by libpq.
+    pub fn connection_string(mut self, connection_string: impl Into<String>) -> Self {
+        self.connection_string = connection_string.into();
+        self
+    }
+
+    /// Set maximum number of pooled connections, [`DEFAULT_POOL_SIZE`] by default.
+    pub fn pool_size(mut self, size: usize) -> Self {
+        self.pool_size = size;
+        self
+    }
+
+    /// Set period of the expired rows sweeper, [`DEFAULT_SWEEP_INTERVAL`] by default.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
+    pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
+        self.key_format = format;
+        self
+    }
+
+    pub fn value_format<NewS>(self, serializer: NewS) -> PostgresBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        PostgresBackendBuilder {
+            connection_string: self.connection_string,
+            pool_size: self.pool_size,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    pub fn compressor<NewC>(self, compressor: NewC) -> PostgresBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        PostgresBackendBuilder {
+            connection_string: self.connection_string,
+            pool_size: self.pool_size,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create the connection pool and start the sweeper.
+    ///
+    /// Connections are established lazily, see [`PostgresBackend::init`]. The sweeper
+    /// runs on the current Tokio runtime until the backend and all its clones are
+    /// dropped; without a runtime expired rows are only skipped on read.
+    pub fn build(self) -> Result<PostgresBackend<S, C>, Error> {
+        let config: tokio_postgres::Config = self.connection_string.parse()?;
+        let manager = Manager::from_config(
+            config.clone(),
+            NoTls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Fast,
+            },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(self.pool_size)
+            .runtime(Runtime::Tokio1)
+            .build()?;
+        let database = Arc::new(Database {
+            pool,
+            initialized: OnceCell::new(),
+        });
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(sweeper(Arc::downgrade(&database), self.sweep_interval));
+            }
+            Err(_) => tracing::warn!("No Tokio runtime, Postgres cache sweeper is not started"),
+        }
+
+        Ok(PostgresBackend {
+            database,
+            locks: Arc::new(Mutex::new(Locks {
+                config,
+                session: None,
+                held: HashSet::new(),
+            })),
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+async fn sweeper(database: Weak<Database>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(database) = database.upgrade() else {
+            break;
+        };
+        match database.sweep().await {
+            Ok(removed) => tracing::trace!("Removed {removed} expired cache rows"),
+            Err(err) => tracing::warn!("Expired cache rows sweep failed: {err}"),
+        }
+    }
+}
+
+#[async_trait]
+impl<S, C> Backend for PostgresBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let key = self.key_format.serialize(key)?;
+        let client = self.database.client().await?;
+        let statement = client
+            .prepare_cached(
+                "SELECT data, expire, stale FROM hitbox_cache
+                 WHERE key = $1 AND (deadline IS NULL OR deadline > $2)",
+            )
+            .await
+            .map_err(Error::from)?;
+        let row = client
+            .query_opt(&statement, &[&key, &Utc::now()])
+            .await
+            .map_err(Error::from)?;
+        Ok(row.map(|row| {
+            let expire: Option<DateTime<Utc>> = row.get(1);
+            let stale: Option<DateTime<Utc>> = row.get(2);
+            CacheValue::new(row.get(0), expire, stale)
+        }))
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let Some(deadline) = entry_deadline(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+
+        let prefix = key.prefix();
+        let key = self.key_format.serialize(key)?;
+        let client = self.database.client().await?;
+        let statement = client
+            .prepare_cached(
+                "INSERT INTO hitbox_cache (key, prefix, data, expire, stale, deadline)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (key) DO UPDATE SET
+                     prefix = EXCLUDED.prefix,
+                     data = EXCLUDED.data,
+                     expire = EXCLUDED.expire,
+                     stale = EXCLUDED.stale,
+                     deadline = EXCLUDED.deadline",
+            )
+            .await
+            .map_err(Error::from)?;
+        client
+            .execute(
+                &statement,
+                &[
+                    &key,
+                    &prefix,
+                    &value.data,
+                    &value.expire,
+                    &value.stale,
+                    &deadline,
+                ],
+            )
+            .await
+            .map_err(Error::from)?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let key = self.key_format.serialize(key)?;
+        let client = self.database.client().await?;
+        let statement = client
+            .prepare_cached("DELETE FROM hitbox_cache WHERE key = $1")
+            .await
+            .map_err(Error::from)?;
+        match client
+            .execute(&statement, &[&key])
+            .await
+            .map_err(Error::from)?
+        {
+            0 => Ok(DeleteStatus::Missing),
+            deleted => Ok(DeleteStatus::Deleted(deleted as u32)),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
@@ -0,0 +1,37 @@
+use deadpool_postgres::PoolError;
+use hitbox_backend::BackendError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Postgres backend error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+    #[error("Postgres backend error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+    #[error("Postgres backend error: {0}")]
+    Build(#[from] deadpool_postgres::BuildError),
+}
+
+impl Error {
+    /// Database can't be reached, as opposed to rejecting a query.
+    fn is_connection(&self) -> bool {
+        match self {
+            Error::Postgres(error) => {
+                error.is_closed()
+                    || std::error::Error::source(error)
+                        .is_some_and(|source| source.is::<std::io::Error>())
+            }
+            Error::Pool(PoolError::Timeout(_) | PoolError::Closed | PoolError::Backend(_)) => true,
+            Error::Pool(_) | Error::Build(_) => false,
+        }
+    }
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        if error.is_connection() {
+            Self::ConnectionError(Box::new(error))
+        } else {
+            Self::InternalError(Box::new(error))
+        }
+    }
+}
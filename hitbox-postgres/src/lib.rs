@@ -0,0 +1,13 @@
+//! hitbox [Backend] implementation for PostgreSQL.
+//!
+//! Entries live in an `UNLOGGED` table: writes skip the write-ahead log, which
+//! makes them cheaper, and the table is emptied after a crash, which is fine for
+//! a cache. Expired rows are removed by a background sweeper, and advisory locks
+//! back [`PostgresBackend::lock`].
+//!
+//! [Backend]: hitbox_backend::Backend
+mod backend;
+pub mod error;
+
+pub use crate::backend::{PostgresBackend, PostgresBackendBuilder, TABLE_NAME};
+pub use crate::error::Error;
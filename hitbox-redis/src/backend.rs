@@ -1,23 +1,30 @@
 //! Redis backend actor implementation.
-use crate::error::Error;
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    connection::{Connection, ConnectionOptions, ConnectionPool, RedisClient, Retry, Topology},
+    envelope,
+    error::Error,
+    invalidation::{DEFAULT_CHANNEL, RedisInvalidationBus},
+    key::HashTag,
+};
 use async_trait::async_trait;
 use chrono::Utc;
 use hitbox::{CacheKey, CacheValue};
 use hitbox_backend::{
     Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
-    PassthroughCompressor,
+    PassthroughCompressor, entry_ttl,
     serializer::{Format, JsonFormat, Raw},
 };
-use redis::{Client, aio::ConnectionManager};
 use tokio::sync::OnceCell;
 use tracing::trace;
 
 /// Redis cache backend based on redis-rs crate.
 ///
 /// This struct provides redis as storage [Backend] for hitbox.
-/// Its use one [MultiplexedConnection] for asynchronous network interaction.
+/// It talks to a single node, Redis Cluster or Sentinel managed master
+/// (see [`Topology`]) through one managed [`Connection`].
 ///
-/// [MultiplexedConnection]: redis::aio::MultiplexedConnection
 /// [Backend]: hitbox_backend::Backend
 #[derive(Clone)]
 pub struct RedisBackend<S = JsonFormat, C = PassthroughCompressor>
@@ -25,10 +32,11 @@ where
     S: Format,
     C: Compressor,
 {
-    client: Client,
-    connection: OnceCell<ConnectionManager>,
+    client: RedisClient,
+    connection: Arc<OnceCell<ConnectionPool>>,
     serializer: S,
     key_format: CacheKeyFormat,
+    hash_tag: HashTag,
     compressor: C,
 }
 
@@ -59,18 +67,42 @@ where
     S: Format,
     C: Compressor,
 {
-    /// Create lazy connection to redis via [`ConnectionManager`]
-    pub async fn connection(&self) -> Result<&ConnectionManager, BackendError> {
+    /// Create lazy connection to redis via managed [`Connection`]
+    ///
+    /// Connections are opened once and shared by all clones of the backend.
+    pub async fn connection(&self) -> Result<&Connection, BackendError> {
         trace!("Get connection manager");
-        let manager = self
+        let pool = self
             .connection
             .get_or_try_init(|| {
                 trace!("Initialize new redis connection manager");
-                self.client.get_connection_manager()
+                self.client.connect()
             })
             .await
             .map_err(Error::from)?;
-        Ok(manager)
+        Ok(pool.get())
+    }
+
+    /// Counters of the local cache summed over all connections.
+    ///
+    /// Returns `None` if [`ClientCache`] is disabled or no connection was opened yet.
+    ///
+    /// [`ClientCache`]: crate::connection::ClientCache
+    #[cfg(feature = "client-cache")]
+    pub fn client_cache_stats(&self) -> Option<crate::connection::ClientCacheStats> {
+        self.connection.get()?.client_cache_stats()
+    }
+
+    /// Invalidation bus on [`DEFAULT_CHANNEL`] of the Redis deployment this
+    /// backend talks to, connected with the same options.
+    ///
+    /// [`DEFAULT_CHANNEL`]: crate::invalidation::DEFAULT_CHANNEL
+    pub fn invalidation_bus(&self) -> RedisInvalidationBus {
+        RedisInvalidationBus::from_client(self.client.clone(), DEFAULT_CHANNEL)
+    }
+
+    fn storage_key(&self, key: &CacheKey) -> BackendResult<Vec<u8>> {
+        Ok(self.hash_tag.apply(key, self.key_format.serialize(key)?))
     }
 }
 
@@ -80,18 +112,22 @@ where
     S: Format,
     C: Compressor,
 {
-    connection_info: String,
+    topology: Topology,
+    options: ConnectionOptions,
     serializer: S,
     key_format: CacheKeyFormat,
+    hash_tag: HashTag,
     compressor: C,
 }
 
 impl Default for RedisBackendBuilder<JsonFormat, PassthroughCompressor> {
     fn default() -> Self {
         Self {
-            connection_info: "redis://127.0.0.1/".to_owned(),
+            topology: Topology::default(),
+            options: ConnectionOptions::default(),
             serializer: JsonFormat,
             key_format: CacheKeyFormat::default(),
+            hash_tag: HashTag::default(),
             compressor: PassthroughCompressor,
         }
     }
@@ -104,7 +140,90 @@ where
 {
     /// Set connection info (host, port, database, etc.) for RedisBackend actor.
     pub fn server(mut self, connection_info: String) -> Self {
-        self.connection_info = connection_info;
+        self.topology = Topology::Single(connection_info);
+        self
+    }
+
+    /// Connect to Redis Cluster through the list of initial nodes.
+    pub fn cluster(mut self, nodes: Vec<String>) -> Self {
+        self.topology = Topology::Cluster(nodes);
+        self
+    }
+
+    /// Connect to the master of a Sentinel monitored group.
+    pub fn sentinel(mut self, master: String, nodes: Vec<String>) -> Self {
+        self.topology = Topology::Sentinel { master, nodes };
+        self
+    }
+
+    /// Set Redis deployment topology.
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Set timeout of establishing a connection.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Set timeout of waiting for a command response.
+    pub fn response_timeout(mut self, timeout: Duration) -> Self {
+        self.options.response_timeout = Some(timeout);
+        self
+    }
+
+    /// Set reconnect policy.
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.options.retry = Some(retry);
+        self
+    }
+
+    /// Set number of multiplexed connections, one by default.
+    pub fn connections(mut self, connections: usize) -> Self {
+        self.options.connections = connections;
+        self
+    }
+
+    /// Set ACL username and password.
+    pub fn credentials(mut self, username: Option<String>, password: String) -> Self {
+        self.options.username = username;
+        self.options.password = Some(password);
+        self
+    }
+
+    /// Set database index.
+    pub fn database(mut self, database: i64) -> Self {
+        self.options.database = Some(database);
+        self
+    }
+
+    /// Connect to Redis nodes over TLS (rustls).
+    ///
+    /// `rediss://` connection strings enable TLS without this option.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, tls: bool) -> Self {
+        self.options.tls = tls;
+        self
+    }
+
+    /// Keep recently read values in a local cache invalidated by Redis.
+    #[cfg(feature = "client-cache")]
+    pub fn client_cache(mut self, cache: crate::connection::ClientCache) -> Self {
+        self.options.client_cache = Some(cache);
+        self
+    }
+
+    /// Set all connection options at once.
+    pub fn connection_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set Redis Cluster hash tag applied to storage keys.
+    pub fn hash_tag(mut self, hash_tag: HashTag) -> Self {
+        self.hash_tag = hash_tag;
         self
     }
 
@@ -114,9 +233,11 @@ where
         NewS: Format,
     {
         RedisBackendBuilder {
-            connection_info: self.connection_info,
+            topology: self.topology,
+            options: self.options,
             serializer,
             key_format: self.key_format,
+            hash_tag: self.hash_tag,
             compressor: self.compressor,
         }
     }
@@ -133,9 +254,11 @@ where
         NewC: Compressor,
     {
         RedisBackendBuilder {
-            connection_info: self.connection_info,
+            topology: self.topology,
+            options: self.options,
             serializer: self.serializer,
             key_format: self.key_format,
+            hash_tag: self.hash_tag,
             compressor,
         }
     }
@@ -143,10 +266,11 @@ where
     /// Create new instance of Redis backend with passed settings.
     pub fn build(self) -> Result<RedisBackend<S, C>, Error> {
         Ok(RedisBackend {
-            client: Client::open(self.connection_info)?,
-            connection: OnceCell::new(),
+            client: RedisClient::open(self.topology, self.options)?,
+            connection: Arc::default(),
             serializer: self.serializer,
             key_format: self.key_format,
+            hash_tag: self.hash_tag,
             compressor: self.compressor,
         })
     }
@@ -159,15 +283,31 @@ where
     C: Compressor + Send + Sync,
 {
     async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
-        let client = self.client.clone();
-        let cache_key = self.key_format.serialize(key)?;
-        let mut con = client.get_connection_manager().await.map_err(Error::from)?;
-        let result: Option<Vec<u8>> = redis::cmd("GET")
-            .arg(cache_key)
-            .query_async(&mut con)
-            .await
-            .map_err(Error::from)?;
-        Ok(result.map(|value| CacheValue::new(value, Some(Utc::now()), Some(Utc::now()))))
+        let mut con = self.connection().await?.clone();
+        let cache_key = self.storage_key(key)?;
+        let mut request = redis::cmd("GET");
+        request.arg(&cache_key);
+        // Connections without client cache ignore the flag.
+        #[cfg(feature = "client-cache")]
+        request.set_cache_config(redis::CommandCacheConfig::new());
+        let result: Option<Vec<u8>> = request.query_async(&mut con).await.map_err(Error::from)?;
+        let Some(value) = result else {
+            return Ok(None);
+        };
+        match envelope::decode(&value) {
+            Ok(value) => Ok(Some(value)),
+            // Values written before the envelope or by another client are
+            // dropped and read as a miss.
+            Err(error) => {
+                trace!("Dropping undecodable cached value: {error}");
+                let _: () = redis::cmd("DEL")
+                    .arg(&cache_key)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(Error::from)?;
+                Ok(None)
+            }
+        }
     }
 
     async fn write(
@@ -177,12 +317,18 @@ where
         ttl: Option<std::time::Duration>,
     ) -> BackendResult<()> {
         let mut con = self.connection().await?.clone();
-        let cache_key = self.key_format.serialize(key)?;
+        let cache_key = self.storage_key(key)?;
+
+        let Some(ttl) = entry_ttl(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
 
         let mut request = redis::cmd("SET");
-        request.arg(cache_key).arg(value.data);
+        request
+            .arg(cache_key)
+            .arg(envelope::encode(value).map_err(Error::from)?);
 
-        ttl.map(|ttl_duration| request.arg("EX").arg(ttl_duration.as_secs()));
+        ttl.map(|ttl| request.arg("PX").arg(ttl.as_millis().max(1) as u64));
 
         request
             .query_async(&mut con)
@@ -192,9 +338,8 @@ where
     }
 
     async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
-        let client = self.client.clone();
-        let cache_key = self.key_format.serialize(key)?;
-        let mut con = client.get_connection_manager().await.map_err(Error::from)?;
+        let mut con = self.connection().await?.clone();
+        let cache_key = self.storage_key(key)?;
 
         let deleted: i32 = redis::cmd("DEL")
             .arg(cache_key)
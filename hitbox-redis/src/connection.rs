@@ -0,0 +1,702 @@
+//! Connections to single node, Redis Cluster and Sentinel managed deployments.
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use redis::{
+    AsyncConnectionConfig, Client, Cmd, ErrorKind, IntoConnectionInfo, Pipeline,
+    RedisConnectionInfo, RedisFuture, RedisResult, Value,
+    aio::{
+        ConnectionLike, ConnectionManager, ConnectionManagerConfig, MultiplexedConnection, PubSub,
+    },
+    cluster::ClusterClient,
+    cluster_async::ClusterConnection,
+    sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType},
+};
+use tokio::sync::{Mutex, RwLock};
+
+/// Redis deployment the backend talks to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Topology {
+    /// Single Redis node, e.g. `redis://127.0.0.1/`.
+    Single(String),
+    /// Redis Cluster discovered through any of the initial nodes.
+    Cluster(Vec<String>),
+    /// Master of a Sentinel monitored group.
+    Sentinel {
+        /// Name of the monitored master.
+        master: String,
+        /// Addresses of the Sentinel nodes.
+        nodes: Vec<String>,
+    },
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Self::Single("redis://127.0.0.1/".to_owned())
+    }
+}
+
+/// Connection settings applied to every [`Topology`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// Timeout of establishing a connection.
+    pub connection_timeout: Option<Duration>,
+    /// Timeout of waiting for a command response.
+    pub response_timeout: Option<Duration>,
+    /// Reconnect policy, redis-rs defaults are used if not set.
+    pub retry: Option<Retry>,
+    /// Number of multiplexed connections used in round-robin order, at least one.
+    pub connections: usize,
+    /// ACL username.
+    pub username: Option<String>,
+    /// ACL or `requirepass` password.
+    pub password: Option<String>,
+    /// Database index. Redis Cluster supports only database 0.
+    pub database: Option<i64>,
+    /// Connect to Redis nodes over TLS, requires the `tls` feature.
+    pub tls: bool,
+    /// Keep recently read values in a local cache coherent via `CLIENT TRACKING`,
+    /// requires the `client-cache` feature.
+    pub client_cache: Option<ClientCache>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            connection_timeout: None,
+            response_timeout: None,
+            retry: None,
+            connections: 1,
+            username: None,
+            password: None,
+            database: None,
+            tls: false,
+            client_cache: None,
+        }
+    }
+}
+
+impl ConnectionOptions {
+    fn redis_connection_info(&self, mut info: RedisConnectionInfo) -> RedisConnectionInfo {
+        if let Some(database) = self.database {
+            info.db = database;
+        }
+        if let Some(username) = &self.username {
+            info.username = Some(username.clone());
+        }
+        if let Some(password) = &self.password {
+            info.password = Some(password.clone());
+        }
+        #[cfg(feature = "client-cache")]
+        if self.client_cache.is_some() {
+            info.protocol = redis::ProtocolVersion::RESP3;
+        }
+        info
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_mode(&self) -> Option<redis::TlsMode> {
+        self.tls.then_some(redis::TlsMode::Secure)
+    }
+
+    fn async_connection_config(&self) -> AsyncConnectionConfig {
+        let mut config = AsyncConnectionConfig::new();
+        if let Some(timeout) = self.connection_timeout {
+            config = config.set_connection_timeout(timeout);
+        }
+        if let Some(timeout) = self.response_timeout {
+            config = config.set_response_timeout(timeout);
+        }
+        #[cfg(feature = "client-cache")]
+        if let Some(cache) = &self.client_cache {
+            config = config.set_cache_config(cache.config());
+        }
+        config
+    }
+
+    fn connection_manager_config(&self) -> ConnectionManagerConfig {
+        let mut config = ConnectionManagerConfig::new();
+        if let Some(timeout) = self.connection_timeout {
+            config = config.set_connection_timeout(timeout);
+        }
+        if let Some(timeout) = self.response_timeout {
+            config = config.set_response_timeout(timeout);
+        }
+        if let Some(retry) = &self.retry {
+            config = config
+                .set_number_of_retries(retry.attempts)
+                .set_factor(retry.min_delay.as_millis() as u64)
+                .set_exponent_base(2)
+                .set_max_delay(retry.max_delay.as_millis() as u64);
+        }
+        #[cfg(feature = "client-cache")]
+        if let Some(cache) = &self.client_cache {
+            config = config.set_cache_config(cache.config());
+        }
+        config
+    }
+}
+
+/// Local cache of values read from Redis, kept coherent by the server.
+///
+/// Connections enable `CLIENT TRACKING` over RESP3 and Redis pushes an
+/// invalidation whenever a cached key changes, so local copies are evicted even
+/// if another process wrote the key. Requires Redis 6 or newer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCache {
+    /// Maximum number of keys cached by each connection.
+    pub capacity: std::num::NonZeroUsize,
+    /// Upper bound of time a value stays in the local cache.
+    ///
+    /// The key expiration time on the server is used if it is shorter.
+    pub ttl: Duration,
+}
+
+impl Default for ClientCache {
+    fn default() -> Self {
+        Self {
+            capacity: std::num::NonZeroUsize::new(10_000).expect("capacity is not zero"),
+            ttl: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+#[cfg(feature = "client-cache")]
+impl ClientCache {
+    fn config(&self) -> redis::caching::CacheConfig {
+        // Only reads marked by the backend are cached, see `RedisBackend::read`.
+        redis::caching::CacheConfig::new()
+            .set_mode(redis::caching::CacheMode::OptIn)
+            .set_size(self.capacity)
+            .set_default_client_ttl(self.ttl)
+    }
+}
+
+/// Counters of the local cache enabled by [`ClientCache`].
+#[cfg(feature = "client-cache")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClientCacheStats {
+    /// Reads served from the local cache.
+    pub hits: usize,
+    /// Reads sent to Redis.
+    pub misses: usize,
+    /// Local entries evicted by server invalidations or capacity limit.
+    pub invalidations: usize,
+}
+
+#[cfg(feature = "client-cache")]
+impl std::ops::Add for ClientCacheStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            hits: self.hits + other.hits,
+            misses: self.misses + other.misses,
+            invalidations: self.invalidations + other.invalidations,
+        }
+    }
+}
+
+#[cfg(feature = "client-cache")]
+impl From<redis::caching::CacheStatistics> for ClientCacheStats {
+    fn from(statistics: redis::caching::CacheStatistics) -> Self {
+        Self {
+            hits: statistics.hit,
+            misses: statistics.miss,
+            invalidations: statistics.invalidate,
+        }
+    }
+}
+
+/// Reconnect policy with exponentially growing delay between attempts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retry {
+    /// Number of reconnect attempts.
+    pub attempts: usize,
+    /// Delay before the first attempt, doubled on every next one.
+    pub min_delay: Duration,
+    /// Upper bound of the delay.
+    pub max_delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 6,
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Retry {
+    fn delay(&self, attempt: usize) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.try_into().unwrap_or(u32::MAX));
+        self.min_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RedisClient {
+    kind: ClientKind,
+    options: ConnectionOptions,
+}
+
+#[derive(Clone)]
+enum ClientKind {
+    Single(Client),
+    Cluster {
+        client: Box<ClusterClient>,
+        /// Clients of the initial nodes, used for pub/sub.
+        nodes: Vec<Client>,
+    },
+    Sentinel(Arc<Mutex<SentinelClient>>),
+}
+
+/// Client of a single node with connection options applied.
+fn node_client(
+    connection_info: impl IntoConnectionInfo,
+    options: &ConnectionOptions,
+) -> RedisResult<Client> {
+    let mut info = connection_info.into_connection_info()?;
+    info.redis = options.redis_connection_info(info.redis);
+    #[cfg(feature = "tls")]
+    if let (Some(_), redis::ConnectionAddr::Tcp(host, port)) = (options.tls_mode(), &info.addr) {
+        info.addr = redis::ConnectionAddr::TcpTls {
+            host: host.clone(),
+            port: *port,
+            insecure: false,
+            tls_params: None,
+        };
+    }
+    Client::open(info)
+}
+
+impl RedisClient {
+    pub(crate) fn open(topology: Topology, options: ConnectionOptions) -> RedisResult<Self> {
+        #[cfg(not(feature = "tls"))]
+        if options.tls {
+            return Err((
+                ErrorKind::InvalidClientConfig,
+                "TLS requires the `tls` feature of hitbox-redis",
+            )
+                .into());
+        }
+        #[cfg(not(feature = "client-cache"))]
+        if options.client_cache.is_some() {
+            return Err((
+                ErrorKind::InvalidClientConfig,
+                "client side caching requires the `client-cache` feature of hitbox-redis",
+            )
+                .into());
+        }
+        let kind = match topology {
+            Topology::Single(connection_info) => {
+                ClientKind::Single(node_client(connection_info, &options)?)
+            }
+            Topology::Cluster(nodes) => {
+                if options.database.is_some_and(|database| database != 0) {
+                    return Err((
+                        ErrorKind::InvalidClientConfig,
+                        "Redis Cluster supports only database 0",
+                    )
+                        .into());
+                }
+                let node_clients = nodes
+                    .iter()
+                    .map(|node| node_client(node.as_str(), &options))
+                    .collect::<RedisResult<_>>()?;
+                let mut builder = ClusterClient::builder(nodes);
+                if let Some(username) = &options.username {
+                    builder = builder.username(username.clone());
+                }
+                if let Some(password) = &options.password {
+                    builder = builder.password(password.clone());
+                }
+                if let Some(timeout) = options.connection_timeout {
+                    builder = builder.connection_timeout(timeout);
+                }
+                if let Some(timeout) = options.response_timeout {
+                    builder = builder.response_timeout(timeout);
+                }
+                if let Some(retry) = &options.retry {
+                    builder = builder
+                        .retries(retry.attempts.try_into().unwrap_or(u32::MAX))
+                        .min_retry_wait(retry.min_delay.as_millis() as u64)
+                        .max_retry_wait(retry.max_delay.as_millis() as u64)
+                        .retry_wait_formula(retry.min_delay.as_millis() as u64, 2);
+                }
+                #[cfg(feature = "tls")]
+                if let Some(tls) = options.tls_mode() {
+                    builder = builder.tls(tls);
+                }
+                #[cfg(feature = "client-cache")]
+                if let Some(cache) = &options.client_cache {
+                    builder = builder
+                        .use_protocol(redis::ProtocolVersion::RESP3)
+                        .cache_config(cache.config());
+                }
+                ClientKind::Cluster {
+                    client: Box::new(builder.build()?),
+                    nodes: node_clients,
+                }
+            }
+            Topology::Sentinel { master, nodes } => {
+                let node_connection_info = SentinelNodeConnectionInfo {
+                    #[cfg(feature = "tls")]
+                    tls_mode: options.tls_mode(),
+                    #[cfg(not(feature = "tls"))]
+                    tls_mode: None,
+                    redis_connection_info: Some(
+                        options.redis_connection_info(RedisConnectionInfo::default()),
+                    ),
+                };
+                let client = SentinelClient::build(
+                    nodes,
+                    master,
+                    Some(node_connection_info),
+                    SentinelServerType::Master,
+                )?;
+                ClientKind::Sentinel(Arc::new(Mutex::new(client)))
+            }
+        };
+        Ok(Self { kind, options })
+    }
+
+    /// Open all multiplexed connections of the pool.
+    pub(crate) async fn connect(&self) -> RedisResult<ConnectionPool> {
+        let mut connections = Vec::with_capacity(self.options.connections.max(1));
+        for _ in 0..self.options.connections.max(1) {
+            connections.push(self.open_connection().await?);
+        }
+        Ok(ConnectionPool {
+            connections: connections.into(),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Open pub/sub connection.
+    ///
+    /// Redis Cluster propagates messages to all nodes, so the first reachable
+    /// initial node is used. Sentinel connections go to the current master.
+    pub(crate) async fn pubsub(&self) -> RedisResult<PubSub> {
+        match &self.kind {
+            ClientKind::Single(client) => client.get_async_pubsub().await,
+            ClientKind::Cluster { nodes, .. } => {
+                let mut last_error = None;
+                for node in nodes {
+                    match node.get_async_pubsub().await {
+                        Ok(pubsub) => return Ok(pubsub),
+                        Err(error) => last_error = Some(error),
+                    }
+                }
+                Err(last_error.unwrap_or_else(|| {
+                    (ErrorKind::InvalidClientConfig, "no Redis Cluster nodes").into()
+                }))
+            }
+            ClientKind::Sentinel(client) => {
+                let master = client.lock().await.async_get_client().await?;
+                master.get_async_pubsub().await
+            }
+        }
+    }
+
+    pub(crate) async fn open_connection(&self) -> RedisResult<Connection> {
+        match &self.kind {
+            ClientKind::Single(client) => client
+                .get_connection_manager_with_config(self.options.connection_manager_config())
+                .await
+                .map(Connection::Single),
+            ClientKind::Cluster { client, .. } => {
+                client.get_async_connection().await.map(Connection::Cluster)
+            }
+            ClientKind::Sentinel(client) => {
+                let connection = SentinelConnection {
+                    client: client.clone(),
+                    master: Arc::default(),
+                    config: Arc::new(self.options.async_connection_config()),
+                    retry: self.options.retry.clone(),
+                    db: self
+                        .options
+                        .redis_connection_info(RedisConnectionInfo::default())
+                        .db,
+                };
+                connection.master().await?;
+                Ok(Connection::Sentinel(connection))
+            }
+        }
+    }
+}
+
+/// Fixed set of managed connections used in round-robin order.
+pub(crate) struct ConnectionPool {
+    connections: Box<[Connection]>,
+    next: AtomicUsize,
+}
+
+impl ConnectionPool {
+    pub(crate) fn get(&self) -> &Connection {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+        &self.connections[index]
+    }
+
+    #[cfg(feature = "client-cache")]
+    pub(crate) fn client_cache_stats(&self) -> Option<ClientCacheStats> {
+        self.connections
+            .iter()
+            .filter_map(Connection::client_cache_stats)
+            .reduce(std::ops::Add::add)
+    }
+}
+
+/// Managed connection shared by operations of [`RedisBackend`].
+///
+/// [`RedisBackend`]: crate::RedisBackend
+#[derive(Clone)]
+pub enum Connection {
+    /// Reconnecting connection to a single node.
+    Single(ConnectionManager),
+    /// Slot aware connection to Redis Cluster.
+    Cluster(ClusterConnection),
+    /// Connection to the current master of a Sentinel group.
+    Sentinel(SentinelConnection),
+}
+
+impl Connection {
+    /// Counters of the local cache, if [`ClientCache`] is enabled.
+    ///
+    /// Sentinel connections report counters since the current master was resolved.
+    #[cfg(feature = "client-cache")]
+    pub fn client_cache_stats(&self) -> Option<ClientCacheStats> {
+        let statistics = match self {
+            Self::Single(connection) => connection.get_cache_statistics(),
+            Self::Cluster(connection) => connection.get_cache_statistics(),
+            Self::Sentinel(connection) => connection
+                .master
+                .try_read()
+                .ok()?
+                .as_ref()?
+                .get_cache_statistics(),
+        };
+        statistics.map(ClientCacheStats::from)
+    }
+}
+
+impl ConnectionLike for Connection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Single(connection) => connection.req_packed_command(cmd),
+            Self::Cluster(connection) => connection.req_packed_command(cmd),
+            Self::Sentinel(connection) => Box::pin(async move {
+                let result = connection.master().await?.req_packed_command(cmd).await;
+                connection.check(result).await
+            }),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Single(connection) => connection.req_packed_commands(cmd, offset, count),
+            Self::Cluster(connection) => connection.req_packed_commands(cmd, offset, count),
+            Self::Sentinel(connection) => Box::pin(async move {
+                let result = connection
+                    .master()
+                    .await?
+                    .req_packed_commands(cmd, offset, count)
+                    .await;
+                connection.check(result).await
+            }),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single(connection) => connection.get_db(),
+            Self::Cluster(connection) => connection.get_db(),
+            Self::Sentinel(connection) => connection.db,
+        }
+    }
+}
+
+/// Connection to the master of a Sentinel group.
+///
+/// The master address is resolved through Sentinel on first use and resolved
+/// again after the connection drops or the node reports it became a replica.
+/// Resolution is retried according to [`Retry`] policy.
+#[derive(Clone)]
+pub struct SentinelConnection {
+    client: Arc<Mutex<SentinelClient>>,
+    master: Arc<RwLock<Option<MultiplexedConnection>>>,
+    config: Arc<AsyncConnectionConfig>,
+    retry: Option<Retry>,
+    /// Database selected on the master, see [`ConnectionOptions::database`].
+    db: i64,
+}
+
+impl SentinelConnection {
+    async fn master(&self) -> RedisResult<MultiplexedConnection> {
+        if let Some(connection) = self.master.read().await.as_ref() {
+            return Ok(connection.clone());
+        }
+        let mut master = self.master.write().await;
+        if let Some(connection) = master.as_ref() {
+            return Ok(connection.clone());
+        }
+        let connection = self.resolve().await?;
+        *master = Some(connection.clone());
+        Ok(connection)
+    }
+
+    async fn resolve(&self) -> RedisResult<MultiplexedConnection> {
+        let mut client = self.client.lock().await;
+        let attempts = self.retry.as_ref().map_or(0, |retry| retry.attempts);
+        let mut attempt = 0;
+        loop {
+            match client.get_async_connection_with_config(&self.config).await {
+                Err(error) if attempt < attempts => {
+                    if let Some(retry) = &self.retry {
+                        tokio::time::sleep(retry.delay(attempt)).await;
+                    }
+                    attempt += 1;
+                    tracing::debug!("Sentinel master resolution failed: {error}, retrying");
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn check<T>(&self, result: RedisResult<T>) -> RedisResult<T> {
+        if let Err(error) = &result
+            && (error.is_connection_dropped()
+                || error.is_io_error()
+                || error.kind() == ErrorKind::ReadOnly)
+        {
+            self.master.write().await.take();
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_is_bounded() {
+        let retry = Retry {
+            attempts: 10,
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+
+        assert_eq!(retry.delay(0), Duration::from_millis(100));
+        assert_eq!(retry.delay(1), Duration::from_millis(200));
+        assert_eq!(retry.delay(3), Duration::from_millis(800));
+        assert_eq!(retry.delay(4), Duration::from_secs(1));
+        assert_eq!(retry.delay(usize::MAX), Duration::from_secs(1));
+    }
+
+    #[cfg(not(feature = "client-cache"))]
+    #[test]
+    fn test_client_cache_requires_feature() {
+        let options = ConnectionOptions {
+            client_cache: Some(ClientCache::default()),
+            ..Default::default()
+        };
+
+        let result = RedisClient::open(Topology::default(), options);
+
+        assert!(matches!(
+            result.map(|_| ()).map_err(|error| error.kind()),
+            Err(ErrorKind::InvalidClientConfig)
+        ));
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn test_tls_requires_feature() {
+        let options = ConnectionOptions {
+            tls: true,
+            ..Default::default()
+        };
+
+        let result = RedisClient::open(Topology::default(), options);
+
+        assert!(matches!(
+            result.map(|_| ()).map_err(|error| error.kind()),
+            Err(ErrorKind::InvalidClientConfig)
+        ));
+    }
+
+    #[test]
+    fn test_cluster_rejects_database() {
+        let options = ConnectionOptions {
+            database: Some(1),
+            ..Default::default()
+        };
+
+        let result = RedisClient::open(
+            Topology::Cluster(vec!["redis://127.0.0.1:7000".to_owned()]),
+            options,
+        );
+
+        assert!(matches!(
+            result.map(|_| ()).map_err(|error| error.kind()),
+            Err(ErrorKind::InvalidClientConfig)
+        ));
+    }
+
+    #[cfg(feature = "client-cache")]
+    #[test]
+    fn test_client_cache_requires_resp3() {
+        let options = ConnectionOptions {
+            client_cache: Some(ClientCache::default()),
+            ..Default::default()
+        };
+
+        let client =
+            RedisClient::open(Topology::Single("redis://127.0.0.1/".to_owned()), options).unwrap();
+
+        let ClientKind::Single(client) = client.kind else {
+            panic!("expected single node client");
+        };
+        assert_eq!(
+            client.get_connection_info().redis.protocol,
+            redis::ProtocolVersion::RESP3
+        );
+    }
+
+    #[test]
+    fn test_single_node_options() {
+        let options = ConnectionOptions {
+            database: Some(3),
+            username: Some("cache".to_owned()),
+            password: Some("secret".to_owned()),
+            ..Default::default()
+        };
+
+        let client = RedisClient::open(
+            Topology::Single("redis://127.0.0.1:6379/1".to_owned()),
+            options,
+        )
+        .unwrap();
+
+        let ClientKind::Single(client) = client.kind else {
+            panic!("expected single node client");
+        };
+        let info = &client.get_connection_info().redis;
+        assert_eq!(info.db, 3);
+        assert_eq!(info.username.as_deref(), Some("cache"));
+        assert_eq!(info.password.as_deref(), Some("secret"));
+    }
+}
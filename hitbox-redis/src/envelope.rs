@@ -0,0 +1,149 @@
+//! Binary envelope used to store cache values in Redis.
+//!
+//! Redis keeps only raw bytes, so the cache metadata (`expire` and `stale`
+//! timestamps) is stored next to the payload. Every stored value starts with
+//! a two byte header followed by the encoded body:
+//!
+//! | offset | size | field                                    |
+//! |--------|------|------------------------------------------|
+//! | 0      | 1    | envelope version, see [`VERSION`]        |
+//! | 1      | 1    | body format id, see [`FORMAT_BINCODE`]   |
+//! | 2      | ..   | body: `stale`, `expire` and payload      |
+//!
+//! The payload itself is the value already serialized and compressed by
+//! [`CacheBackend`], the envelope never looks inside it.
+//!
+//! [`CacheBackend`]: hitbox_backend::CacheBackend
+use bincode::{
+    config::standard as bincode_config,
+    serde::{decode_from_slice, encode_to_vec},
+};
+use chrono::{DateTime, Utc};
+use hitbox::CacheValue;
+use hitbox_backend::serializer::Raw;
+use serde::{Deserialize, Serialize};
+
+/// Current envelope version.
+pub const VERSION: u8 = 1;
+
+/// Body encoded with bincode standard configuration.
+pub const FORMAT_BINCODE: u8 = 1;
+
+const HEADER_LEN: usize = 2;
+
+/// Errors of decoding values stored in Redis.
+#[derive(Debug, thiserror::Error)]
+pub enum EnvelopeError {
+    /// Stored value is shorter than the envelope header.
+    #[error("value is too short to be a cache envelope")]
+    Truncated,
+    /// Envelope was written by an unknown version of the backend.
+    #[error("unsupported envelope version: {0}")]
+    UnsupportedVersion(u8),
+    /// Envelope body uses an unknown format.
+    #[error("unsupported envelope format: {0}")]
+    UnsupportedFormat(u8),
+    /// Envelope body can't be encoded or decoded.
+    #[error("malformed envelope body: {0}")]
+    Body(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Body {
+    stale: Option<DateTime<Utc>>,
+    expire: Option<DateTime<Utc>>,
+    #[serde(with = "serde_bytes")]
+    data: Vec<u8>,
+}
+
+/// Wrap value and its metadata into envelope.
+pub fn encode(value: CacheValue<Raw>) -> Result<Vec<u8>, EnvelopeError> {
+    let body = Body {
+        stale: value.stale,
+        expire: value.expire,
+        data: value.data,
+    };
+    let mut envelope = vec![VERSION, FORMAT_BINCODE];
+    let encoded = encode_to_vec(&body, bincode_config())
+        .map_err(|error| EnvelopeError::Body(error.to_string()))?;
+    envelope.extend_from_slice(&encoded);
+    Ok(envelope)
+}
+
+/// Restore value and its metadata from envelope.
+pub fn decode(envelope: &[u8]) -> Result<CacheValue<Raw>, EnvelopeError> {
+    if envelope.len() < HEADER_LEN {
+        return Err(EnvelopeError::Truncated);
+    }
+    match envelope[0] {
+        VERSION => {}
+        version => return Err(EnvelopeError::UnsupportedVersion(version)),
+    }
+    match envelope[1] {
+        FORMAT_BINCODE => {}
+        format => return Err(EnvelopeError::UnsupportedFormat(format)),
+    }
+    let (body, _): (Body, _) = decode_from_slice(&envelope[HEADER_LEN..], bincode_config())
+        .map_err(|error| EnvelopeError::Body(error.to_string()))?;
+    Ok(CacheValue::new(body.data, body.expire, body.stale))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_keeps_metadata() {
+        let now = Utc::now();
+        let value = CacheValue::new(
+            b"payload".to_vec(),
+            Some(now + Duration::seconds(60)),
+            Some(now + Duration::seconds(30)),
+        );
+
+        let decoded = decode(&encode(value.clone()).unwrap()).unwrap();
+
+        assert_eq!(decoded.data, value.data);
+        assert_eq!(decoded.expire, value.expire);
+        assert_eq!(decoded.stale, value.stale);
+    }
+
+    #[test]
+    fn test_roundtrip_without_metadata() {
+        let value = CacheValue::new(Vec::new(), None, None);
+
+        let decoded = decode(&encode(value).unwrap()).unwrap();
+
+        assert!(decoded.data.is_empty());
+        assert_eq!(decoded.expire, None);
+        assert_eq!(decoded.stale, None);
+    }
+
+    #[test]
+    fn test_header_is_validated() {
+        let envelope = encode(CacheValue::new(b"payload".to_vec(), None, None)).unwrap();
+
+        assert!(matches!(decode(&[]), Err(EnvelopeError::Truncated)));
+
+        let mut unknown_version = envelope.clone();
+        unknown_version[0] = VERSION + 1;
+        assert!(matches!(
+            decode(&unknown_version),
+            Err(EnvelopeError::UnsupportedVersion(_))
+        ));
+
+        let mut unknown_format = envelope.clone();
+        unknown_format[1] = 0;
+        assert!(matches!(
+            decode(&unknown_format),
+            Err(EnvelopeError::UnsupportedFormat(0))
+        ));
+
+        assert!(matches!(
+            decode(&envelope[..HEADER_LEN]),
+            Err(EnvelopeError::Body(_))
+        ));
+    }
+}
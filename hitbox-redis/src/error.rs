@@ -4,6 +4,8 @@
 use hitbox_backend::BackendError;
 use redis::RedisError;
 
+use crate::envelope::EnvelopeError;
+
 /// Redis backend error declaration.
 ///
 /// Simply, it's just a wrapper for [redis::RedisError].
@@ -14,10 +16,23 @@ pub enum Error {
     /// Wrapper for all kinds redis-rs errors.
     #[error("Redis backend error: {0}")]
     Redis(#[from] RedisError),
+    /// Stored value can't be decoded as a cache envelope.
+    #[error("Redis backend error: {0}")]
+    Envelope(#[from] EnvelopeError),
 }
 
 impl From<Error> for BackendError {
     fn from(error: Error) -> Self {
-        Self::InternalError(Box::new(error))
+        match &error {
+            Error::Redis(redis)
+                if redis.is_io_error()
+                    || redis.is_connection_refusal()
+                    || redis.is_connection_dropped()
+                    || redis.is_timeout() =>
+            {
+                Self::ConnectionError(Box::new(error))
+            }
+            _ => Self::InternalError(Box::new(error)),
+        }
     }
 }
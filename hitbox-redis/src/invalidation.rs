@@ -0,0 +1,123 @@
+//! [`InvalidationBus`] implementation over Redis pub/sub.
+//!
+//! [`InvalidationBus`]: hitbox_backend::InvalidationBus
+use async_trait::async_trait;
+use futures::StreamExt;
+use hitbox_backend::{BackendResult, Invalidation, InvalidationBus, InvalidationStream};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+use crate::{
+    connection::{Connection, ConnectionOptions, RedisClient, Topology},
+    error::Error,
+};
+
+/// Channel used by [`RedisInvalidationBus::new`].
+pub const DEFAULT_CHANNEL: &str = "hitbox:invalidation";
+
+/// Invalidation bus publishing events on a Redis pub/sub channel.
+///
+/// Events are published through one managed connection, while every subscription
+/// opens its own pub/sub connection. The bus connects with the same [`Topology`]
+/// and [`ConnectionOptions`] as [`RedisBackend`], see
+/// [`RedisBackend::invalidation_bus`].
+///
+/// [`RedisBackend`]: crate::RedisBackend
+/// [`RedisBackend::invalidation_bus`]: crate::RedisBackend::invalidation_bus
+pub struct RedisInvalidationBus {
+    client: RedisClient,
+    channel: String,
+    connection: OnceCell<Connection>,
+}
+
+impl RedisInvalidationBus {
+    /// Create bus using [`DEFAULT_CHANNEL`].
+    pub fn new(topology: Topology, options: ConnectionOptions) -> Result<Self, Error> {
+        Self::with_channel(topology, options, DEFAULT_CHANNEL)
+    }
+
+    /// Create bus using the given channel.
+    pub fn with_channel(
+        topology: Topology,
+        options: ConnectionOptions,
+        channel: impl Into<String>,
+    ) -> Result<Self, Error> {
+        Ok(Self::from_client(
+            RedisClient::open(topology, options)?,
+            channel,
+        ))
+    }
+
+    pub(crate) fn from_client(client: RedisClient, channel: impl Into<String>) -> Self {
+        Self {
+            client,
+            channel: channel.into(),
+            connection: OnceCell::new(),
+        }
+    }
+
+    /// Pub/sub channel name.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+}
+
+impl std::fmt::Debug for RedisInvalidationBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisInvalidationBus")
+            .field("channel", &self.channel)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for RedisInvalidationBus {
+    async fn publish(&self, invalidation: Invalidation) -> BackendResult<()> {
+        let mut con = self
+            .connection
+            .get_or_try_init(|| self.client.open_connection())
+            .await
+            .map_err(Error::from)?
+            .clone();
+        let _receivers: i64 = redis::cmd("PUBLISH")
+            .arg(&self.channel)
+            .arg(invalidation.encode())
+            .query_async(&mut con)
+            .await
+            .map_err(Error::from)?;
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> BackendResult<InvalidationStream> {
+        let mut pubsub = self.client.pubsub().await.map_err(Error::from)?;
+        pubsub.subscribe(&self.channel).await.map_err(Error::from)?;
+        let events = pubsub.into_on_message().filter_map(|message| async move {
+            Invalidation::decode(message.get_payload_bytes())
+                .inspect_err(|error| warn!("Malformed invalidation message: {error}"))
+                .ok()
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_applies_connection_options() {
+        let options = ConnectionOptions {
+            database: Some(1),
+            ..Default::default()
+        };
+
+        let single = RedisInvalidationBus::new(Topology::default(), options.clone());
+        let cluster = RedisInvalidationBus::new(
+            Topology::Cluster(vec!["redis://127.0.0.1:7000".to_owned()]),
+            options,
+        );
+
+        assert!(single.is_ok());
+        assert!(cluster.is_err());
+    }
+}
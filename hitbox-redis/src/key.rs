@@ -0,0 +1,72 @@
+//! Redis Cluster hash tags for storage keys.
+//!
+//! Redis Cluster assigns a key to a slot by hashing the whole key, unless the key
+//! contains a hash tag: then only the part between the first `{` and the next `}`
+//! is hashed. Keys which must live on the same node, such as values together
+//! with their tags or locks, share the same hash tag.
+use hitbox::CacheKey;
+
+/// Hash tag applied to every storage key of [`RedisBackend`].
+///
+/// [`RedisBackend`]: crate::RedisBackend
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum HashTag {
+    /// Keys are spread over all slots.
+    #[default]
+    Disabled,
+    /// Keys with the same [`CacheKey::prefix`] are stored in one slot.
+    ///
+    /// Keys with an empty prefix are left untagged.
+    Prefix,
+    /// All keys are stored in the slot of the given tag.
+    Fixed(String),
+}
+
+impl HashTag {
+    pub(crate) fn apply(&self, key: &CacheKey, serialized: Vec<u8>) -> Vec<u8> {
+        match self {
+            HashTag::Disabled => serialized,
+            HashTag::Prefix if key.prefix().is_empty() => serialized,
+            HashTag::Prefix => hash_tagged(key.prefix(), &serialized),
+            HashTag::Fixed(tag) => hash_tagged(tag, &serialized),
+        }
+    }
+}
+
+/// Prepend `{tag}` to the key so Redis Cluster stores it in the slot of `tag`.
+///
+/// The tag should not contain `}`, otherwise only its part before `}` is hashed.
+pub fn hash_tagged(tag: &str, key: &[u8]) -> Vec<u8> {
+    let mut tagged = Vec::with_capacity(tag.len() + key.len() + 2);
+    tagged.push(b'{');
+    tagged.extend_from_slice(tag.as_bytes());
+    tagged.push(b'}');
+    tagged.extend_from_slice(key);
+    tagged
+}
+
+#[cfg(test)]
+mod tests {
+    use hitbox::KeyPart;
+
+    use super::*;
+
+    #[test]
+    fn test_hash_tagged() {
+        assert_eq!(hash_tagged("orders", b"key"), b"{orders}key");
+    }
+
+    #[test]
+    fn test_apply() {
+        let key = CacheKey::new("api".to_owned(), 1, vec![KeyPart::new("id", Some("1"))]);
+        let untagged = CacheKey::from_str("id", "1");
+
+        assert_eq!(HashTag::Disabled.apply(&key, b"key".to_vec()), b"key");
+        assert_eq!(HashTag::Prefix.apply(&key, b"key".to_vec()), b"{api}key");
+        assert_eq!(HashTag::Prefix.apply(&untagged, b"key".to_vec()), b"key");
+        assert_eq!(
+            HashTag::Fixed("cache".to_owned()).apply(&untagged, b"key".to_vec()),
+            b"{cache}key"
+        );
+    }
+}
@@ -2,13 +2,26 @@
 //! hitbox [Backend] implementation for Redis.
 //!
 //! This crate uses [redis-rs] as base library for asynchronous interaction with redis nodes.
-//! It use one [MultiplexedConnection] for better connection utilization.
+//! Single node, Redis Cluster and Sentinel deployments are supported, each through
+//! one managed connection for better connection utilization.
 //!
-//! [MultiplexedConnection]: redis::aio::MultiplexedConnection
 //! [Backend]: hitbox_backend::Backend
 //! [redis-rs]: redis-rs::aio
 pub mod backend;
+pub mod connection;
+pub mod envelope;
 pub mod error;
+pub mod invalidation;
+pub mod key;
 
 #[doc(inline)]
 pub use crate::backend::{RedisBackend, RedisBackendBuilder};
+#[cfg(feature = "client-cache")]
+#[doc(inline)]
+pub use crate::connection::ClientCacheStats;
+#[doc(inline)]
+pub use crate::connection::{ClientCache, ConnectionOptions, Retry, Topology};
+#[doc(inline)]
+pub use crate::invalidation::RedisInvalidationBus;
+#[doc(inline)]
+pub use crate::key::HashTag;
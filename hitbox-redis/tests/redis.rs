@@ -0,0 +1,28 @@
+mod support;
+
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::Backend;
+use hitbox_redis::RedisBackend;
+use support::FakeRedis;
+
+#[tokio::test]
+async fn test_undecodable_value_is_a_miss() {
+    let server = FakeRedis::start().await;
+    let backend = RedisBackend::builder()
+        .server(server.address.clone())
+        .build()
+        .unwrap();
+    let key = CacheKey::from_str("key", "1");
+
+    backend
+        .write(&key, CacheValue::new(b"value".to_vec(), None, None), None)
+        .await
+        .unwrap();
+    assert!(backend.read(&key).await.unwrap().is_some());
+    for stored in server.items().into_keys() {
+        server.set(stored, b"{\"raw\":true}".to_vec());
+    }
+
+    assert_eq!(backend.read(&key).await.unwrap(), None);
+    assert!(server.items().is_empty());
+}
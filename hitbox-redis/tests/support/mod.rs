@@ -0,0 +1,98 @@
+//! In-process fake Redis speaking the subset of RESP2 used by the backend.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream},
+    net::{TcpListener, TcpStream},
+};
+
+type Items = Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>;
+
+#[derive(Clone, Default)]
+pub struct FakeRedis {
+    pub address: String,
+    items: Items,
+}
+
+impl FakeRedis {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = Self {
+            address: format!("redis://{}", listener.local_addr().unwrap()),
+            items: Default::default(),
+        };
+        let items = server.items.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, items.clone()));
+            }
+        });
+        server
+    }
+
+    pub fn items(&self) -> HashMap<Vec<u8>, Vec<u8>> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, key: Vec<u8>, value: Vec<u8>) {
+        self.items.lock().unwrap().insert(key, value);
+    }
+}
+
+async fn read_line(stream: &mut BufStream<TcpStream>) -> Option<String> {
+    let mut line = String::new();
+    match stream.read_line(&mut line).await {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end().to_owned()),
+    }
+}
+
+async fn read_command(stream: &mut BufStream<TcpStream>) -> Option<Vec<Vec<u8>>> {
+    let count: usize = read_line(stream).await?.strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        let length: usize = read_line(stream).await?.strip_prefix('$')?.parse().ok()?;
+        let mut arg = vec![0; length + 2];
+        stream.read_exact(&mut arg).await.ok()?;
+        arg.truncate(length);
+        args.push(arg);
+    }
+    Some(args)
+}
+
+async fn serve(stream: TcpStream, items: Items) {
+    let mut stream = BufStream::new(stream);
+    while let Some(args) = read_command(&mut stream).await {
+        let command = args[0].to_ascii_uppercase();
+        let response = match (command.as_slice(), &args[1..]) {
+            (b"GET", [key]) => match items.lock().unwrap().get(key) {
+                Some(value) => {
+                    let mut response = format!("${}\r\n", value.len()).into_bytes();
+                    response.extend_from_slice(value);
+                    response.extend_from_slice(b"\r\n");
+                    response
+                }
+                None => b"$-1\r\n".to_vec(),
+            },
+            (b"SET", [key, value, ..]) => {
+                items.lock().unwrap().insert(key.clone(), value.clone());
+                b"+OK\r\n".to_vec()
+            }
+            (b"DEL", keys) => {
+                let mut items = items.lock().unwrap();
+                let removed = keys
+                    .iter()
+                    .filter(|key| items.remove(*key).is_some())
+                    .count();
+                format!(":{removed}\r\n").into_bytes()
+            }
+            _ => b"+OK\r\n".to_vec(),
+        };
+        if stream.write_all(&response).await.is_err() || stream.flush().await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use hitbox_backend::{
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    entry_deadline,
+};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::error::Error;
+
+/// Name of the table holding cache entries.
+pub const TABLE_NAME: &str = "hitbox_cache";
+
+/// Default period of the expired rows sweeper.
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timestamps are stored as milliseconds since the Unix epoch. `deadline` is the
+/// moment the row may be removed, derived from the write ttl. Reads and the
+/// sweeper filter on it, so `expire` and `stale` are not indexed.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS hitbox_cache (
+    key BLOB PRIMARY KEY NOT NULL,
+    prefix TEXT NOT NULL,
+    data BLOB NOT NULL,
+    expire INTEGER,
+    stale INTEGER,
+    deadline INTEGER
+) WITHOUT ROWID;
+CREATE INDEX IF NOT EXISTS hitbox_cache_prefix ON hitbox_cache (prefix);
+CREATE INDEX IF NOT EXISTS hitbox_cache_deadline ON hitbox_cache (deadline) WHERE deadline IS NOT NULL;
+DROP INDEX IF EXISTS hitbox_cache_expire;
+DROP INDEX IF EXISTS hitbox_cache_stale;
+";
+
+/// Connection shared by all clones of the backend.
+///
+/// Queries run on the blocking thread pool, one at a time.
+struct Database {
+    connection: Mutex<Connection>,
+}
+
+impl Database {
+    fn open(path: Option<&PathBuf>) -> Result<Self, Error> {
+        let connection = match path {
+            Some(path) => Connection::open(path)?,
+            None => Connection::open_in_memory()?,
+        };
+        // In-memory databases keep their own journal mode, which is fine.
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    async fn run<T, F>(self: &Arc<Self>, query: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let database = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = database
+                .connection
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            query(&connection)
+        })
+        .await?
+        .map_err(Error::from)
+    }
+
+    async fn sweep(self: &Arc<Self>) -> Result<usize, Error> {
+        let now = Utc::now().timestamp_millis();
+        self.run(move |connection| {
+            connection.execute(
+                "DELETE FROM hitbox_cache WHERE deadline <= ?1",
+                params![now],
+            )
+        })
+        .await
+    }
+}
+
+/// SQLite cache backend based on rusqlite crate.
+#[derive(Clone)]
+pub struct SqliteBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    database: Arc<Database>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl SqliteBackend<JsonFormat, PassthroughCompressor> {
+    /// Open database file with default settings, creating it if needed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::builder().path(path).build()
+    }
+
+    /// Creates new SqliteBackend builder, in-memory database by default.
+    pub fn builder() -> SqliteBackendBuilder<JsonFormat, PassthroughCompressor> {
+        SqliteBackendBuilder::default()
+    }
+}
+
+impl<S, C> SqliteBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Remove expired rows now, returning the number of removed rows.
+    ///
+    /// The background sweeper calls it periodically.
+    pub async fn sweep(&self) -> Result<usize, Error> {
+        self.database.sweep().await
+    }
+
+    /// Remove all entries, returning the number of removed rows.
+    pub async fn clear(&self) -> Result<usize, Error> {
+        self.database
+            .run(|connection| connection.execute("DELETE FROM hitbox_cache", []))
+            .await
+    }
+
+    /// Remove all entries with the given [`CacheKey::prefix`], returning the number of removed rows.
+    pub async fn remove_prefix(&self, prefix: &str) -> Result<usize, Error> {
+        let prefix = prefix.to_owned();
+        self.database
+            .run(move |connection| {
+                connection.execute(
+                    "DELETE FROM hitbox_cache WHERE prefix = ?1",
+                    params![prefix],
+                )
+            })
+            .await
+    }
+
+    /// Number of stored entries with the given [`CacheKey::prefix`], expired ones included.
+    pub async fn count_prefix(&self, prefix: &str) -> Result<usize, Error> {
+        let prefix = prefix.to_owned();
+        self.database
+            .run(move |connection| {
+                connection.query_row(
+                    "SELECT count(*) FROM hitbox_cache WHERE prefix = ?1",
+                    params![prefix],
+                    |row| row.get(0),
+                )
+            })
+            .await
+    }
+}
+
+impl<S, C> std::fmt::Debug for SqliteBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteBackend")
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct SqliteBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    path: Option<PathBuf>,
+    sweep_interval: Duration,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl Default for SqliteBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
+
+impl<S, C> SqliteBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Set database file path.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Set period of the expired rows sweeper, [`DEFAULT_SWEEP_INTERVAL`] by default.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
+    pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
+        self.key_format = format;
+        self
+    }
+
+    pub fn value_format<NewS>(self, serializer: NewS) -> SqliteBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        SqliteBackendBuilder {
+            path: self.path,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    pub fn compressor<NewC>(self, compressor: NewC) -> SqliteBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        SqliteBackendBuilder {
+            path: self.path,
+            sweep_interval: self.sweep_interval,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Open database, create the table and start the sweeper.
+    ///
+    /// The sweeper runs on the current Tokio runtime until the backend and all its
+    /// clones are dropped; without a runtime expired rows are only skipped on read.
+    pub fn build(self) -> Result<SqliteBackend<S, C>, Error> {
+        let database = Arc::new(Database::open(self.path.as_ref())?);
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn(sweeper(Arc::downgrade(&database), self.sweep_interval));
+            }
+            Err(_) => tracing::warn!("No Tokio runtime, SQLite cache sweeper is not started"),
+        }
+
+        Ok(SqliteBackend {
+            database,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
+    }
+}
+
+async fn sweeper(database: Weak<Database>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(database) = database.upgrade() else {
+            break;
+        };
+        match database.sweep().await {
+            Ok(removed) => tracing::trace!("Removed {removed} expired cache rows"),
+            Err(err) => tracing::warn!("Expired cache rows sweep failed: {err}"),
+        }
+    }
+}
+
+fn to_millis(timestamp: Option<DateTime<Utc>>) -> Option<i64> {
+    timestamp.map(|timestamp| timestamp.timestamp_millis())
+}
+
+fn from_millis(millis: Option<i64>) -> Option<DateTime<Utc>> {
+    millis.and_then(DateTime::from_timestamp_millis)
+}
+
+#[async_trait]
+impl<S, C> Backend for SqliteBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        let key = self.key_format.serialize(key)?;
+        let now = Utc::now().timestamp_millis();
+        let value = self
+            .database
+            .run(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT data, expire, stale FROM hitbox_cache
+                         WHERE key = ?1 AND (deadline IS NULL OR deadline > ?2)",
+                        params![key, now],
+                        |row| {
+                            Ok(CacheValue::new(
+                                row.get(0)?,
+                                from_millis(row.get(1)?),
+                                from_millis(row.get(2)?),
+                            ))
+                        },
+                    )
+                    .optional()
+            })
+            .await?;
+        Ok(value)
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        let Some(deadline) = entry_deadline(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+
+        let prefix = key.prefix().to_owned();
+        let key = self.key_format.serialize(key)?;
+        self.database
+            .run(move |connection| {
+                connection.execute(
+                    "INSERT OR REPLACE INTO hitbox_cache (key, prefix, data, expire, stale, deadline)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        key,
+                        prefix,
+                        value.data,
+                        to_millis(value.expire),
+                        to_millis(value.stale),
+                        to_millis(deadline),
+                    ],
+                )
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        let key = self.key_format.serialize(key)?;
+        let deleted = self
+            .database
+            .run(move |connection| {
+                connection.execute("DELETE FROM hitbox_cache WHERE key = ?1", params![key])
+            })
+            .await?;
+        match deleted {
+            0 => Ok(DeleteStatus::Missing),
+            deleted => Ok(DeleteStatus::Deleted(deleted as u32)),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
+    }
+}
@@ -0,0 +1,15 @@
+use hitbox_backend::BackendError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("SQLite backend error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("SQLite backend error: background task failed, {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        Self::InternalError(Box::new(error))
+    }
+}
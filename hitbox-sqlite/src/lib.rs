@@ -0,0 +1,14 @@
+//! hitbox [Backend] implementation for SQLite.
+//!
+//! Entries live in a single table keyed by the serialized [`CacheKey`], so the
+//! cache survives restarts and can be inspected with ordinary SQLite tools.
+//! Queries run on the blocking thread pool, expired rows are removed by a
+//! background sweeper.
+//!
+//! [Backend]: hitbox_backend::Backend
+//! [`CacheKey`]: hitbox::CacheKey
+mod backend;
+pub mod error;
+
+pub use crate::backend::{SqliteBackend, SqliteBackendBuilder, TABLE_NAME};
+pub use crate::error::Error;
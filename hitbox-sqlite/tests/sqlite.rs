@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::{Backend, DeleteStatus};
+use hitbox_backend_tests::{Conformance, SystemClock};
+use hitbox_sqlite::SqliteBackend;
+
+fn value() -> CacheValue<Vec<u8>> {
+    CacheValue::new(b"value".to_vec(), None, None)
+}
+
+fn prefixed(prefix: &str, value: &str) -> CacheKey {
+    CacheKey::new(
+        prefix.to_owned(),
+        0,
+        vec![hitbox::KeyPart::new("key", Some(value))],
+    )
+}
+
+#[tokio::test]
+async fn test_write_read_remove() {
+    let backend = SqliteBackend::builder().build().unwrap();
+    let key = CacheKey::from_str("key", "1");
+    let expire = Utc::now() + chrono::Duration::minutes(1);
+    let stale = Utc::now() + chrono::Duration::seconds(30);
+    let value = CacheValue::new(b"payload".to_vec(), Some(expire), Some(stale));
+
+    backend.write(&key, value.clone(), None).await.unwrap();
+
+    let cached = backend.read(&key).await.unwrap().unwrap();
+    assert_eq!(cached.data, value.data);
+    assert_eq!(
+        cached.expire.map(|expire| expire.timestamp_millis()),
+        Some(expire.timestamp_millis())
+    );
+    assert_eq!(
+        cached.stale.map(|stale| stale.timestamp_millis()),
+        Some(stale.timestamp_millis())
+    );
+    assert_eq!(
+        backend.remove(&key).await.unwrap(),
+        DeleteStatus::Deleted(1)
+    );
+    assert_eq!(backend.remove(&key).await.unwrap(), DeleteStatus::Missing);
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_ttl_and_sweep() {
+    let backend = SqliteBackend::builder().build().unwrap();
+    let expiring = CacheKey::from_str("key", "1");
+    let permanent = CacheKey::from_str("key", "2");
+
+    backend
+        .write(&expiring, value(), Some(Duration::from_millis(20)))
+        .await
+        .unwrap();
+    backend.write(&permanent, value(), None).await.unwrap();
+    assert!(backend.read(&expiring).await.unwrap().is_some());
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(backend.read(&expiring).await.unwrap().is_none());
+    assert_eq!(backend.sweep().await.unwrap(), 1);
+    assert!(backend.read(&permanent).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_expired_value_is_not_written() {
+    let backend = SqliteBackend::builder().build().unwrap();
+    let key = CacheKey::from_str("key", "1");
+    let expired = CacheValue::new(
+        b"value".to_vec(),
+        Some(Utc::now() - chrono::Duration::seconds(1)),
+        None,
+    );
+
+    backend.write(&key, expired, None).await.unwrap();
+
+    assert!(backend.read(&key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_clear_and_remove_prefix() {
+    let backend = SqliteBackend::builder().build().unwrap();
+    for key in [
+        prefixed("users", "1"),
+        prefixed("users", "2"),
+        prefixed("orders", "1"),
+    ] {
+        backend.write(&key, value(), None).await.unwrap();
+    }
+
+    assert_eq!(backend.count_prefix("users").await.unwrap(), 2);
+    assert_eq!(backend.remove_prefix("users").await.unwrap(), 2);
+    assert!(
+        backend
+            .read(&prefixed("orders", "1"))
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert_eq!(backend.clear().await.unwrap(), 1);
+    assert_eq!(backend.count_prefix("orders").await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.db");
+    let key = CacheKey::from_str("key", "1");
+    {
+        let backend = SqliteBackend::open(&path).unwrap();
+        backend.write(&key, value(), None).await.unwrap();
+    }
+
+    let backend = SqliteBackend::open(&path).unwrap();
+
+    assert_eq!(backend.read(&key).await.unwrap().unwrap().data, b"value");
+}
+
+#[tokio::test]
+async fn test_conformance() {
+    let backend = SqliteBackend::builder().build().unwrap();
+
+    Conformance::new(&backend, SystemClock)
+        .ttl(Duration::from_millis(200))
+        .run()
+        .await;
+}
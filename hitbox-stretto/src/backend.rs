@@ -1,161 +1,181 @@
-use crate::builder::StrettoBackendBuilder;
+use crate::{builder::StrettoBackendBuilder, error::Error};
 use async_trait::async_trait;
-use hitbox::{CacheKey, CacheValue, CacheableResponse};
+use chrono::Utc;
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
 use hitbox_backend::{
-    // serializer::{BinSerializer, Serializer},
-    Backend,
-    BackendError,
-    BackendResult,
-    CacheBackend,
-    DeleteStatus,
+    Backend, BackendError, BackendResult, CacheKeyFormat, Compressor, DeleteStatus,
+    PassthroughCompressor, entry_ttl,
 };
 use std::time::Duration;
 use stretto::AsyncCache;
 
 #[derive(Clone)]
-pub struct StrettoBackend {
-    pub(crate) cache: AsyncCache<CacheKey, Vec<u8>>,
+pub struct StrettoBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    pub(crate) cache: AsyncCache<CacheKey, CacheValue<Raw>>,
+    pub(crate) key_format: CacheKeyFormat,
+    pub(crate) serializer: S,
+    pub(crate) compressor: C,
 }
 
-impl StrettoBackend {
-    pub fn builder(max_size: i64) -> StrettoBackendBuilder {
-        StrettoBackendBuilder::new(max_size)
+impl<S, C> std::fmt::Debug for StrettoBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrettoBackend")
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StrettoBackend<JsonFormat, PassthroughCompressor> {
+    /// Create builder of a cache holding values up to `max_cost` bytes in total,
+    /// sized for about `max_items` entries.
+    pub fn builder(
+        max_items: usize,
+        max_cost: i64,
+    ) -> StrettoBackendBuilder<JsonFormat, PassthroughCompressor> {
+        StrettoBackendBuilder::new(max_items, max_cost)
     }
 }
 
 #[async_trait]
-impl Backend for StrettoBackend {
-    async fn read(
-        &self,
-        key: &CacheKey,
-    ) -> BackendResult<Option<CacheValue<hitbox_backend::serializer::Raw>>> {
-        todo!()
+impl<S, C> Backend for StrettoBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        Ok(self.cache.get(key).await.map(|value| {
+            let cached = value.value().clone();
+            value.release();
+            cached
+        }))
     }
 
     async fn write(
         &self,
         key: &CacheKey,
-        value: CacheValue<hitbox_backend::serializer::Raw>,
+        value: CacheValue<Raw>,
         ttl: Option<Duration>,
     ) -> BackendResult<()> {
-        todo!()
+        let Some(ttl) = entry_ttl(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+        let cost = value.data.len().max(1) as i64;
+        let admitted = match ttl {
+            Some(ttl) => {
+                self.cache
+                    .insert_with_ttl(key.clone(), value, cost, ttl)
+                    .await
+            }
+            None => self.cache.insert(key.clone(), value, cost).await,
+        };
+        if !admitted {
+            // Rejection by the admission policy is regular cache behaviour.
+            tracing::debug!("Value was not admitted to the cache");
+            return Ok(());
+        }
+        // Insertions are applied asynchronously, wait for the value to become readable.
+        self.cache
+            .wait()
+            .await
+            .map_err(Error::from)
+            .map_err(BackendError::from)
     }
 
     async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
-        todo!()
+        let exists = match self.cache.get(key).await {
+            Some(value) => {
+                value.release();
+                true
+            }
+            None => false,
+        };
+        self.cache.remove(key).await;
+        self.cache.wait().await.map_err(Error::from)?;
+        match exists {
+            true => Ok(DeleteStatus::Deleted(1)),
+            false => Ok(DeleteStatus::Missing),
+        }
+    }
+
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
+    }
+
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
     }
-    // async fn get<T>(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<T::Cached>>>
-    // where
-    //     T: CacheableResponse,
-    //     <T as CacheableResponse>::Cached: serde::de::DeserializeOwned,
-    // {
-    //     let () = self
-    //         .cache
-    //         .wait()
-    //         .await
-    //         .map_err(crate::error::Error::from)
-    //         .map_err(BackendError::from)?;
-    //
-    //     match self.cache.get(key).await {
-    //         Some(cached) => BinSerializer::<Vec<u8>>::deserialize(cached.value().to_owned())
-    //             .map_err(BackendError::from)
-    //             .map(Some),
-    //
-    //         None => Ok(None),
-    //     }
-    // }
-    //
-    // async fn set<T>(
-    //     &self,
-    //     key: &CacheKey,
-    //     value: &CacheValue<T::Cached>,
-    //     ttl: Option<u32>,
-    // ) -> BackendResult<()>
-    // where
-    //     T: CacheableResponse + Send,
-    //     T::Cached: serde::Serialize + Send + Sync,
-    // {
-    //     let serialized = BinSerializer::<Vec<u8>>::serialize(value).map_err(BackendError::from)?;
-    //     let cost = serialized.len();
-    //     let inserted = match ttl {
-    //         Some(ttl) => {
-    //             self.cache
-    //                 .insert_with_ttl(
-    //                     key.to_owned(),
-    //                     serialized,
-    //                     cost as i64,
-    //                     Duration::from_secs(ttl as u64),
-    //                 )
-    //                 .await
-    //         }
-    //         None => {
-    //             self.cache
-    //                 .insert(key.to_owned(), serialized, cost as i64)
-    //                 .await
-    //         }
-    //     };
-    //     if inserted {
-    //         Ok(())
-    //     } else {
-    //         Err(BackendError::from(crate::error::Error::Insert))
-    //     }
-    // }
-    //
-    // async fn delete(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
-    //     self.cache.remove(key).await;
-    //     Ok(DeleteStatus::Deleted(1))
-    // }
-    //
-    // async fn start(&self) -> BackendResult<()> {
-    //     Ok(())
-    // }
 }
 
 #[cfg(test)]
 mod test {
-    use async_trait::async_trait;
-    use chrono::Utc;
-    use serde::{Deserialize, Serialize};
-
     use super::*;
-    use hitbox::CacheableResponse;
 
-    #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
-    struct Test {
-        a: i32,
-        b: String,
+    fn value() -> CacheValue<Raw> {
+        CacheValue::new(b"value".to_vec(), None, None)
     }
 
-    #[async_trait]
-    impl CacheableResponse for Test {
-        type Cached = Self;
+    #[tokio::test]
+    async fn test_write_read_remove() {
+        let backend = StrettoBackend::builder(100, 1024).build().unwrap();
+        let key = CacheKey::from_str("key", "1");
 
-        async fn into_cached(self) -> Self::Cached {
-            self
-        }
-        async fn from_cached(cached: Self::Cached) -> Self {
-            cached
-        }
+        backend.write(&key, value(), None).await.unwrap();
+        let cached = backend.read(&key).await.unwrap().unwrap();
+        assert_eq!(cached.data, b"value");
+
+        assert_eq!(
+            backend.remove(&key).await.unwrap(),
+            DeleteStatus::Deleted(1)
+        );
+        assert!(backend.read(&key).await.unwrap().is_none());
+        assert_eq!(backend.remove(&key).await.unwrap(), DeleteStatus::Missing);
     }
 
-    impl Test {
-        pub fn new() -> Self {
-            Self {
-                a: 42,
-                b: "nope".to_owned(),
-            }
-        }
+    #[tokio::test]
+    async fn test_ttl() {
+        let backend = StrettoBackend::builder(100, 1024)
+            .set_cleanup_duration(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let key = CacheKey::from_str("key", "1");
+
+        backend
+            .write(&key, value(), Some(Duration::from_millis(50)))
+            .await
+            .unwrap();
+        assert!(backend.read(&key).await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(backend.read(&key).await.unwrap().is_none());
     }
 
     #[tokio::test]
-    async fn test_set_and_get() {
-        let cache = crate::StrettoBackend::builder(100).finalize().unwrap();
-        let value = CacheValue::new(Test::new(), Utc::now());
+    async fn test_expired_value_is_not_written() {
+        let backend = StrettoBackend::builder(100, 1024).build().unwrap();
         let key = CacheKey::from_str("key", "1");
-        let res = cache.set::<Test>(&key, &value, None).await;
-        assert!(res.is_ok());
-        let value = cache.get::<Test>(&key).await.unwrap().unwrap().into_inner();
-        assert_eq!(value.a, 42);
-        assert_eq!(value.b, "nope".to_owned());
+        let expired = CacheValue::new(
+            b"value".to_vec(),
+            Some(Utc::now() - chrono::Duration::seconds(1)),
+            None,
+        );
+
+        backend.write(&key, expired, None).await.unwrap();
+
+        assert!(backend.read(&key).await.unwrap().is_none());
     }
 }
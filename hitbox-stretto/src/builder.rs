@@ -1,35 +1,96 @@
-use crate::{error::Error, StrettoBackend};
-use hitbox::CacheKey;
+use crate::{StrettoBackend, error::Error};
+use hitbox::{CacheKey, CacheValue};
+use hitbox_backend::serializer::{Format, JsonFormat, Raw};
+use hitbox_backend::{CacheKeyFormat, Compressor, PassthroughCompressor};
 use std::time::Duration;
 use stretto::AsyncCacheBuilder;
 
-type Cache = AsyncCacheBuilder<CacheKey, Vec<u8>>;
+pub struct StrettoBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    builder: AsyncCacheBuilder<CacheKey, CacheValue<Raw>>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl StrettoBackendBuilder<JsonFormat, PassthroughCompressor> {
+    /// Create builder of a cache holding values up to `max_cost` bytes in total,
+    /// sized for about `max_items` entries.
+    pub fn new(max_items: usize, max_cost: i64) -> Self {
+        // Stretto recommends tracking ten times more keys than the cache can hold.
+        let num_counters = max_items.max(1).saturating_mul(10);
+        Self {
+            builder: AsyncCacheBuilder::new(num_counters, max_cost),
+            key_format: CacheKeyFormat::Bitcode,
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
+}
 
-pub struct StrettoBackendBuilder(Cache);
+impl<S, C> StrettoBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    pub fn set_buffer_size(mut self, sz: usize) -> Self {
+        self.builder = self.builder.set_buffer_size(sz);
+        self
+    }
+
+    pub fn set_buffer_items(mut self, sz: usize) -> Self {
+        self.builder = self.builder.set_buffer_items(sz);
+        self
+    }
 
-impl StrettoBackendBuilder {
-    pub fn new(max_size: i64) -> Self {
-        let num_counters = max_size * 10;
-        Self(AsyncCacheBuilder::new(num_counters as usize, max_size))
+    pub fn set_cleanup_duration(mut self, d: Duration) -> Self {
+        self.builder = self.builder.set_cleanup_duration(d);
+        self
     }
 
-    pub fn set_buffer_size(self, sz: usize) -> Self {
-        Self(self.0.set_buffer_size(sz))
+    pub fn key_format(mut self, format: CacheKeyFormat) -> Self {
+        self.key_format = format;
+        self
     }
 
-    pub fn set_buffer_items(self, sz: usize) -> Self {
-        Self(self.0.set_buffer_items(sz))
+    pub fn value_format<NewS>(self, serializer: NewS) -> StrettoBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        StrettoBackendBuilder {
+            builder: self.builder,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
     }
 
-    pub fn set_cleanup_duration(self, d: Duration) -> Self {
-        Self(self.0.set_cleanup_duration(d))
+    pub fn compressor<NewC>(self, compressor: NewC) -> StrettoBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        StrettoBackendBuilder {
+            builder: self.builder,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
     }
 
-    pub fn finalize(self) -> Result<StrettoBackend, Error> {
-        self.0
+    pub fn build(self) -> Result<StrettoBackend<S, C>, Error> {
+        // Cost is the size of stored data, so bookkeeping overhead is not counted.
+        let cache = self
+            .builder
             .set_ignore_internal_cost(true)
-            .finalize(tokio::spawn)
-            .map(|cache| StrettoBackend { cache })
-            .map_err(Error::from)
+            .finalize(tokio::spawn)?;
+        Ok(StrettoBackend {
+            cache,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        })
     }
 }
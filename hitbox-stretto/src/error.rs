@@ -3,8 +3,6 @@ use stretto::CacheError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Cache backend error: cannot insert value")]
-    Insert,
     #[error("Cache backend error: {0}")]
     CacheError(#[from] CacheError),
 }
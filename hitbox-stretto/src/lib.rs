@@ -1,5 +1,11 @@
+//! hitbox [Backend] implementation based on [Stretto], an in-process cache with
+//! cost-based TinyLFU admission.
+//!
+//! [Backend]: hitbox_backend::Backend
+//! [Stretto]: stretto
 mod backend;
 mod builder;
 pub mod error;
 
 pub use crate::backend::StrettoBackend;
+pub use crate::builder::StrettoBackendBuilder;
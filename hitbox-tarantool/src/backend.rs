@@ -1,147 +1,335 @@
+//! Tarantool backend implementation.
+use std::{sync::Arc, time::Duration};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use hitbox_backend::{
-    serializer::SerializableCachedValue, BackendError, BackendResult, CacheBackend, DeleteStatus,
+    Backend, BackendResult, CacheKeyFormat, Compressor, DeleteStatus, PassthroughCompressor,
+    entry_ttl,
+    serializer::{Format, JsonFormat, Raw},
 };
-use hitbox_core::{CacheKey, CacheableResponse, CachedValue};
+use hitbox_core::{CacheKey, CacheValue};
 use rusty_tarantool::tarantool::{Client, ClientConfig, ExecWithParamaters};
 use serde::{Deserialize, Serialize};
-use std::io::Error;
-use typed_builder::TypedBuilder;
+use serde_bytes::ByteBuf;
+use tokio::sync::OnceCell;
+
+use crate::error::Error;
 
 const TARANTOOL_INIT_LUA: &str = include_str!("init.lua");
 
+/// Name of the space holding cache entries.
+pub const SPACE_NAME: &str = "hitbox_cache";
+
+/// Cache entry as stored in the space.
+///
+/// `expire_at` is the Tarantool clock time after which the expiration fiber removes
+/// the tuple, `expire` and `stale` are [`CacheValue`] timestamps in milliseconds.
+#[doc(hidden)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: ByteBuf,
+    pub expire_at: f64,
+    pub data: ByteBuf,
+    pub expire: Option<i64>,
+    pub stale: Option<i64>,
+}
+
+impl From<CacheEntry> for CacheValue<Raw> {
+    fn from(entry: CacheEntry) -> Self {
+        CacheValue::new(
+            entry.data.into_vec(),
+            entry.expire.and_then(DateTime::from_timestamp_millis),
+            entry.stale.and_then(DateTime::from_timestamp_millis),
+        )
+    }
+}
+
 /// Tarantool cache backend based on rusty_tarantool crate.
 ///
+/// The space and the expiration fiber are set up on first use, see [`TarantoolBackend::init`].
+///
 /// # Examples
 /// ```
-/// use hitbox_tarantool::Tarantool;
+/// use hitbox_tarantool::TarantoolBackend;
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     let mut backend = Tarantool::builder().build();
-///     // backend.init().await.unwrap();
+///     let backend = TarantoolBackend::builder().port(3301).build();
 /// }
 /// ```
-#[derive(Clone, TypedBuilder)]
-#[builder(build_method(vis="", name=__build))]
-pub struct Tarantool {
-    #[builder(default = "hitbox".to_string())]
+#[derive(Clone)]
+pub struct TarantoolBackend<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    client: Arc<Client>,
+    initialized: Arc<OnceCell<()>>,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
+}
+
+impl TarantoolBackend<JsonFormat, PassthroughCompressor> {
+    /// Creates new TarantoolBackend builder with default settings.
+    pub fn builder() -> TarantoolBackendBuilder<JsonFormat, PassthroughCompressor> {
+        TarantoolBackendBuilder::default()
+    }
+}
+
+impl<S, C> TarantoolBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    /// Init backend and configure tarantool instance.
+    ///
+    /// Creates the cache space with its indexes, starts the expiration fiber and
+    /// registers the `hitbox` Lua functions. This function is idempotent and is
+    /// called automatically before the first operation.
+    pub async fn init(&self) -> BackendResult<()> {
+        self.initialized
+            .get_or_try_init(|| async {
+                self.client
+                    .eval(TARANTOOL_INIT_LUA, &(SPACE_NAME,))
+                    .await
+                    .map(|_| ())
+                    .map_err(Error::from)
+            })
+            .await?;
+        Ok(())
+    }
+
+    fn storage_key(&self, key: &CacheKey) -> BackendResult<ByteBuf> {
+        Ok(ByteBuf::from(self.key_format.serialize(key)?))
+    }
+}
+
+impl<S, C> std::fmt::Debug for TarantoolBackend<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TarantoolBackend")
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Part of builder pattern implementation for TarantoolBackend.
+pub struct TarantoolBackendBuilder<S = JsonFormat, C = PassthroughCompressor>
+where
+    S: Format,
+    C: Compressor,
+{
+    host: String,
+    port: u16,
     user: String,
-    #[builder(default = "hitbox".to_string())]
     password: String,
-    #[builder(default = "127.0.0.1".to_string())]
-    host: String,
-    #[builder(default = "3301".to_string())]
-    port: String,
+    key_format: CacheKeyFormat,
+    serializer: S,
+    compressor: C,
 }
 
-pub struct TarantoolBackend {
-    client: Client,
+impl Default for TarantoolBackendBuilder<JsonFormat, PassthroughCompressor> {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_owned(),
+            port: 3301,
+            user: "hitbox".to_owned(),
+            password: "hitbox".to_owned(),
+            key_format: CacheKeyFormat::default(),
+            serializer: JsonFormat,
+            compressor: PassthroughCompressor,
+        }
+    }
 }
 
-#[allow(non_camel_case_types)]
-impl<
-        __user: ::typed_builder::Optional<String>,
-        __password: ::typed_builder::Optional<String>,
-        __host: ::typed_builder::Optional<String>,
-        __port: ::typed_builder::Optional<String>,
-    > TarantoolBuilder<(__user, __password, __host, __port)>
+impl<S, C> TarantoolBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
 {
-    pub fn build(self) -> TarantoolBackend {
-        let t = self.__build();
-        let client =
-            ClientConfig::new(format!("{}:{}", t.host, t.port), t.user, t.password).build();
-        TarantoolBackend { client }
+    /// Set Tarantool host.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
     }
-}
 
-impl TarantoolBackend {
-    /// Init backend and configure tarantool instance
-    /// This function is idempotent
-    pub async fn init(&mut self) -> BackendResult<()> {
-        self.client
-            .eval(TARANTOOL_INIT_LUA, &("hitbox_cache",))
-            .await
-            .map_err(|err| BackendError::InternalError(Box::new(err)))?;
+    /// Set Tarantool port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
 
-        Ok(())
+    /// Set Tarantool user.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = user.into();
+        self
     }
 
-    fn map_err(err: Error) -> BackendError {
-        BackendError::InternalError(Box::new(err))
+    /// Set password of the Tarantool user.
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Set value serialization format (JSON, Bincode, etc.)
+    pub fn value_format<NewS>(self, serializer: NewS) -> TarantoolBackendBuilder<NewS, C>
+    where
+        NewS: Format,
+    {
+        TarantoolBackendBuilder {
+            host: self.host,
+            port: self.port,
+            user: self.user,
+            password: self.password,
+            key_format: self.key_format,
+            serializer,
+            compressor: self.compressor,
+        }
+    }
+
+    /// Set key serialization format (String, JSON, Bincode, UrlEncoded)
+    pub fn key_format(mut self, key_format: CacheKeyFormat) -> Self {
+        self.key_format = key_format;
+        self
+    }
+
+    /// Set compressor for value compression
+    pub fn compressor<NewC>(self, compressor: NewC) -> TarantoolBackendBuilder<S, NewC>
+    where
+        NewC: Compressor,
+    {
+        TarantoolBackendBuilder {
+            host: self.host,
+            port: self.port,
+            user: self.user,
+            password: self.password,
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor,
+        }
+    }
+
+    /// Create new instance of Tarantool backend with passed settings.
+    ///
+    /// Connection is established lazily by the client.
+    pub fn build(self) -> TarantoolBackend<S, C> {
+        let client = ClientConfig::new(
+            format!("{}:{}", self.host, self.port),
+            self.user,
+            self.password,
+        )
+        .build();
+        TarantoolBackend {
+            client: Arc::new(client),
+            initialized: Arc::default(),
+            key_format: self.key_format,
+            serializer: self.serializer,
+            compressor: self.compressor,
+        }
     }
 }
 
-#[doc(hidden)]
-#[derive(Serialize, Deserialize)]
-pub struct CacheEntry<T> {
-    pub key: String,
-    pub ttl: Option<u32>,
-    pub value: SerializableCachedValue<T>,
+impl<S, C> std::fmt::Debug for TarantoolBackendBuilder<S, C>
+where
+    S: Format,
+    C: Compressor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TarantoolBackendBuilder")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("key_format", &self.key_format)
+            .field("serializer", &std::any::type_name::<S>())
+            .field("compressor", &std::any::type_name::<C>())
+            .finish_non_exhaustive()
+    }
 }
 
 #[async_trait]
-impl CacheBackend for TarantoolBackend {
-    async fn get<T>(&self, key: &CacheKey) -> BackendResult<Option<CachedValue<T::Cached>>>
-    where
-        T: CacheableResponse,
-        <T as CacheableResponse>::Cached: serde::de::DeserializeOwned,
-    {
-        self.client
+impl<S, C> Backend for TarantoolBackend<S, C>
+where
+    S: Format + Send + Sync,
+    C: Compressor + Send + Sync,
+{
+    async fn read(&self, key: &CacheKey) -> BackendResult<Option<CacheValue<Raw>>> {
+        self.init().await?;
+        let entry = self
+            .client
             .prepare_fn_call("hitbox.get")
-            .bind_ref(&(key.serialize()))
-            .map_err(TarantoolBackend::map_err)?
+            .bind_ref(&(self.storage_key(key)?,))
+            .map_err(Error::from)?
+            .execute()
+            .await
+            .map_err(Error::from)?
+            .decode_single::<Option<CacheEntry>>()
+            .map_err(Error::from)?;
+        Ok(entry.map(CacheValue::from))
+    }
+
+    async fn write(
+        &self,
+        key: &CacheKey,
+        value: CacheValue<Raw>,
+        ttl: Option<Duration>,
+    ) -> BackendResult<()> {
+        self.init().await?;
+
+        let Some(ttl) = entry_ttl(ttl, value.expire, Utc::now()) else {
+            return Ok(());
+        };
+
+        let params = (
+            self.storage_key(key)?,
+            ttl.map(|ttl| ttl.as_secs_f64()),
+            ByteBuf::from(value.data),
+            value.expire.map(|expire| expire.timestamp_millis()),
+            value.stale.map(|stale| stale.timestamp_millis()),
+        );
+        self.client
+            .prepare_fn_call("hitbox.set")
+            .bind_ref(&params)
+            .map_err(Error::from)?
             .execute()
             .await
-            .map_err(TarantoolBackend::map_err)?
-            .decode_single::<Option<CacheEntry<T::Cached>>>()
-            .map_err(TarantoolBackend::map_err)
-            .map(|v| v.map(|v| v.value.into_cached_value()))
+            .map_err(Error::from)?;
+        Ok(())
     }
 
-    async fn delete(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
-        let result: bool = self
+    async fn remove(&self, key: &CacheKey) -> BackendResult<DeleteStatus> {
+        self.init().await?;
+        let deleted: bool = self
             .client
             .prepare_fn_call("hitbox.delete")
-            .bind_ref(&(key.serialize()))
-            .map_err(TarantoolBackend::map_err)?
+            .bind_ref(&(self.storage_key(key)?,))
+            .map_err(Error::from)?
             .execute()
             .await
-            .map_err(TarantoolBackend::map_err)?
+            .map_err(Error::from)?
             .decode_single()
-            .map_err(TarantoolBackend::map_err)?;
-        match result {
+            .map_err(Error::from)?;
+        match deleted {
             true => Ok(DeleteStatus::Deleted(1)),
             false => Ok(DeleteStatus::Missing),
         }
     }
 
-    async fn set<T>(
-        &self,
-        key: &CacheKey,
-        value: &CachedValue<T::Cached>,
-        ttl: Option<u32>,
-    ) -> BackendResult<()>
-    where
-        T: CacheableResponse + Send,
-        T::Cached: serde::Serialize + Send + Sync,
-    {
-        let entry: CacheEntry<T::Cached> = CacheEntry {
-            key: key.serialize(),
-            ttl,
-            value: value.clone().into(),
-        };
-        self.client
-            .prepare_fn_call("hitbox.set")
-            .bind_ref(&entry)
-            .map_err(TarantoolBackend::map_err)?
-            .execute()
-            .await
-            .map(|_| ())
-            .map_err(TarantoolBackend::map_err)
+    fn value_format(&self) -> &dyn Format {
+        &self.serializer
     }
 
-    async fn start(&self) -> BackendResult<()> {
-        Ok(())
+    fn key_format(&self) -> &CacheKeyFormat {
+        &self.key_format
+    }
+
+    fn compressor(&self) -> &dyn Compressor {
+        &self.compressor
     }
 }
@@ -0,0 +1,30 @@
+//! Error declaration and transformation into [BackendError].
+//!
+//! [BackendError]: hitbox_backend::BackendError
+use hitbox_backend::BackendError;
+
+/// Tarantool backend error declaration.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Wrapper for rusty_tarantool errors, which are reported as [`std::io::Error`].
+    #[error("Tarantool backend error: {0}")]
+    Tarantool(#[from] std::io::Error),
+}
+
+impl From<Error> for BackendError {
+    fn from(error: Error) -> Self {
+        use std::io::ErrorKind;
+
+        let Error::Tarantool(io) = &error;
+        match io.kind() {
+            ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+            | ErrorKind::UnexpectedEof => Self::ConnectionError(Box::new(error)),
+            _ => Self::InternalError(Box::new(error)),
+        }
+    }
+}
@@ -3,22 +3,41 @@ local log = require("log")
 
 local SCAN_INTERVAL = 0.1
 local MAX_TUPLES_FOR_DELETE = 1000
+-- expire_at of entries without ttl, far beyond any real timestamp
+local NO_EXPIRY = 2 ^ 53
 
 box.cfg({})
 
 local space_name = ...
 
+-- tuple format: {key, expire_at, data, expire, stale}
 box.schema.space.create(space_name, { if_not_exists = true })
 box.space[space_name]:create_index("primary", {
 	type = "HASH",
-	parts = { { 1, "string" } },
+	parts = { { 1, "varbinary" } },
 	if_not_exists = true,
 })
 box.space[space_name]:create_index("by_ttl", {
-	parts = { { 2, "integer" } },
+	parts = { { 2, "number" } },
+	unique = false,
 	if_not_exists = true,
 })
 
+-- Spaces created by earlier versions key entries by string and index expiration
+-- as unique integers, which varbinary keys and fractional times don't fit. Cache
+-- entries are disposable, so such a space is emptied and its indexes redefined.
+local space = box.space[space_name]
+if
+	space.index.primary.parts[1].type ~= "varbinary"
+	or space.index.by_ttl.parts[1].type ~= "number"
+	or space.index.by_ttl.unique
+then
+	log.info("Upgrading hitbox cache space %s, dropping cached entries", space_name)
+	space:truncate()
+	space.index.primary:alter({ parts = { { 1, "varbinary" } } })
+	space.index.by_ttl:alter({ parts = { { 2, "number" } }, unique = false })
+end
+
 if not _G.__hitbox_cache_fiber then
 	_G.__hitbox_cache_fiber = fiber.create(function()
 		fiber.name("hitbox_cache_fiber")
@@ -27,7 +46,7 @@ if not _G.__hitbox_cache_fiber then
 
 			local ok, err = pcall(function()
 				local for_del = box.space[space_name].index.by_ttl
-					:pairs({ math.floor(fiber.time()) }, { iterator = "LE" })
+					:pairs({ fiber.time() }, { iterator = "LE" })
 					:take(MAX_TUPLES_FOR_DELETE)
 					:totable()
 
@@ -52,17 +71,26 @@ end
 
 -- lua api for hitbox
 _G.hitbox = {
-	---Get cache entry by key
+	---Get cache entry by key, entries waiting for the expiration fiber are skipped
 	---@param key string
 	---@return table?
 	get = function(key)
-		return box.space[space_name]:get(key)
+		local entry = box.space[space_name]:get(key)
+		if entry == nil or entry[2] <= fiber.time() then
+			return nil
+		end
+		return entry
 	end,
 	---Insert cache entry
-	---@param entry table {key: string, ttl: number, value: any}
+	---@param key string
+	---@param ttl number? seconds to keep the entry, forever if nil
+	---@param data string
+	---@param expire number? value expiration time, milliseconds since epoch
+	---@param stale number? value staleness time, milliseconds since epoch
 	---@return table saved entry
-	set = function(entry)
-		return box.space[space_name]:replace(entry)
+	set = function(key, ttl, data, expire, stale)
+		local expire_at = ttl ~= nil and ttl ~= box.NULL and fiber.time() + ttl or NO_EXPIRY
+		return box.space[space_name]:replace({ key, expire_at, data, expire or box.NULL, stale or box.NULL })
 	end,
 	---Delete cache entry
 	---@param key string
@@ -1,6 +1,13 @@
 //! hitbox [Backend] implementation for Tarantool.
+//!
+//! Values are stored in a Tarantool space created by [`TarantoolBackend::init`],
+//! expired entries are removed by a fiber running inside the Tarantool instance.
+//!
 //! [Backend]: hitbox_backend::Backend
 pub mod backend;
+pub mod error;
 
 #[doc(inline)]
-pub use crate::backend::Tarantool;
+pub use crate::backend::{TarantoolBackend, TarantoolBackendBuilder};
+#[doc(inline)]
+pub use crate::error::Error;
@@ -1,52 +1,36 @@
-use async_trait::async_trait;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
-use hitbox_backend::{CacheBackend, DeleteStatus};
-use hitbox_core::{CacheKey, CachePolicy, CacheableResponse, CachedValue, PredicateResult};
-use hitbox_tarantool::{backend::CacheEntry, backend::TarantoolBackend, Tarantool};
-use once_cell::sync::Lazy;
+use hitbox_backend::{Backend, DeleteStatus};
+use hitbox_core::{CacheKey, CacheValue};
+use hitbox_tarantool::{TarantoolBackend, backend::SPACE_NAME};
 use rusty_tarantool::tarantool::{Client, ClientConfig, ExecWithParamaters};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr, thread, time::Duration};
-use testcontainers::{clients, core::WaitFor, Container, Image};
-
-static DOCKER: Lazy<clients::Cli> = Lazy::new(clients::Cli::default);
-
-impl Image for TarantoolImage {
-    type Args = ();
-
-    fn name(&self) -> String {
-        "tarantool/tarantool".to_owned()
-    }
-
-    fn tag(&self) -> String {
-        "latest".to_owned()
-    }
-
-    fn ready_conditions(&self) -> Vec<WaitFor> {
-        vec![WaitFor::Healthcheck]
-    }
-
-    fn expose_ports(&self) -> Vec<u16> {
-        vec![3301]
-    }
-
-    fn env_vars(&self) -> Box<dyn Iterator<Item = (&String, &String)> + '_> {
-        Box::new(self.env_vars.iter())
-    }
-}
-
-#[derive(Debug)]
-struct TarantoolImage {
-    env_vars: HashMap<String, String>,
+use testcontainers::{
+    ContainerAsync, GenericImage, ImageExt,
+    core::{ContainerPort, WaitFor},
+    runners::AsyncRunner,
+};
+
+struct TarantoolContainer {
+    _container: ContainerAsync<GenericImage>,
+    client: Client,
+    backend: TarantoolBackend,
 }
 
-impl<'a> TarantoolImage {
-    async fn start() -> TarantoolContainer<'a> {
-        let container = DOCKER.run(TarantoolImage::default());
-        let port = &container.ports().map_to_host_port_ipv4(3301).unwrap();
-        let client =
-            ClientConfig::new(format!("{}:{}", "127.0.0.1", &port), "hitbox", "hitbox").build();
-        let mut backend = Tarantool::builder().port(port.to_string()).build();
+impl TarantoolContainer {
+    async fn start() -> Self {
+        let container = GenericImage::new("tarantool/tarantool", "2.11")
+            .with_exposed_port(ContainerPort::Tcp(3301))
+            .with_wait_for(WaitFor::healthcheck())
+            .with_env_var("TARANTOOL_USER_NAME", "hitbox")
+            .with_env_var("TARANTOOL_USER_PASSWORD", "hitbox")
+            .start()
+            .await
+            .unwrap();
+        let port = container.get_host_port_ipv4(3301).await.unwrap();
+        let client = ClientConfig::new(format!("127.0.0.1:{port}"), "hitbox", "hitbox").build();
+        let backend = TarantoolBackend::builder().port(port).build();
         backend.init().await.unwrap();
         TarantoolContainer {
             _container: container,
@@ -54,19 +38,11 @@ impl<'a> TarantoolImage {
             backend,
         }
     }
-}
-
-struct TarantoolContainer<'a> {
-    _container: Container<'a, TarantoolImage>,
-    client: Client,
-    backend: TarantoolBackend,
-}
 
-impl<'a> TarantoolContainer<'a> {
     async fn eval<T, R>(&self, cmd: &str, params: &T) -> R
     where
         T: Serialize,
-        R: Deserialize<'a>,
+        R: for<'de> Deserialize<'de>,
     {
         self.client
             .eval(cmd, params)
@@ -75,69 +51,32 @@ impl<'a> TarantoolContainer<'a> {
             .decode()
             .unwrap()
     }
-}
-
-impl Default for TarantoolImage {
-    fn default() -> Self {
-        let mut env_vars = HashMap::new();
-        env_vars.insert("TARANTOOL_USER_NAME".to_owned(), "hitbox".to_owned());
-        env_vars.insert("TARANTOOL_USER_PASSWORD".to_owned(), "hitbox".to_owned());
-
-        Self { env_vars }
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
-struct Test {
-    a: i32,
-    b: String,
-}
 
-#[async_trait]
-impl CacheableResponse for Test {
-    type Cached = Self;
-    type Subject = Self;
-
-    async fn cache_policy<P>(self, predicates: P) -> hitbox_core::ResponseCachePolicy<Self>
-    where
-        P: hitbox_core::Predicate<Subject = Self::Subject> + Send + Sync,
-    {
-        match predicates.check(self).await {
-            PredicateResult::Cacheable(cacheable) => match cacheable.into_cached().await {
-                CachePolicy::Cacheable(res) => {
-                    CachePolicy::Cacheable(CachedValue::new(res, Utc::now()))
-                }
-                CachePolicy::NonCacheable(res) => CachePolicy::NonCacheable(res),
-            },
-            PredicateResult::NonCacheable(res) => CachePolicy::NonCacheable(res),
-        }
-    }
-    async fn into_cached(self) -> CachePolicy<Self::Cached, Self> {
-        CachePolicy::Cacheable(self)
-    }
-    async fn from_cached(cached: Self::Cached) -> Self {
-        cached
+    async fn stored(&self, key: &CacheKey) -> usize {
+        let key = serde_bytes::ByteBuf::from(self.backend.key_format().serialize(key).unwrap());
+        let count: (usize,) = self
+            .eval(
+                "return box.space[...]:count(select(2, ...))",
+                &(SPACE_NAME, key),
+            )
+            .await;
+        count.0
     }
 }
 
-impl Default for Test {
-    fn default() -> Self {
-        Self {
-            a: 42,
-            b: "nope".to_owned(),
-        }
-    }
+fn value(expire: Option<DateTime<Utc>>) -> CacheValue<Vec<u8>> {
+    let stale = expire.map(|expire| expire - chrono::Duration::seconds(10));
+    CacheValue::new(b"value".to_vec(), expire, stale)
 }
 
 #[tokio::test]
 async fn test_init() {
-    let t = TarantoolImage::start().await;
+    let t = TarantoolContainer::start().await;
+    // repeated init is a no-op
+    t.backend.init().await.unwrap();
 
     let space_exists: (bool,) = t
-        .eval(
-            "return box.space[...] and true or false",
-            &("hitbox_cache",),
-        )
+        .eval("return box.space[...] and true or false", &(SPACE_NAME,))
         .await;
     assert!(space_exists.0);
 
@@ -151,122 +90,139 @@ async fn test_init() {
 }
 
 #[tokio::test]
-async fn test_set() {
-    let t = TarantoolImage::start().await;
-    let key = CacheKey::from_str("test_key", "1");
-    let dt = "2012-12-12T12:12:12Z";
-    let ttl = 42;
-    let value = CachedValue::new(Test::default(), DateTime::from_str(dt).unwrap());
+async fn test_init_upgrades_old_schema() {
+    let t = TarantoolContainer::start().await;
+    let _: (bool,) = t
+        .eval(
+            r#"
+            local name = ...
+            box.space[name]:drop()
+            box.schema.space.create(name)
+            box.space[name]:create_index("primary", { type = "HASH", parts = { { 1, "string" } } })
+            box.space[name]:create_index("by_ttl", { parts = { { 2, "integer" } } })
+            box.space[name]:insert({ "old", 2000000000, "data" })
+            return true
+            "#,
+            &(SPACE_NAME,),
+        )
+        .await;
+
+    t.backend.init().await.unwrap();
 
+    let key_type: (String,) = t
+        .eval(
+            "return box.space[...].index.primary.parts[1].type",
+            &(SPACE_NAME,),
+        )
+        .await;
+    assert_eq!(key_type.0, "varbinary");
+    let key = CacheKey::from_str("test_key", "1");
     t.backend
-        .set::<Test>(&key, &value, Some(ttl))
+        .write(&key, value(None), Some(Duration::from_secs(42)))
         .await
         .unwrap();
+    assert_eq!(t.backend.read(&key).await.unwrap().unwrap().data, b"value");
+    let count: (usize,) = t
+        .eval("return box.space[...]:count()", &(SPACE_NAME,))
+        .await;
+    assert_eq!(count.0, 1);
+}
 
-    let result = t
-        .client
-        .prepare_fn_call("box.space.hitbox_cache:get")
-        .bind_ref(&key.serialize())
-        .unwrap()
-        .execute()
+#[tokio::test]
+async fn test_write_read() {
+    let t = TarantoolContainer::start().await;
+    let key = CacheKey::from_str("test_key", "1");
+    let expire = DateTime::from_timestamp_millis(Utc::now().timestamp_millis() + 60_000);
+    let value = value(expire);
+
+    t.backend
+        .write(&key, value.clone(), Some(Duration::from_secs(42)))
         .await
-        .unwrap()
-        .decode_single::<CacheEntry<Test>>()
         .unwrap();
 
-    assert_eq!(&result.ttl.unwrap(), &ttl);
-    assert_eq!(&result.value.into_cached_value().data, &Test::default());
+    let result = t.backend.read(&key).await.unwrap().unwrap();
+    assert_eq!(result.data, value.data);
+    assert_eq!(result.expire, value.expire);
+    assert_eq!(result.stale, value.stale);
 }
 
 #[tokio::test]
 async fn test_expire() {
-    let t = TarantoolImage::start().await;
+    let t = TarantoolContainer::start().await;
     let key = CacheKey::from_str("test_key", "1");
-    let dt = "2012-12-12T12:12:12Z";
-    let value = CachedValue::new(Test::default(), DateTime::from_str(dt).unwrap());
-
-    t.backend.set::<Test>(&key, &value, Some(0)).await.unwrap();
 
-    thread::sleep(Duration::from_secs(1));
-
-    let result = t
-        .client
-        .prepare_fn_call("box.space.hitbox_cache:get")
-        .bind_ref(&key.serialize())
-        .unwrap()
-        .execute()
+    t.backend
+        .write(&key, value(None), Some(Duration::from_millis(100)))
         .await
-        .unwrap()
-        .decode_result_set::<CacheEntry<Test>>()
         .unwrap();
-    assert!(result.is_empty())
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    assert!(t.backend.read(&key).await.unwrap().is_none());
+    assert_eq!(t.stored(&key).await, 0);
 }
 
 #[tokio::test]
-async fn test_delete() {
-    let t = TarantoolImage::start().await;
+async fn test_write_without_ttl_uses_value_expire() {
+    let t = TarantoolContainer::start().await;
     let key = CacheKey::from_str("test_key", "1");
-    let dt: DateTime<Utc> = DateTime::from_str("2012-12-12T12:12:12Z").unwrap();
-    let value = Test::default();
-    let cached_value = CachedValue::new(&value, dt);
-    let entry = CacheEntry {
-        key: key.serialize(),
-        ttl: Some(42),
-        value: cached_value.into(),
-    };
-
-    let status = t.backend.delete(&key).await.unwrap();
-    assert_eq!(status, DeleteStatus::Missing);
+    let expired = CacheKey::from_str("test_key", "2");
 
-    t.client
-        .prepare_fn_call("box.space.hitbox_cache:replace")
-        .bind_ref(&entry)
-        .unwrap()
-        .execute()
+    t.backend
+        .write(
+            &key,
+            value(Some(Utc::now() + chrono::Duration::milliseconds(200))),
+            None,
+        )
         .await
         .unwrap();
-
-    let status = t.backend.delete(&key).await.unwrap();
-    assert_eq!(status, DeleteStatus::Deleted(1));
-
-    let result = t
-        .client
-        .prepare_fn_call("box.space.hitbox_cache:get")
-        .bind_ref(&key.serialize())
-        .unwrap()
-        .execute()
+    t.backend
+        .write(
+            &expired,
+            value(Some(Utc::now() - chrono::Duration::seconds(1))),
+            None,
+        )
         .await
-        .unwrap()
-        .decode_result_set::<CacheEntry<Test>>()
         .unwrap();
 
-    assert!(result.is_empty())
+    assert!(t.backend.read(&key).await.unwrap().is_some());
+    assert_eq!(t.stored(&expired).await, 0);
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    assert!(t.backend.read(&key).await.unwrap().is_none());
 }
 
 #[tokio::test]
-async fn test_get() {
-    let t = TarantoolImage::start().await;
+async fn test_remove() {
+    let t = TarantoolContainer::start().await;
     let key = CacheKey::from_str("test_key", "1");
-    let dt: DateTime<Utc> = DateTime::from_str("2012-12-12T12:12:12Z").unwrap();
 
-    let value = Test::default();
-    let cached_value = CachedValue::new(&value, dt);
-    let entry = CacheEntry {
-        key: key.serialize(),
-        ttl: Some(42),
-        value: cached_value.into(),
-    };
+    let status = t.backend.remove(&key).await.unwrap();
+    assert_eq!(status, DeleteStatus::Missing);
+
+    t.backend.write(&key, value(None), None).await.unwrap();
+
+    let status = t.backend.remove(&key).await.unwrap();
+    assert_eq!(status, DeleteStatus::Deleted(1));
+    assert!(t.backend.read(&key).await.unwrap().is_none());
+}
 
-    t.client
-        .prepare_fn_call("box.space.hitbox_cache:replace")
-        .bind_ref(&entry)
+#[tokio::test]
+async fn test_get_missing() {
+    let t = TarantoolContainer::start().await;
+    let key = serde_bytes::ByteBuf::from(b"missing".to_vec());
+
+    let entry = t
+        .client
+        .prepare_fn_call("hitbox.get")
+        .bind_ref(&(key,))
         .unwrap()
         .execute()
         .await
+        .unwrap()
+        .decode_single::<Option<hitbox_tarantool::backend::CacheEntry>>()
         .unwrap();
 
-    let data = t.backend.get::<Test>(&key).await.unwrap().unwrap();
-
-    assert_eq!(data.data, value);
-    assert_eq!(data.expired, dt);
+    assert!(entry.is_none());
 }
@@ -118,8 +118,8 @@ async fn test_write_and_read_with_metadata<B: CacheBackend>(backend: &B) {
     assert!(result.is_some(), "value should exist");
     let cached_value = result.unwrap();
     assert_eq!(cached_value.data, response, "data should match");
-    assert!(cached_value.expire.is_some(), "expire should be set");
-    assert!(cached_value.stale.is_some(), "stale should be set");
+    assert_eq!(cached_value.expire, expire, "expire should be preserved");
+    assert_eq!(cached_value.stale, stale, "stale should be preserved");
 }
 
 async fn test_delete_existing<B: CacheBackend>(backend: &B) {
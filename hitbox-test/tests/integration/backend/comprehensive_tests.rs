@@ -1,7 +1,8 @@
+use hitbox_backend::testing::HashMapBackend;
 use hitbox_backend::{Backend, CacheBackend};
 use hitbox_configuration::backend::{
-    BackendConfig, Compression, FeOxDb, KeyFormat, KeySerialization, Moka, Redis, ValueFormat,
-    ValueSerialization,
+    BackendConfig, Compression, FeOxDb, FeOxDbSync, KeyFormat, KeySerialization, Moka, Redis,
+    RedisConnection, RedisHashTag, RedisOptions, ValueFormat, ValueSerialization,
 };
 use hitbox_feoxdb::FeOxDbBackend;
 use hitbox_moka::MokaBackend;
@@ -20,7 +21,14 @@ static TEST_CONFIGS: LazyLock<Vec<(KeySerialization, ValueSerialization, Compres
         let mut configs = Vec::new();
 
         let key_formats = [KeySerialization::UrlEncoded, KeySerialization::Bitcode];
-        let value_formats = [ValueSerialization::Json, ValueSerialization::Bincode];
+        #[allow(unused_mut)]
+        let mut value_formats = vec![ValueSerialization::Json, ValueSerialization::Bincode];
+        #[cfg(feature = "msgpack")]
+        value_formats.push(ValueSerialization::MessagePack);
+        #[cfg(feature = "cbor")]
+        value_formats.push(ValueSerialization::Cbor);
+        #[cfg(feature = "postcard")]
+        value_formats.push(ValueSerialization::Postcard);
 
         for key_format in &key_formats {
             for value_format in &value_formats {
@@ -46,6 +54,42 @@ static TEST_CONFIGS: LazyLock<Vec<(KeySerialization, ValueSerialization, Compres
                     value_format.clone(),
                     Compression::Zstd { level: 3 },
                 ));
+
+                // Lz4 compression
+                #[cfg(feature = "lz4")]
+                configs.push((
+                    key_format.clone(),
+                    value_format.clone(),
+                    Compression::Lz4 { level: 0 },
+                ));
+
+                // Brotli compression
+                #[cfg(feature = "brotli")]
+                configs.push((
+                    key_format.clone(),
+                    value_format.clone(),
+                    Compression::Brotli { level: 9 },
+                ));
+
+                // Snappy compression
+                #[cfg(feature = "snappy")]
+                configs.push((
+                    key_format.clone(),
+                    value_format.clone(),
+                    Compression::Snappy,
+                ));
+
+                // Adaptive zstd compression
+                #[cfg(feature = "zstd")]
+                configs.push((
+                    key_format.clone(),
+                    value_format.clone(),
+                    Compression::Adaptive {
+                        threshold: 256,
+                        codec: Box::new(Compression::Zstd { level: 3 }),
+                        fallback: None,
+                    },
+                ));
             }
         }
 
@@ -69,15 +113,13 @@ async fn run_comprehensive_backend_tests<B>(
         (KeySerialization::UrlEncoded, ValueSerialization::Json) => {
             test_url_encoded_key_json_value(backend).await
         }
-        (KeySerialization::UrlEncoded, ValueSerialization::Bincode) => {
-            test_url_encoded_key_bincode_value(backend).await
-        }
+        // Binary formats
+        (KeySerialization::UrlEncoded, _) => test_url_encoded_key_bincode_value(backend).await,
         (KeySerialization::Bitcode, ValueSerialization::Json) => {
             test_bitcode_key_json_value(backend).await
         }
-        (KeySerialization::Bitcode, ValueSerialization::Bincode) => {
-            test_bitcode_key_bincode_value(backend).await
-        }
+        // Binary formats
+        (KeySerialization::Bitcode, _) => test_bitcode_key_bincode_value(backend).await,
     }
 
     // Run compression verification test if compression is enabled
@@ -85,7 +127,12 @@ async fn run_comprehensive_backend_tests<B>(
         Compression::Disabled => {
             // No compression to test
         }
-        Compression::Gzip { .. } | Compression::Zstd { .. } => {
+        Compression::Gzip { .. }
+        | Compression::Zstd { .. }
+        | Compression::Lz4 { .. }
+        | Compression::Brotli { .. }
+        | Compression::Snappy
+        | Compression::Adaptive { .. } => {
             test_compression_is_used(backend).await;
         }
     }
@@ -103,8 +150,15 @@ async fn test_moka_all_combinations() {
             value: ValueFormat {
                 format: value_format.clone(),
                 compression: compression.clone(),
+                envelope: None,
+                integrity: None,
+                encryption: None,
+            },
+            backend: Moka {
+                max_capacity: 1000,
+                max_bytes: None,
+                time_to_idle_ms: None,
             },
-            backend: Moka { max_capacity: 1000 },
         };
 
         // Skip configurations with unavailable compression features
@@ -115,7 +169,38 @@ async fn test_moka_all_combinations() {
 
         let backend = MokaBackend::builder(config.backend.max_capacity)
             .key_format(config.key.format.to_cache_key_format())
-            .value_format(config.value.format.to_serializer())
+            .value_format(
+                config
+                    .value
+                    .format
+                    .to_serializer()
+                    .expect("failed to create value format"),
+            )
+            .compressor(compressor)
+            .build();
+
+        run_comprehensive_backend_tests(&backend, key_format, value_format, compression).await;
+    }
+}
+
+// ==================== HashMap Backend Tests ====================
+
+#[tokio::test]
+async fn test_hashmap_all_combinations() {
+    for (key_format, value_format, compression) in TEST_CONFIGS.iter() {
+        // Skip configurations with unavailable compression features
+        let compressor = match compression.to_compressor() {
+            Ok(c) => c,
+            Err(_) => continue, // Skip this combination if compression feature not available
+        };
+
+        let backend = HashMapBackend::builder()
+            .key_format(key_format.to_cache_key_format())
+            .value_format(
+                value_format
+                    .to_serializer()
+                    .expect("failed to create value format"),
+            )
             .compressor(compressor)
             .build();
 
@@ -135,8 +220,17 @@ async fn test_feoxdb_all_combinations() {
             value: ValueFormat {
                 format: value_format.clone(),
                 compression: compression.clone(),
+                envelope: None,
+                integrity: None,
+                encryption: None,
+            },
+            backend: FeOxDb {
+                path: None,
+                max_memory: None,
+                file_size: None,
+                sync: FeOxDbSync::Background,
+                sweep_interval_ms: None,
             },
-            backend: FeOxDb { path: None },
         };
 
         // Skip configurations with unavailable compression features
@@ -147,7 +241,13 @@ async fn test_feoxdb_all_combinations() {
 
         let backend = FeOxDbBackend::builder()
             .key_format(config.key.format.to_cache_key_format())
-            .value_format(config.value.format.to_serializer())
+            .value_format(
+                config
+                    .value
+                    .format
+                    .to_serializer()
+                    .expect("failed to create value format"),
+            )
             .compressor(compressor)
             .build()
             .expect("failed to create backend");
@@ -180,9 +280,16 @@ async fn test_redis_all_combinations() {
             value: ValueFormat {
                 format: value_format.clone(),
                 compression: compression.clone(),
+                envelope: None,
+                integrity: None,
+                encryption: None,
             },
             backend: Redis {
-                connection_string: connection_string.clone(),
+                connection: RedisConnection::Single {
+                    connection_string: connection_string.clone(),
+                },
+                options: RedisOptions::default(),
+                hash_tag: RedisHashTag::Disabled,
             },
         };
 
@@ -193,9 +300,15 @@ async fn test_redis_all_combinations() {
         };
 
         let backend = RedisBackend::builder()
-            .server(config.backend.connection_string.clone())
+            .server(connection_string.clone())
             .key_format(config.key.format.to_cache_key_format())
-            .value_format(config.value.format.to_serializer())
+            .value_format(
+                config
+                    .value
+                    .format
+                    .to_serializer()
+                    .expect("failed to create value format"),
+            )
             .compressor(compressor)
             .build()
             .expect("failed to create backend");
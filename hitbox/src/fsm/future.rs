@@ -223,7 +223,7 @@ where
     T::Future: Future<Output = Res> + Send + 'static,
     B: CacheBackend + Send + Sync + 'static,
     Res: CacheableResponse,
-    Res::Cached: Serialize + DeserializeOwned + Send + Sync,
+    Res::Cached: Serialize + DeserializeOwned + Send + Sync + 'static,
     Req: CacheableRequest + Send + 'static,
     // Debug bounds
     Req: Debug,